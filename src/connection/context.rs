@@ -16,7 +16,9 @@ use std::any::TypeId;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
 use tokio::io::AsyncWrite;
+use tokio_util::sync::CancellationToken;
 
 use crate::connection::global::GlobalContext;
 use crate::http::meta::HttpMetadata;
@@ -108,6 +110,10 @@ pub type AexWriter = dyn AsyncWrite + Send + Sync + Unpin;
 pub type BoxReader = Box<AexReader>;
 pub type BoxWriter = Box<AexWriter>;
 
+/// `watch_disconnect` 启动后写入 `ctx.local`，流式写入器据此在对端断开后停止输出
+#[derive(Clone)]
+pub(crate) struct DisconnectWatch(pub(crate) CancellationToken);
+
 /// Per-request context containing connection info, I/O, and data storage.
 pub struct Context {
     pub addr: SocketAddr,
@@ -116,6 +122,8 @@ pub struct Context {
     pub writer: Option<BoxWriter>,
     pub global: Arc<GlobalContext>,
     pub local: LocalTypeMap,
+    /// 当前请求的取消信号：请求超时或对端断开时取消，每个请求开始时重置
+    pub cancel_token: CancellationToken,
 }
 
 impl Context {
@@ -132,12 +140,79 @@ impl Context {
             global,
             local: LocalTypeMap::new(),
             addr,
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    /// 请求是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_token.is_cancelled()
+    }
+
+    /// 在后台读取连接直到 EOF，对端关闭时取消 `cancel_token`，返回该 token。
+    ///
+    /// 用于 SSE / 分块流等长连接处理器：此后 `ChunkedWriter` / `SseStream`
+    /// 在对端断开后立即返回错误，不再继续产出数据。读端被移交给后台任务，
+    /// 请求体需在此之前读取，且连接不再复用（写入 `Connection: close`）
+    pub fn watch_disconnect(&mut self) -> CancellationToken {
+        let token = self.cancel_token.clone();
+        if self.local.get_ref::<DisconnectWatch>().is_some() {
+            return token;
+        }
+        self.local.set_value(DisconnectWatch(token.clone()));
+        if let Some(meta) = self.local.get_mut::<HttpMetadata>() {
+            meta.headers.insert(HeaderKey::Connection, "close");
+        }
+        if let Some(mut reader) = self.reader.take() {
+            let watch = token.clone();
+            tokio::spawn(async move {
+                let eof = async {
+                    // 丢弃对端后续发送的数据，直到 EOF 或出错
+                    loop {
+                        match reader.fill_buf().await {
+                            Ok(buf) if !buf.is_empty() => {
+                                let n = buf.len();
+                                reader.consume(n);
+                            }
+                            _ => break,
+                        }
+                    }
+                };
+                tokio::select! {
+                    _ = watch.cancelled() => {}
+                    _ = eof => watch.cancel(),
+                }
+            });
+        }
+        token
+    }
+
+    /// 等待请求被取消：超时、显式取消，或对端关闭连接（读到 EOF）。
+    /// 适合在耗时的下游调用中配合 `tokio::select!` 提前退出
+    pub async fn cancelled(&mut self) {
+        let token = self.cancel_token.clone();
+        let Some(reader) = self.reader.as_mut() else {
+            return token.cancelled().await;
+        };
+        let eof = async {
+            // 已缓冲的数据（如流水线上的下一个请求）不会被消费，此时只能等待 token
+            match reader.fill_buf().await {
+                Ok(buf) if !buf.is_empty() => std::future::pending().await,
+                _ => {}
+            }
+        };
+        tokio::select! {
+            _ = token.cancelled() => {}
+            _ = eof => token.cancel(),
         }
     }
 
     /// 获取 Request 视图
     pub fn req(&mut self) -> Request<'_> {
         Request::new(&mut self.reader, &mut self.local)
+            .with_writer(&mut self.writer)
+            .with_peer_addr(self.addr)
+            .with_max_body_size(self.global.max_body_size)
     }
 
     /// 获取 Response 视图
@@ -191,9 +266,7 @@ impl Context {
     /// Redirect to another URL (302 Found).
     pub fn redirect(&mut self, location: &str) {
         if let Some(meta) = self.local.get_mut::<HttpMetadata>() {
-            meta.status = StatusCode::Found;
-            meta.headers
-                .insert(HeaderKey::Location, location.to_string());
+            meta.redirect_temporary(location);
             meta.body = Vec::new();
         }
     }
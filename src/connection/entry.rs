@@ -1,7 +1,8 @@
-use crate::connection::context::{BoxReader, BoxWriter, Context};
+use crate::connection::context::{BoxReader, BoxWriter, Context, TypeMapExt, get_tcp_router};
 use crate::connection::global::GlobalContext;
 use crate::connection::heartbeat::{HeartbeatConfig, HeartbeatManager};
 use crate::connection::node::Node;
+use crate::http::router::Router as HttpRouter;
 use crate::tcp::types::{TCPCommand, TCPFrame};
 use std::fmt;
 use std::future::Future;
@@ -158,9 +159,23 @@ impl ConnectionEntry {
         F: TCPFrame + Send + 'static,
         C: TCPCommand + Send + 'static,
     {
-        move |_ctx: Arc<Mutex<Context>>| {
-            Box::pin(async move { Ok(()) })
-                as Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>
+        move |ctx: Arc<Mutex<Context>>| {
+            Box::pin(async move {
+                let global = ctx.lock().await.global.clone();
+
+                // 同一端口复用：先窥探首包，HTTP（含 WebSocket 升级）交给 HTTP 路由
+                if let Some(http) = global.routers.get_value::<Arc<HttpRouter>>()
+                    && http.is_http(ctx.clone()).await?
+                {
+                    return Ok(());
+                }
+
+                // 其余按自定义二进制协议处理
+                if let Some(tcp) = get_tcp_router::<F, C>(&global.routers) {
+                    tcp.handle(ctx).await?;
+                }
+                Ok(())
+            }) as Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>
         }
     }
 }
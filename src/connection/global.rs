@@ -5,6 +5,7 @@ use std::{
     collections::HashMap,
     net::SocketAddr,
     sync::{Arc, OnceLock},
+    time::Duration,
 };
 
 use tokio::{
@@ -14,7 +15,10 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 
 use crate::connection::scope::NetworkScope;
-use crate::constants::server::SERVER_NAME;
+use crate::constants::{
+    http::{BODY_READ_TIMEOUT_MS, HEADER_READ_TIMEOUT_MS, IDLE_TIMEOUT_MS, MAX_BODY_SIZE},
+    server::SERVER_NAME,
+};
 use crate::{
     communicators::{
         event::{Event, EventCallback, EventEmitter},
@@ -40,6 +44,22 @@ pub struct GlobalContext {
     pub paired_session_keys: Option<Arc<Mutex<PairedSessionKey>>>,
     pub heartbeat_config: HeartbeatConfig,
     pub heartbeat_manager: Option<HeartbeatManager>,
+    /// 请求体允许的最大字节数，超出时返回 413
+    pub max_body_size: usize,
+    /// 读取请求体的超时时间，超时或提前断开时返回 400
+    pub body_read_timeout: Duration,
+    /// 收到首字节后读完请求行与头部的期限，超时返回 408 并关闭连接
+    pub header_read_timeout: Duration,
+    /// 新连接或 keep-alive 连接等待下一个请求首字节的期限，超时直接关闭连接、不回复
+    pub idle_timeout: Duration,
+    /// 处理耗时超过该值的 HTTP 请求会记录一条 warn 日志，None 表示不记录
+    pub slow_request_threshold: Option<Duration>,
+    /// 请求处理的期限，到期后取消 `Context::cancel_token`；None 表示不限制
+    pub request_timeout: Option<Duration>,
+    /// 响应的 Server 头，None 表示不发送
+    pub server_header: Option<String>,
+    /// 是否为每个响应补充 `SECURITY_HEADERS`，处理器已设置的不覆盖
+    pub security_headers: bool,
     pub extensions: Arc<RwLock<TypeMap>>,
     pub routers: TypeMap,
     pub h2_codec: OnceLock<Arc<crate::http2::H2Codec>>,
@@ -64,6 +84,14 @@ impl GlobalContext {
             paired_session_keys,
             heartbeat_config: HeartbeatConfig::new(),
             heartbeat_manager: None,
+            max_body_size: MAX_BODY_SIZE,
+            body_read_timeout: Duration::from_millis(BODY_READ_TIMEOUT_MS),
+            header_read_timeout: Duration::from_millis(HEADER_READ_TIMEOUT_MS),
+            idle_timeout: Duration::from_millis(IDLE_TIMEOUT_MS),
+            slow_request_threshold: None,
+            request_timeout: None,
+            server_header: None,
+            security_headers: false,
             extensions: Arc::new(RwLock::new(TypeMap::default())),
             routers: TypeMap::default(),
             h2_codec: OnceLock::new(),
@@ -76,6 +104,50 @@ impl GlobalContext {
         self
     }
 
+    pub fn with_max_body_size(mut self, size: usize) -> Self {
+        self.max_body_size = size;
+        self
+    }
+
+    pub fn with_body_read_timeout(mut self, timeout: Duration) -> Self {
+        self.body_read_timeout = timeout;
+        self
+    }
+
+    pub fn with_header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.header_read_timeout = timeout;
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// 开启慢请求告警
+    pub fn with_slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_request_threshold = Some(threshold);
+        self
+    }
+
+    /// 设置请求期限，到期后处理器可通过 `ctx.cancelled()` 感知
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// 为每个响应设置 Server 头
+    pub fn with_server_header(mut self, value: impl Into<String>) -> Self {
+        self.server_header = Some(value.into());
+        self
+    }
+
+    /// 开启或关闭默认安全 Header（nosniff、X-Frame-Options 等）
+    pub fn with_security_headers(mut self, enabled: bool) -> Self {
+        self.security_headers = enabled;
+        self
+    }
+
     pub fn init_heartbeat_manager(&mut self) {
         let local_node = futures::executor::block_on(self.local_node.read()).clone();
         self.heartbeat_manager =
@@ -12,6 +12,10 @@ pub mod http {
     pub const MAX_HEADER_COUNT: usize = 64;
     pub const MAX_COOKIE_COUNT: usize = 32;
     pub const MAX_FORM_BODY_SIZE: usize = 65536;
+    pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+    pub const BODY_READ_TIMEOUT_MS: u64 = 30_000;
+    pub const HEADER_READ_TIMEOUT_MS: u64 = 10_000;
+    pub const IDLE_TIMEOUT_MS: u64 = 60_000;
 
     pub const HTTP_VERSION: &str = "HTTP/1.1";
     pub const HEADER_DELIMITER: &str = "\r\n";
@@ -29,6 +33,13 @@ pub mod http {
     pub const ACCEPT_KEY: &str = "Accept";
     pub const ORIGIN_KEY: &str = "Origin";
 
+    /// `with_security_headers(true)` 时为每个响应补充的 Header
+    pub const SECURITY_HEADERS: &[(&str, &str)] = &[
+        ("X-Content-Type-Options", "nosniff"),
+        ("X-Frame-Options", "DENY"),
+        ("Referrer-Policy", "strict-origin-when-cross-origin"),
+    ];
+
     pub const STATUS_OK: u16 = 200;
     pub const STATUS_BAD_REQUEST: u16 = 400;
     pub const STATUS_NOT_FOUND: u16 = 404;
@@ -45,6 +56,9 @@ pub mod tcp {
     pub const HANDSHAKE_VERSION: u8 = 1;
     pub const DEFAULT_PING_INTERVAL_SEC: u64 = 30;
     pub const DEFAULT_PING_TIMEOUT_SEC: u64 = 10;
+    /// TCP keepalive：空闲多久后开始探测、探测间隔
+    pub const KEEPALIVE_IDLE_SEC: u64 = 60;
+    pub const KEEPALIVE_INTERVAL_SEC: u64 = 10;
 }
 
 pub mod udp {
@@ -0,0 +1,338 @@
+//! # Extractors
+//!
+//! Axum-style handler arguments built on `Context`.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use aex::handler;
+//! use aex::http::extract::{Json, Path, State};
+//!
+//! async fn update(Path(id): Path<u32>, Json(user): Json<User>, State(db): State<Db>) -> Json<User> {
+//!     Json(db.save(id, user).await)
+//! }
+//!
+//! router.put("/users/:id", handler!(update)).register();
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::{BoxFuture, FutureExt};
+use serde::{
+    Deserializer, Serialize,
+    de::{self, DeserializeOwned, IntoDeserializer, Unexpected, Visitor, value::MapDeserializer},
+    forward_to_deserialize_any,
+};
+
+use crate::connection::context::Context;
+use crate::http::{
+    meta::HttpMetadata,
+    protocol::{
+        content_type::ContentType,
+        header::HeaderKey,
+        media_type::{MediaType, SubMediaType},
+        status::StatusCode,
+    },
+    types::Executor,
+};
+
+/// 提取失败时写回的错误响应
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl Rejection {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BadRequest, message)
+    }
+
+    fn apply(self, ctx: &mut Context) {
+        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+            meta.error(self.status, self.message);
+        }
+    }
+}
+
+/// 从请求上下文中构造处理器参数
+pub trait FromContext: Sized {
+    fn from_context(ctx: &mut Context) -> BoxFuture<'_, Result<Self, Rejection>>;
+}
+
+/// 路径参数。只有一个参数时直接解析为 `T`（如 `Path<u32>`），
+/// 多个参数时按参数名反序列化为结构体
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<T>(pub T);
+
+/// 查询参数，按键名反序列化为 `T`；重复的键取第一个值
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<T>(pub T);
+
+/// JSON 请求体；作为返回值时序列化为 `application/json` 响应
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+/// 通过 `GlobalContext::set` 注册的共享状态，未注册时返回 500
+#[derive(Debug, Clone, PartialEq)]
+pub struct State<T>(pub T);
+
+/// 单个路径/查询值的反序列化器：按目标类型解析数字与布尔，其余按字符串处理，
+/// 这样同一个结构体里的 `u32` 与 `String` 字段都能从字符串值反序列化
+struct ParamValue<'a>(&'a str);
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.0.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ParamValue<'de> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct newtype_struct seq
+        tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de> for ParamValue<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// 单个值直接反序列化为 `T`，否则按键名反序列化为结构体/映射
+fn from_pairs<'a, T, I>(mut pairs: I, single: bool) -> Result<T, String>
+where
+    T: DeserializeOwned,
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
+    let result = if single {
+        let (_, value) = pairs.next().ok_or("missing value")?;
+        T::deserialize(ParamValue(value))
+    } else {
+        T::deserialize(MapDeserializer::new(pairs.map(|(k, v)| (k, ParamValue(v)))))
+    };
+    result.map_err(|e| e.to_string())
+}
+
+impl<T: DeserializeOwned + Send + 'static> FromContext for Path<T> {
+    fn from_context(ctx: &mut Context) -> BoxFuture<'_, Result<Self, Rejection>> {
+        let data = ctx
+            .local
+            .get_ref::<HttpMetadata>()
+            .and_then(|m| m.params.as_ref())
+            .and_then(|p| p.data.clone())
+            .unwrap_or_default();
+        let pairs = data.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+        let result = from_pairs(pairs, data.len() == 1)
+            .map(Path)
+            .map_err(|e| Rejection::bad_request(format!("Invalid path parameter: {}", e)));
+        async move { result }.boxed()
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static> FromContext for Query<T> {
+    fn from_context(ctx: &mut Context) -> BoxFuture<'_, Result<Self, Rejection>> {
+        let query = ctx
+            .local
+            .get_ref::<HttpMetadata>()
+            .and_then(|m| m.params.as_ref())
+            .map(|p| p.query.clone())
+            .unwrap_or_default();
+        let pairs = query
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_str(), v.first()?.as_str())));
+        let result = from_pairs(pairs, false)
+            .map(Query)
+            .map_err(|e| Rejection::bad_request(format!("Invalid query: {}", e)));
+        async move { result }.boxed()
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static> FromContext for Json<T> {
+    fn from_context(ctx: &mut Context) -> BoxFuture<'_, Result<Self, Rejection>> {
+        async move {
+            let body = ctx
+                .req()
+                .read_body()
+                .await
+                .map_err(|_| Rejection::bad_request("Bad Request"))?;
+            serde_json::from_slice(&body)
+                .map(Json)
+                .map_err(|e| Rejection::bad_request(format!("Invalid JSON: {}", e)))
+        }
+        .boxed()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> FromContext for State<T> {
+    fn from_context(ctx: &mut Context) -> BoxFuture<'_, Result<Self, Rejection>> {
+        async move {
+            ctx.global.get::<T>().await.map(State).ok_or_else(|| {
+                Rejection::new(StatusCode::InternalServerError, "Internal Server Error")
+            })
+        }
+        .boxed()
+    }
+}
+
+/// 处理器返回值写入响应的方式；返回值决定是否继续执行后续中间件
+pub trait Reply {
+    fn reply(self, ctx: &mut Context) -> bool;
+}
+
+/// 原样返回，由处理器自行决定（不写响应体）
+impl Reply for bool {
+    fn reply(self, _ctx: &mut Context) -> bool {
+        self
+    }
+}
+
+impl Reply for String {
+    fn reply(self, ctx: &mut Context) -> bool {
+        ctx.send(self, None);
+        true
+    }
+}
+
+impl Reply for &'static str {
+    fn reply(self, ctx: &mut Context) -> bool {
+        ctx.send(self, None);
+        true
+    }
+}
+
+impl<T: Serialize> Reply for Json<T> {
+    fn reply(self, ctx: &mut Context) -> bool {
+        let body = match serde_json::to_vec(&self.0) {
+            Ok(body) => body,
+            Err(e) => return crate::http::types::fail_with_error(ctx, e.into()),
+        };
+        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+            meta.headers.insert(
+                HeaderKey::ContentType,
+                ContentType::new(MediaType::Application, SubMediaType::Json)
+                    .with_charset("utf-8")
+                    .to_header_value(),
+            );
+            meta.headers
+                .insert(HeaderKey::ContentLength, body.len().to_string());
+            meta.body = body;
+        }
+        true
+    }
+}
+
+impl<R: Reply> Reply for (StatusCode, R) {
+    fn reply(self, ctx: &mut Context) -> bool {
+        let next = self.1.reply(ctx);
+        ctx.status(self.0);
+        next
+    }
+}
+
+/// `Err` 按 [`Rejection`] 写回错误响应并终止处理
+impl<R: Reply> Reply for Result<R, Rejection> {
+    fn reply(self, ctx: &mut Context) -> bool {
+        match self {
+            Ok(r) => r.reply(ctx),
+            Err(rejection) => {
+                rejection.apply(ctx);
+                false
+            }
+        }
+    }
+}
+
+/// 参数均实现 [`FromContext`]、返回值实现 [`Reply`] 的异步函数
+pub trait Handler<Args>: Clone + Send + Sync + 'static {
+    fn call(self, ctx: &mut Context) -> BoxFuture<'_, bool>;
+}
+
+macro_rules! impl_handler {
+    ($($arg:ident),*) => {
+        impl<F, Fut, R, $($arg,)*> Handler<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = R> + Send,
+            R: Reply,
+            $($arg: FromContext + Send,)*
+        {
+            #[allow(non_snake_case, unused_variables)]
+            fn call(self, ctx: &mut Context) -> BoxFuture<'_, bool> {
+                async move {
+                    $(
+                        let $arg = match $arg::from_context(ctx).await {
+                            Ok(v) => v,
+                            Err(rejection) => {
+                                rejection.apply(ctx);
+                                return false;
+                            }
+                        };
+                    )*
+                    self($($arg),*).await.reply(ctx)
+                }
+                .boxed()
+            }
+        }
+    };
+}
+
+impl_handler!();
+impl_handler!(A1);
+impl_handler!(A1, A2);
+impl_handler!(A1, A2, A3);
+impl_handler!(A1, A2, A3, A4);
+impl_handler!(A1, A2, A3, A4, A5);
+impl_handler!(A1, A2, A3, A4, A5, A6);
+
+/// 将提取器风格的处理器包装为 `Executor`，通常通过 `handler!` 调用
+pub fn into_executor<H, Args>(handler: H) -> Arc<Executor>
+where
+    H: Handler<Args>,
+    Args: 'static,
+{
+    Arc::new(move |ctx: &mut Context| handler.clone().call(ctx))
+}
@@ -11,6 +11,18 @@
 //!     ctx.send("response");
 //!     true
 //! });
+//!
+//! get!(router, "/users/:id", handler);
+//! patch!(router, "/users/:id", handler, [auth]);
+//! route!(router, get!("/users/:id", handler, [auth]));
+//! methods!(router, ["GET", "POST"], "/search", handler);
+//!
+//! // 可失败的处理器：Err 会被记录并转换为 500
+//! let fallible = exe_try!(|ctx| {
+//!     let user = load_user(ctx).await?;
+//!     ctx.send(user.name, None);
+//!     Ok(true)
+//! });
 //! ```
 
 // for `.boxed()`
@@ -110,13 +122,63 @@ macro_rules! exe {
     }};
 }
 
+/// 与 `exe!` 相同，但 body 返回 `anyhow::Result<bool>`，可以使用 `?`；
+/// `Err` 由 `fail_with_error` 记录并转换为 500
+#[macro_export]
+macro_rules! exe_try {
+    (move | $ctx:ident | $body:block) => {
+        $crate::exe_try!(|$ctx| $body)
+    };
+
+    (| $ctx:ident | $body:block) => {{
+        #[allow(unused_variables)]
+        {
+            use futures::future::FutureExt;
+            use std::sync::Arc;
+            use $crate::connection::context::Context;
+
+            let executor: std::sync::Arc<$crate::http::types::Executor> =
+                Arc::new(move |$ctx: &mut Context| {
+                    async move {
+                        let result: anyhow::Result<bool> = async { $body }.await;
+                        match result {
+                            Ok(next) => next,
+                            Err(err) => $crate::http::types::fail_with_error($ctx, err),
+                        }
+                    }
+                    .boxed()
+                });
+            executor
+        }
+    }};
+}
+
+/// 将参数为提取器（`Path`、`Query`、`Json`、`State`）的异步函数包装为 `Executor`
+///
+/// ```rust,ignore
+/// async fn show(Path(id): Path<u32>) -> String { format!("user {}", id) }
+/// router.get("/users/:id", handler!(show)).register();
+/// ```
+#[macro_export]
+macro_rules! handler {
+    ($handler:expr) => {
+        $crate::http::extract::into_executor($handler)
+    };
+}
+
+/// 生成校验中间件；可在规则前以 `on_error = ...;` 指定校验失败时的连接策略
+///
+/// ```rust,ignore
+/// v!(body => "(name: string)");
+/// v!(on_error = OnError::Close; body => "(name: string)");
+/// ```
 #[macro_export]
 macro_rules! validator {
-    ($($key:ident => $dsl:expr),* $(,)?) => {
+    (on_error = $policy:expr; $($key:ident => $dsl:expr),* $(,)?) => {
         {
         use ahash::AHashMap;
         use std::sync::Arc;
-        use $crate::http::middlewares::validator::to_validator;
+        use $crate::http::middlewares::validator::to_validator_with;
 
         #[allow(unused_imports)]
         use $crate::http::types::Executor;
@@ -127,10 +189,16 @@ macro_rules! validator {
             dsl_map.insert(stringify!($key).to_string(), $dsl.to_string());
         )*
 
-        let mw: std::sync::Arc<$crate::http::types::Executor> = to_validator(dsl_map);
+        let mw: std::sync::Arc<$crate::http::types::Executor> = to_validator_with(dsl_map, $policy);
         mw
         }
     };
+    ($($key:ident => $dsl:expr),* $(,)?) => {
+        $crate::validator!(
+            on_error = $crate::http::middlewares::validator::OnError::default();
+            $($key => $dsl),*
+        )
+    };
 }
 
 // 文件：src/macros.rs
@@ -141,3 +209,162 @@ macro_rules! v {
         $crate::validator!($($tokens)*)
     };
 }
+
+/// 通用路由注册宏，展开为 `Router::insert`；也接受 `get!` 等生成的路由元组
+///
+/// ```rust,ignore
+/// route!(router, "GET", "/users/:id", handler);
+/// route!(router, "POST", "/users", handler, [auth, logger]);
+/// route!(router, get!("/users/:id", handler, [auth]));
+/// ```
+#[macro_export]
+macro_rules! route {
+    ($router:expr, $route:expr) => {{
+        let (method, path, handler, middlewares): (
+            &str,
+            &str,
+            std::sync::Arc<$crate::http::types::Executor>,
+            Option<Vec<std::sync::Arc<$crate::http::types::Executor>>>,
+        ) = $route;
+        $router.insert(path, Some(method), handler, middlewares)
+    }};
+    ($router:expr, $method:expr, $path:expr, $handler:expr) => {
+        $router.insert($path, Some($method), $handler, None)
+    };
+    ($router:expr, $method:expr, $path:expr, $handler:expr, [$($mw:expr),* $(,)?]) => {
+        $router.insert($path, Some($method), $handler, Some(vec![$($mw),*]))
+    };
+}
+
+/// 生成 `(方法, 路径, 处理器, 中间件)` 路由元组，交给 `route!` 注册
+#[doc(hidden)]
+#[macro_export]
+macro_rules! route_tuple {
+    ($method:expr, $path:expr, $handler:expr) => {
+        ($method, $path, $handler, None)
+    };
+    ($method:expr, $path:expr, $handler:expr, [$($mw:expr),* $(,)?]) => {
+        ($method, $path, $handler, Some(vec![$($mw),*]))
+    };
+}
+
+/// 按方法注册路由：`get!(router, path, handler[, [mws]])` 直接注册，
+/// `get!(path, handler[, [mws]])` 生成路由元组，配合 `route!(router, ...)` 使用
+#[macro_export]
+macro_rules! get {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("GET", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "GET", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+#[macro_export]
+macro_rules! post {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("POST", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "POST", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+#[macro_export]
+macro_rules! put {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("PUT", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "PUT", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+#[macro_export]
+macro_rules! delete {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("DELETE", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "DELETE", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+#[macro_export]
+macro_rules! patch {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("PATCH", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "PATCH", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+#[macro_export]
+macro_rules! options {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("OPTIONS", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "OPTIONS", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+#[macro_export]
+macro_rules! head {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("HEAD", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "HEAD", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+/// TRACE 路由；不叫 `trace!`，避免与 `tracing::trace!` 同名冲突
+#[macro_export]
+macro_rules! trace_route {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("TRACE", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "TRACE", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+/// CONNECT 路由，命名同 [`trace_route!`]
+#[macro_export]
+macro_rules! connect_route {
+    ($path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route_tuple!("CONNECT", $path, $handler $(, [$($mw),*])?)
+    };
+    ($router:expr, $path:expr, $handler:expr $(, [$($mw:expr),* $(,)?])?) => {
+        $crate::route!($router, "CONNECT", $path, $handler $(, [$($mw),*])?)
+    };
+}
+
+/// 一次为多个方法注册同一个处理器，展开为 `Router::insert_methods`
+///
+/// ```rust,ignore
+/// methods!(router, ["GET", "POST"], "/search", handler);
+/// methods!(router, ["PUT", "PATCH"], "/users/:id", handler, [auth]);
+/// ```
+#[macro_export]
+macro_rules! methods {
+    ($router:expr, [$($method:expr),+ $(,)?], $path:expr, $handler:expr) => {
+        $router.insert_methods($path, &[$($method),+], $handler, None)
+    };
+    ($router:expr, [$($method:expr),+ $(,)?], $path:expr, $handler:expr, [$($mw:expr),* $(,)?]) => {
+        $router.insert_methods($path, &[$($method),+], $handler, Some(vec![$($mw),*]))
+    };
+}
+
+/// 匹配所有方法（method key 为 `*`）
+#[macro_export]
+macro_rules! all {
+    ($router:expr, $path:expr, $handler:expr) => {
+        $router.insert($path, None, $handler, None)
+    };
+    ($router:expr, $path:expr, $handler:expr, [$($mw:expr),* $(,)?]) => {
+        $router.insert($path, None, $handler, Some(vec![$($mw),*]))
+    };
+}
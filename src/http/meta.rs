@@ -1,9 +1,15 @@
 use ahash::AHashMap;
+use anyhow::bail;
+use sha1::{Digest, Sha1};
 
 use crate::http::{
     params::Params,
     protocol::{
-        content_type::ContentType, header::Headers, method::HttpMethod, status::StatusCode,
+        content_type::ContentType,
+        header::{HeaderKey, Headers},
+        media_type::{MediaType, SubMediaType},
+        method::HttpMethod,
+        status::StatusCode,
         version::HttpVersion,
     },
 };
@@ -17,7 +23,8 @@ pub struct HttpMetadata {
     pub is_chunked: bool,
     pub transfer_encoding: Option<String>,
     pub multipart_boundary: Option<String>,
-    pub params: Option<Params>, // 放在Trie路由里解析
+    pub params: Option<Params>,        // 放在Trie路由里解析
+    pub matched_route: Option<String>, // 命中的路由模板，如 `/users/:id`
     pub headers: Headers,
     pub content_type: ContentType,
     // pub length: usize,
@@ -41,6 +48,7 @@ impl Default for HttpMetadata {
             transfer_encoding: None,
             multipart_boundary: None,
             params: None,
+            matched_route: None,
             headers: Headers::new(),
             // 假设 ContentType 有默认值（通常是 text/plain 或 application/octet-stream）
             content_type: ContentType::default(),
@@ -58,4 +66,94 @@ impl HttpMetadata {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// 客户端是否在等待 `100 Continue`（仅 HTTP/1.1 有效）
+    pub fn expects_continue(&self) -> bool {
+        self.version == HttpVersion::Http11
+            && self
+                .headers
+                .get(&HeaderKey::Expect)
+                .map(|v| v.trim().eq_ignore_ascii_case("100-continue"))
+                .unwrap_or(false)
+    }
+
+    /// 设置重定向状态码与 Location 头，状态码必须是 3xx
+    pub fn redirect(
+        &mut self,
+        status: StatusCode,
+        location: impl Into<String>,
+    ) -> anyhow::Result<()> {
+        if !(300..400).contains(&status.as_u16()) {
+            bail!("Redirect requires a 3xx status, got {}", status.as_u16());
+        }
+        self.status = status;
+        self.headers.insert(HeaderKey::Location, location.into());
+        Ok(())
+    }
+
+    /// 301 Moved Permanently
+    pub fn redirect_permanent(&mut self, location: impl Into<String>) {
+        self.status = StatusCode::MovedPermanently;
+        self.headers.insert(HeaderKey::Location, location.into());
+    }
+
+    /// 302 Found
+    pub fn redirect_temporary(&mut self, location: impl Into<String>) {
+        self.status = StatusCode::Found;
+        self.headers.insert(HeaderKey::Location, location.into());
+    }
+
+    /// 同时设置错误状态码与纯文本响应体
+    pub fn error(&mut self, status: StatusCode, message: impl Into<String>) {
+        self.status = status;
+        self.body = message.into().into_bytes();
+        self.headers.insert(
+            HeaderKey::ContentType,
+            ContentType::new(MediaType::Text, SubMediaType::Plain)
+                .with_charset("utf-8")
+                .to_header_value(),
+        );
+    }
+
+    /// 同 `error`，响应体为 `{"error": message}`
+    pub fn error_json(&mut self, status: StatusCode, message: impl Into<String>) {
+        self.status = status;
+        self.body = serde_json::json!({ "error": message.into() })
+            .to_string()
+            .into_bytes();
+        self.headers.insert(
+            HeaderKey::ContentType,
+            ContentType::new(MediaType::Application, SubMediaType::Json)
+                .with_charset("utf-8")
+                .to_header_value(),
+        );
+    }
+
+    /// 根据响应体生成强 ETag（SHA-1，带引号）
+    pub fn etag_for(body: &[u8]) -> String {
+        format!("\"{:x}\"", Sha1::digest(body))
+    }
+
+    /// 为当前响应体写入 ETag；GET/HEAD 请求的 If-None-Match 命中时
+    /// 改为 304 Not Modified 并清空响应体，返回是否命中
+    pub fn maybe_not_modified(&mut self) -> bool {
+        let etag = Self::etag_for(&self.body);
+        let matched = matches!(self.method, HttpMethod::GET | HttpMethod::HEAD)
+            && self
+                .headers
+                .get(&HeaderKey::IfNoneMatch)
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+                })
+                .unwrap_or(false);
+
+        self.headers.insert(HeaderKey::ETag, etag);
+        if matched {
+            self.status = StatusCode::NotModified;
+            self.body.clear();
+        }
+        matched
+    }
 }
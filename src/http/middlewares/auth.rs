@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata,
+        protocol::{header::HeaderKey, status::StatusCode},
+        types::Executor,
+    },
+};
+
+/// 认证通过后写入 `ctx.local` 的身份信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthIdentity {
+    /// Basic 认证的用户名
+    Basic(String),
+    /// Bearer 认证的 token
+    Bearer(String),
+}
+
+type BasicVerifier = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
+type BearerVerifier = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// 从 Authorization 头中拆出指定 scheme 的凭证部分（scheme 大小写不敏感）
+fn credentials<'a>(header: &'a str, scheme: &str) -> Option<&'a str> {
+    let (s, rest) = header.trim().split_once(' ')?;
+    if s.eq_ignore_ascii_case(scheme) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+/// 写入 401 与 WWW-Authenticate，供中间件拦截时使用
+fn reject(meta: &mut HttpMetadata, challenge: String) {
+    meta.status = StatusCode::Unauthorized;
+    meta.headers.insert(HeaderKey::WWWAuthenticate, challenge);
+    meta.body = b"Unauthorized".to_vec();
+}
+
+#[derive(Clone)]
+pub struct BasicAuth {
+    realm: String,
+    verify: BasicVerifier,
+}
+
+impl BasicAuth {
+    pub fn new<F>(verify: F) -> Self
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            realm: "aex".to_string(),
+            verify: Arc::new(verify),
+        }
+    }
+
+    pub fn realm(mut self, realm: &str) -> Self {
+        self.realm = realm.to_string();
+        self
+    }
+
+    /// 解析 `Basic base64(user:pass)`，失败返回 None
+    pub fn parse(header: &str) -> Option<(String, String)> {
+        let encoded = credentials(header, "Basic")?;
+        let decoded = STANDARD.decode(encoded).ok()?;
+        let pair = String::from_utf8(decoded).ok()?;
+        let (user, pass) = pair.split_once(':')?;
+        Some((user.to_string(), pass.to_string()))
+    }
+
+    pub fn build(self) -> Arc<Executor> {
+        let config = Arc::new(self);
+        exe!(
+            move |ctx, config| {
+                let meta = match ctx.local.get_mut::<HttpMetadata>() {
+                    Some(m) => m,
+                    None => return false,
+                };
+
+                let user = meta
+                    .headers
+                    .get(&HeaderKey::Authorization)
+                    .and_then(|h| Self::parse(h))
+                    .filter(|(user, pass)| (config.verify)(user, pass))
+                    .map(|(user, _)| user);
+
+                match user {
+                    Some(user) => {
+                        ctx.local.set_value(AuthIdentity::Basic(user));
+                        true
+                    }
+                    None => {
+                        reject(meta, format!("Basic realm=\"{}\"", config.realm));
+                        false
+                    }
+                }
+            },
+            |ctx| { config.clone() }
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct BearerAuth {
+    realm: String,
+    verify: BearerVerifier,
+}
+
+impl BearerAuth {
+    pub fn new<F>(verify: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            realm: "aex".to_string(),
+            verify: Arc::new(verify),
+        }
+    }
+
+    pub fn realm(mut self, realm: &str) -> Self {
+        self.realm = realm.to_string();
+        self
+    }
+
+    /// 解析 `Bearer <token>`，空 token 视为无效
+    pub fn parse(header: &str) -> Option<String> {
+        credentials(header, "Bearer")
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+    }
+
+    pub fn build(self) -> Arc<Executor> {
+        let config = Arc::new(self);
+        exe!(
+            move |ctx, config| {
+                let meta = match ctx.local.get_mut::<HttpMetadata>() {
+                    Some(m) => m,
+                    None => return false,
+                };
+
+                let token = meta
+                    .headers
+                    .get(&HeaderKey::Authorization)
+                    .and_then(|h| Self::parse(h))
+                    .filter(|token| (config.verify)(token));
+
+                match token {
+                    Some(token) => {
+                        ctx.local.set_value(AuthIdentity::Bearer(token));
+                        true
+                    }
+                    None => {
+                        reject(meta, format!("Bearer realm=\"{}\"", config.realm));
+                        false
+                    }
+                }
+            },
+            |ctx| { config.clone() }
+        )
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use flate2::{
+    Compression as Level,
+    write::{DeflateEncoder, GzEncoder},
+};
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata,
+        protocol::{header::HeaderKey, status::StatusCode},
+        types::Executor,
+    },
+};
+
+/// 小于该长度的响应体压缩收益不大，默认不压缩
+pub const DEFAULT_MIN_SIZE: usize = 256;
+
+/// 协商得到的响应编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+    Identity,
+}
+
+impl Encoding {
+    /// 服务端支持的压缩编码，q 值相同时按此顺序优先
+    const SUPPORTED: [Encoding; 2] = [Encoding::Gzip, Encoding::Deflate];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Identity => "identity",
+        }
+    }
+
+    fn encode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Gzip => {
+                let mut enc = GzEncoder::new(Vec::new(), Level::default());
+                enc.write_all(data)?;
+                enc.finish()
+            }
+            Encoding::Deflate => {
+                let mut enc = DeflateEncoder::new(Vec::new(), Level::default());
+                enc.write_all(data)?;
+                enc.finish()
+            }
+            Encoding::Identity => Ok(data.to_vec()),
+        }
+    }
+}
+
+/// 解析 `Accept-Encoding`，返回 (小写编码名, q 值)；q 非法时按 0 处理
+fn parse_accept_encoding(header: &str) -> Vec<(String, f32)> {
+    header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let coding = parts.next()?.trim().to_ascii_lowercase();
+            if coding.is_empty() {
+                return None;
+            }
+            let q = parts
+                .filter_map(|p| p.split_once('='))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("q"))
+                .map(|(_, v)| {
+                    v.trim()
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|q| (0.0..=1.0).contains(q))
+                })
+                .map_or(1.0, |q| q.unwrap_or(0.0));
+            Some((coding, q))
+        })
+        .collect()
+}
+
+/// 按 RFC 9110 12.5.3 协商响应编码；`None` 表示没有可接受的编码，应返回 406。
+///
+/// - 未携带头：identity
+/// - 未列出的编码取 `*` 的 q 值；identity 未列出且无 `*` 时始终可接受
+/// - `identity;q=0` 或（identity 未列出时的）`*;q=0` 禁止不压缩
+pub fn negotiate(accept: Option<&str>) -> Option<Encoding> {
+    let Some(accept) = accept.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Some(Encoding::Identity);
+    };
+    let codings = parse_accept_encoding(accept);
+    let q_of = |name: &str| {
+        codings
+            .iter()
+            .find(|(c, _)| c == name)
+            .or_else(|| codings.iter().find(|(c, _)| c == "*"))
+            .map(|(_, q)| *q)
+    };
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in Encoding::SUPPORTED {
+        if let Some(q) = q_of(encoding.as_str())
+            && q > 0.0
+            && best.is_none_or(|(_, b)| q > b)
+        {
+            best = Some((encoding, q));
+        }
+    }
+    if let Some((encoding, _)) = best {
+        return Some(encoding);
+    }
+
+    match q_of("identity") {
+        Some(q) if q <= 0.0 => None,
+        _ => Some(Encoding::Identity),
+    }
+}
+
+/// 按 `Accept-Encoding` 压缩响应体。
+///
+/// `negotiate` 挂为中间件（无可接受编码时 406），`compress` 挂为后置钩子：
+///
+/// ```rust,ignore
+/// let gzip = Compression::new();
+/// hr.get("/report", handler)
+///     .middleware(gzip.negotiate())
+///     .after(gzip.compress())
+///     .register();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compression {
+    min_size: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compression {
+    pub fn new() -> Self {
+        Self {
+            min_size: DEFAULT_MIN_SIZE,
+        }
+    }
+
+    /// 响应体不小于 `min_size` 字节时才压缩
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// 中间件：协商编码并写入 `ctx.local`，无可接受编码时返回 406
+    pub fn negotiate(&self) -> Arc<Executor> {
+        exe!(|ctx| {
+            let accept = ctx.local.get_ref::<HttpMetadata>().and_then(|meta| {
+                meta.headers
+                    .get(&HeaderKey::AcceptEncoding)
+                    .map(|v| v.to_string())
+            });
+            match negotiate(accept.as_deref()) {
+                Some(encoding) => {
+                    ctx.local.set_value(encoding);
+                    true
+                }
+                None => {
+                    if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                        meta.status = StatusCode::NotAcceptable;
+                        meta.body = b"No acceptable content encoding".to_vec();
+                    }
+                    false
+                }
+            }
+        })
+    }
+
+    /// 后置钩子：按协商结果压缩 `meta.body`；已自行写出或已编码的响应不处理
+    pub fn compress(&self) -> Arc<Executor> {
+        let min_size = self.min_size;
+        exe!(move |ctx| {
+            let Some(encoding) = ctx.local.get_value::<Encoding>() else {
+                return true;
+            };
+            let committed = ctx.res().is_committed();
+            let Some(meta) = ctx.local.get_mut::<HttpMetadata>() else {
+                return true;
+            };
+            // 响应内容随 Accept-Encoding 变化，缓存需区分
+            match meta.headers.get(&HeaderKey::Vary) {
+                Some(vary) if vary.to_ascii_lowercase().contains("accept-encoding") => {}
+                Some(vary) => {
+                    let vary = format!("{}, Accept-Encoding", vary);
+                    meta.headers.insert(HeaderKey::Vary, vary);
+                }
+                None => {
+                    meta.headers.insert(HeaderKey::Vary, "Accept-Encoding");
+                }
+            }
+            if committed
+                || encoding == Encoding::Identity
+                || meta.body.len() < min_size
+                || meta.headers.contains(&HeaderKey::ContentEncoding)
+            {
+                return true;
+            }
+            if let Ok(body) = encoding.encode(&meta.body) {
+                meta.body = body;
+                meta.headers
+                    .insert(HeaderKey::ContentEncoding, encoding.as_str());
+            }
+            true
+        })
+    }
+}
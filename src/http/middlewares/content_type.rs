@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata,
+        protocol::{
+            media_type::{MediaType, SubMediaType},
+            status::StatusCode,
+        },
+        types::Executor,
+    },
+};
+
+/// 要求请求的 Content-Type 为指定类型（忽略 charset 等参数），否则返回 415
+pub fn require(top_level: MediaType, sub_type: SubMediaType) -> Arc<Executor> {
+    exe!(move |ctx| {
+        let meta = match ctx.local.get_mut::<HttpMetadata>() {
+            Some(m) => m,
+            None => return false,
+        };
+
+        let actual = &meta.content_type;
+        if actual.top_level == top_level && actual.sub_type == sub_type {
+            return true;
+        }
+
+        meta.status = StatusCode::UnsupportedMediaType;
+        meta.body = format!(
+            "Unsupported Media Type: expected {}/{}, got {}/{}",
+            top_level.as_str(),
+            sub_type.as_str(),
+            actual.top_level.as_str(),
+            actual.sub_type.as_str()
+        )
+        .into_bytes();
+        false
+    })
+}
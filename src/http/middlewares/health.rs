@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata,
+        protocol::{
+            content_type::ContentType,
+            header::HeaderKey,
+            media_type::{MediaType, SubMediaType},
+            status::StatusCode,
+        },
+        router::Router,
+        types::Executor,
+    },
+};
+
+pub const LIVENESS_PATH: &str = "/healthz";
+pub const READINESS_PATH: &str = "/readyz";
+
+fn reply(meta: &mut HttpMetadata, status: StatusCode, body: &str) {
+    meta.status = status;
+    meta.headers.insert(
+        HeaderKey::ContentType,
+        ContentType::new(MediaType::Text, SubMediaType::Plain)
+            .with_charset("utf-8")
+            .to_header_value(),
+    );
+    meta.headers.insert(HeaderKey::CacheControl, "no-store");
+    meta.body = body.as_bytes().to_vec();
+}
+
+/// 存活探针：进程能处理请求即返回 200
+pub fn liveness() -> Arc<Executor> {
+    exe!(|ctx| {
+        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+            reply(meta, StatusCode::Ok, "ok");
+        }
+        true
+    })
+}
+
+/// 就绪探针：`is_ready` 返回 true 时 200，否则 503，负载均衡据此摘除实例
+pub fn readiness<F>(is_ready: F) -> Arc<Executor>
+where
+    F: Fn() -> bool + Send + Sync + 'static,
+{
+    exe!(
+        move |ctx, ready| {
+            if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                if ready {
+                    reply(meta, StatusCode::Ok, "ready");
+                } else {
+                    reply(meta, StatusCode::ServiceUnavailable, "not ready");
+                }
+            }
+            true
+        },
+        |ctx| { is_ready() }
+    )
+}
+
+/// 在 `/healthz` 与 `/readyz` 挂载存活与就绪探针
+///
+/// ```rust,ignore
+/// let ready = Arc::new(AtomicBool::new(false));
+/// let flag = ready.clone();
+/// health::mount(&mut router, move || flag.load(Ordering::Relaxed));
+/// ```
+pub fn mount<F>(router: &mut Router, is_ready: F)
+where
+    F: Fn() -> bool + Send + Sync + 'static,
+{
+    router.get(LIVENESS_PATH, liveness()).register();
+    router.get(READINESS_PATH, readiness(is_ready)).register();
+}
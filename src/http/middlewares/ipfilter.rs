@@ -0,0 +1,72 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use ipnet::IpNet;
+
+use crate::{
+    exe,
+    http::{meta::HttpMetadata, protocol::status::StatusCode, req::real_ip, types::Executor},
+};
+
+/// 按对端 IP 的访问控制。
+///
+/// 命中 deny 列表直接拒绝；allow 列表非空时只放行其中的地址；
+/// 两个列表都为空时全部放行。被拒绝的请求返回 403 并中断后续链路。
+#[derive(Clone, Default)]
+pub struct IpFilter {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl IpFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加允许的网段，单个地址可写作 `/32` 或 `/128`
+    pub fn allow(mut self, nets: impl IntoIterator<Item = IpNet>) -> Self {
+        self.allow.extend(nets);
+        self
+    }
+
+    /// 追加拒绝的网段，优先级高于 allow
+    pub fn deny(mut self, nets: impl IntoIterator<Item = IpNet>) -> Self {
+        self.deny.extend(nets);
+        self
+    }
+
+    /// 位于反向代理之后时，按转发头还原的客户端地址过滤，见 [`real_ip`]
+    pub fn trusted_proxies(mut self, nets: impl IntoIterator<Item = IpNet>) -> Self {
+        self.trusted_proxies.extend(nets);
+        self
+    }
+
+    /// IPv4 映射的 IPv6 地址按 IPv4 匹配
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip))
+    }
+
+    pub fn build(self) -> Arc<Executor> {
+        let config = Arc::new(self);
+        exe!(
+            move |ctx, config| {
+                let peer = ctx.addr.ip();
+                let ip = match ctx.local.get_ref::<HttpMetadata>() {
+                    Some(meta) => real_ip(peer, &meta.headers, &config.trusted_proxies),
+                    None => peer,
+                };
+                if config.is_allowed(ip) {
+                    return true;
+                }
+                ctx.status(StatusCode::Forbidden).send("Forbidden", None);
+                false
+            },
+            |ctx| { config.clone() }
+        )
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::{BoxFuture, FutureExt, join_all};
+use serde_json::{Value, json};
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata,
+        protocol::{
+            content_type::ContentType,
+            header::HeaderKey,
+            media_type::{MediaType, SubMediaType},
+            status::StatusCode,
+        },
+        types::Executor,
+    },
+};
+
+/// JSON-RPC 2.0 错误对象
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(Self::INVALID_PARAMS, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(Self::INTERNAL_ERROR, message)
+    }
+
+    fn to_value(&self) -> Value {
+        let mut err = json!({ "code": self.code, "message": self.message });
+        if let Some(data) = &self.data {
+            err["data"] = data.clone();
+        }
+        err
+    }
+}
+
+type RpcHandler =
+    Arc<dyn Fn(Option<Value>) -> BoxFuture<'static, Result<Value, RpcError>> + Send + Sync>;
+
+/// 基于 HTTP POST 的 JSON-RPC 2.0 分发器，挂为路由处理器使用：
+///
+/// ```rust,ignore
+/// let rpc = JsonRpc::new().register("sum", |params| async move {
+///     let nums: Vec<i64> = serde_json::from_value(params.unwrap_or_default())
+///         .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+///     Ok(json!(nums.iter().sum::<i64>()))
+/// });
+/// post!(hr, "/rpc", rpc.build());
+/// ```
+///
+/// 支持批量请求；通知（无 id）不产生响应，全部为通知时返回 204
+#[derive(Clone, Default)]
+pub struct JsonRpc {
+    methods: HashMap<String, RpcHandler>,
+}
+
+impl JsonRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册方法；handler 收到 `params`（数组或对象，缺省为 None）
+    pub fn register<F, Fut>(mut self, method: &str, handler: F) -> Self
+    where
+        F: Fn(Option<Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, RpcError>> + Send + 'static,
+    {
+        self.methods.insert(
+            method.to_string(),
+            Arc::new(move |params| handler(params).boxed()),
+        );
+        self
+    }
+
+    /// 处理一个请求体，返回响应体；全部为通知时返回 None
+    pub async fn handle(&self, body: &[u8]) -> Option<Value> {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(v) => v,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(RpcError::PARSE_ERROR, "Parse error")
+                        .with_data(json!(e.to_string())),
+                ));
+            }
+        };
+
+        match request {
+            Value::Array(calls) if calls.is_empty() => Some(error_response(
+                Value::Null,
+                RpcError::new(RpcError::INVALID_REQUEST, "Invalid Request"),
+            )),
+            Value::Array(calls) => {
+                let responses: Vec<Value> = join_all(calls.into_iter().map(|c| self.call(c)))
+                    .await
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            call => self.call(call).await,
+        }
+    }
+
+    async fn call(&self, call: Value) -> Option<Value> {
+        let invalid = || RpcError::new(RpcError::INVALID_REQUEST, "Invalid Request");
+        let Value::Object(mut call) = call else {
+            return Some(error_response(Value::Null, invalid()));
+        };
+
+        // id 只能是字符串、数字或 null；缺失即为通知
+        let id = call.remove("id");
+        if id
+            .as_ref()
+            .is_some_and(|id| !(id.is_string() || id.is_number() || id.is_null()))
+        {
+            return Some(error_response(Value::Null, invalid()));
+        }
+        let params = call.remove("params");
+        let valid = call.get("jsonrpc").and_then(Value::as_str) == Some("2.0")
+            && call.get("method").is_some_and(Value::is_string)
+            && params
+                .as_ref()
+                .is_none_or(|p| p.is_array() || p.is_object());
+        if !valid {
+            return Some(error_response(id.unwrap_or(Value::Null), invalid()));
+        }
+
+        let method = call["method"].as_str().unwrap_or_default();
+        let result = match self.methods.get(method) {
+            Some(handler) => handler(params).await,
+            None => Err(RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                "Method not found",
+            )),
+        };
+
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err(err) => error_response(id, err),
+        })
+    }
+
+    pub fn build(self) -> Arc<Executor> {
+        let rpc = Arc::new(self);
+        exe!(
+            move |ctx, rpc| {
+                let body = match ctx.req().read_body().await {
+                    Ok(body) => body,
+                    Err(_) => {
+                        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                            meta.error(StatusCode::BadRequest, "Bad Request");
+                        }
+                        return false;
+                    }
+                };
+                let response = rpc.handle(&body).await;
+
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    match response {
+                        Some(response) => {
+                            meta.status = StatusCode::Ok;
+                            meta.body = response.to_string().into_bytes();
+                            meta.headers.insert(
+                                HeaderKey::ContentType,
+                                ContentType::new(MediaType::Application, SubMediaType::Json)
+                                    .with_charset("utf-8")
+                                    .to_header_value(),
+                            );
+                        }
+                        None => {
+                            meta.status = StatusCode::NoContent;
+                            meta.body.clear();
+                        }
+                    }
+                }
+                true
+            },
+            |ctx| { rpc.clone() }
+        )
+    }
+}
+
+fn error_response(id: Value, err: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "error": err.to_value(), "id": id })
+}
@@ -0,0 +1,249 @@
+use std::fmt::Write;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+
+use dashmap::DashMap;
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata,
+        protocol::{
+            content_type::ContentType,
+            header::HeaderKey,
+            media_type::{MediaType, SubMediaType},
+        },
+        types::Executor,
+    },
+};
+
+/// 默认的延迟分桶（秒），与 Prometheus 客户端库一致
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// `track` 写入 `ctx.local` 的请求开始时间
+#[derive(Debug, Clone, Copy)]
+pub struct RequestStart(pub Instant);
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// 与 buckets 一一对应，非累计
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// 按路由统计请求数、状态码分布与延迟，并以 Prometheus 文本格式输出。
+///
+/// 克隆后共享同一份数据。`track` 挂为中间件、`record` 挂为后置钩子：
+///
+/// ```rust,ignore
+/// let metrics = Metrics::new();
+/// hr.get("/users/:id", handler)
+///     .middleware(metrics.track())
+///     .after(metrics.record())
+///     .register();
+/// route!(hr, get!("/metrics", metrics.handler()));
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    buckets: Arc<Vec<f64>>,
+    /// (method, route, status) -> 请求数
+    requests: Arc<DashMap<(String, String, u16), u64>>,
+    /// (method, route) -> 延迟分布
+    latency: Arc<DashMap<(String, String), Histogram>>,
+}
+
+static GLOBAL: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            buckets: Arc::new(DEFAULT_BUCKETS.to_vec()),
+            requests: Arc::new(DashMap::new()),
+            latency: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// 进程级共享实例，供 `metrics::track()` 等快捷函数使用
+    pub fn global() -> &'static Metrics {
+        &GLOBAL
+    }
+
+    /// 自定义延迟分桶上界（秒），需在记录任何请求之前设置
+    pub fn buckets(mut self, buckets: &[f64]) -> Self {
+        let mut buckets = buckets.to_vec();
+        buckets.sort_by(f64::total_cmp);
+        self.buckets = Arc::new(buckets);
+        self
+    }
+
+    /// 记录一次请求；route 为路由模板，未命中时使用原始路径
+    pub fn observe(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        *self
+            .requests
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert(0) += 1;
+
+        let mut hist = self
+            .latency
+            .entry((method.to_string(), route.to_string()))
+            .or_insert_with(|| Histogram {
+                counts: vec![0; self.buckets.len()],
+                ..Default::default()
+            });
+        if let Some(i) = self.buckets.iter().position(|le| seconds <= *le) {
+            hist.counts[i] += 1;
+        }
+        hist.sum += seconds;
+        hist.count += 1;
+    }
+
+    /// 某个路由与状态码下的请求数
+    pub fn request_count(&self, method: &str, route: &str, status: u16) -> u64 {
+        self.requests
+            .get(&(method.to_string(), route.to_string(), status))
+            .map(|n| *n)
+            .unwrap_or(0)
+    }
+
+    /// 中间件：记录请求开始时间
+    pub fn track(&self) -> Arc<Executor> {
+        exe!(|ctx| {
+            ctx.local.set_value(RequestStart(Instant::now()));
+            true
+        })
+    }
+
+    /// 后置钩子：按最终状态码记录请求数与耗时
+    pub fn record(&self) -> Arc<Executor> {
+        let metrics = self.clone();
+        exe!(
+            move |ctx, metrics| {
+                let elapsed = ctx
+                    .local
+                    .get_value::<RequestStart>()
+                    .map(|RequestStart(t)| t.elapsed().as_secs_f64())
+                    .unwrap_or_default();
+                if let Some(meta) = ctx.local.get_ref::<HttpMetadata>() {
+                    let route = meta.matched_route.as_deref().unwrap_or(&meta.path);
+                    metrics.observe(meta.method.to_str(), route, meta.status as u16, elapsed);
+                }
+                true
+            },
+            |ctx| { metrics.clone() }
+        )
+    }
+
+    /// 以 Prometheus 文本格式输出的处理器
+    pub fn handler(&self) -> Arc<Executor> {
+        let metrics = self.clone();
+        exe!(
+            move |ctx, body| {
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    meta.headers.insert(
+                        HeaderKey::ContentType,
+                        ContentType::new(MediaType::Text, SubMediaType::Plain)
+                            .with_param("version", "0.0.4")
+                            .with_charset("utf-8")
+                            .to_header_value(),
+                    );
+                    meta.body = body.into_bytes();
+                }
+                true
+            },
+            |ctx| { metrics.render() }
+        )
+    }
+
+    /// 渲染为 Prometheus 文本格式，标签按字典序输出
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let mut requests: Vec<_> = self
+            .requests
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect();
+        requests.sort();
+        out.push_str("# HELP aex_http_requests_total Total HTTP requests.\n");
+        out.push_str("# TYPE aex_http_requests_total counter\n");
+        for ((method, route, status), n) in requests {
+            let _ = writeln!(
+                out,
+                "aex_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method,
+                escape(&route),
+                status,
+                n
+            );
+        }
+
+        let mut latency: Vec<_> = self
+            .latency
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect();
+        latency.sort_by(|a, b| a.0.cmp(&b.0));
+        out.push_str("# HELP aex_http_request_duration_seconds HTTP request latency in seconds.\n");
+        out.push_str("# TYPE aex_http_request_duration_seconds histogram\n");
+        for ((method, route), hist) in latency {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape(&route));
+            let mut cumulative = 0;
+            for (le, n) in self.buckets.iter().zip(&hist.counts) {
+                cumulative += n;
+                let _ = writeln!(
+                    out,
+                    "aex_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "aex_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, hist.count
+            );
+            let _ = writeln!(
+                out,
+                "aex_http_request_duration_seconds_sum{{{}}} {}",
+                labels, hist.sum
+            );
+            let _ = writeln!(
+                out,
+                "aex_http_request_duration_seconds_count{{{}}} {}",
+                labels, hist.count
+            );
+        }
+        out
+    }
+}
+
+/// 标签值转义：反斜杠、双引号与换行
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// `Metrics::global().track()`
+pub fn track() -> Arc<Executor> {
+    Metrics::global().track()
+}
+
+/// `Metrics::global().record()`
+pub fn record() -> Arc<Executor> {
+    Metrics::global().record()
+}
+
+/// `Metrics::global().handler()`，可直接挂到 `/metrics`
+pub fn handler() -> Arc<Executor> {
+    Metrics::global().handler()
+}
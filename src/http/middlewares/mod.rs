@@ -1,5 +1,14 @@
+pub mod auth;
+pub mod compression;
+pub mod content_type;
 pub mod cors;
+pub mod health;
+pub mod ipfilter;
+pub mod jsonrpc;
 pub mod logger;
+pub mod metrics;
 pub mod rate_limit;
+pub mod request_id;
+pub mod session;
 pub mod validator;
 pub mod websocket;
@@ -2,13 +2,14 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
+use ipnet::IpNet;
 
 use crate::{
     connection::context::Context,
     exe,
     http::{
         meta::HttpMetadata, protocol::header::HeaderKey, protocol::status::StatusCode,
-        types::Executor,
+        req::real_ip, types::Executor,
     },
 };
 
@@ -33,6 +34,18 @@ impl RateLimitConfig {
         self
     }
 
+    /// 按客户端真实 IP 限流；对端属于 `trusted_proxies` 时读取转发头
+    pub fn by_real_ip(mut self, trusted_proxies: Vec<IpNet>) -> Self {
+        self.key_fn = Arc::new(move |ctx| {
+            let peer = ctx.addr.ip();
+            match ctx.local.get_ref::<HttpMetadata>() {
+                Some(meta) => real_ip(peer, &meta.headers, &trusted_proxies).to_string(),
+                None => peer.to_string(),
+            }
+        });
+        self
+    }
+
     pub fn by_header(mut self, header: &str) -> Self {
         let header_name = header.to_string();
         self.key_fn = Arc::new(move |ctx| {
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use chacha20poly1305::aead::{OsRng, rand_core::RngCore};
+
+use crate::{
+    exe,
+    http::{meta::HttpMetadata, protocol::header::HeaderKey, types::Executor},
+};
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// 外部传入的 id 超过该长度时忽略并重新生成
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// 当前请求的关联 id，写入 `ctx.local`，供处理器与日志使用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// 生成 32 位十六进制的随机 id
+pub fn generate() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// 沿用请求中的 `X-Request-Id`（缺失或非法时生成新的），存入 `ctx.local` 并回写到响应头
+pub fn request_id() -> Arc<Executor> {
+    exe!(|ctx| {
+        let meta = match ctx.local.get_mut::<HttpMetadata>() {
+            Some(m) => m,
+            None => return true,
+        };
+
+        let key = HeaderKey::from(REQUEST_ID_HEADER);
+        let id = meta
+            .headers
+            .get(&key)
+            .map(|v| v.trim().to_string())
+            .filter(|v| is_valid(v))
+            .unwrap_or_else(generate);
+        meta.headers.insert(key, id.clone());
+        ctx.local.set_value(RequestId(id));
+        true
+    })
+}
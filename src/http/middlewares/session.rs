@@ -0,0 +1,205 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::future::BoxFuture;
+use sha1::{Digest, Sha1};
+use tokio::sync::Mutex;
+
+use crate::{
+    exe,
+    http::{
+        meta::HttpMetadata, middlewares::request_id::generate, protocol::header::HeaderKey,
+        types::Executor,
+    },
+};
+
+pub type SessionData = HashMap<String, String>;
+
+/// 会话存储后端，默认提供内存实现，可替换为 Redis 等外部存储
+pub trait SessionStore: Send + Sync {
+    fn load<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Option<SessionData>>;
+    fn save<'a>(&'a self, id: &'a str, data: SessionData) -> BoxFuture<'a, ()>;
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, ()>;
+}
+
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    sessions: Arc<Mutex<HashMap<String, SessionData>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Option<SessionData>> {
+        Box::pin(async move { self.sessions.lock().await.get(id).cloned() })
+    }
+
+    fn save<'a>(&'a self, id: &'a str, data: SessionData) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.sessions.lock().await.insert(id.to_string(), data);
+        })
+    }
+
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.sessions.lock().await.remove(id);
+        })
+    }
+}
+
+/// HMAC-SHA1（RFC 2104）
+pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..20].copy_from_slice(&Sha1::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha1::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha1::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 常量时间比较，避免签名校验泄露时序信息
+fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 当前请求的会话，写入 `ctx.local`；修改会立即写入存储
+#[derive(Clone)]
+pub struct Session {
+    id: String,
+    data: SessionData,
+    store: Arc<dyn SessionStore>,
+}
+
+impl Session {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.data.get(key)
+    }
+
+    pub fn data(&self) -> &SessionData {
+        &self.data
+    }
+
+    pub async fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.data.insert(key.into(), value.into());
+        self.store.save(&self.id, self.data.clone()).await;
+    }
+
+    pub async fn remove(&mut self, key: &str) -> Option<String> {
+        let old = self.data.remove(key);
+        self.store.save(&self.id, self.data.clone()).await;
+        old
+    }
+
+    /// 清空并从存储中删除会话
+    pub async fn destroy(&mut self) {
+        self.data.clear();
+        self.store.remove(&self.id).await;
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionConfig {
+    secret: Vec<u8>,
+    cookie_name: String,
+    max_age: u64,
+    store: Arc<dyn SessionStore>,
+}
+
+impl SessionConfig {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            cookie_name: "aex_sid".to_string(),
+            max_age: 24 * 60 * 60,
+            store: Arc::new(MemoryStore::new()),
+        }
+    }
+
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.cookie_name = name.to_string();
+        self
+    }
+
+    /// Cookie 有效期（秒），每次请求都会刷新
+    pub fn max_age(mut self, secs: u64) -> Self {
+        self.max_age = secs;
+        self
+    }
+
+    pub fn store<S: SessionStore + 'static>(mut self, store: S) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// 生成 `id.signature` 形式的 Cookie 值
+    pub fn sign(&self, id: &str) -> String {
+        format!("{}.{}", id, hex(&hmac_sha1(&self.secret, id.as_bytes())))
+    }
+
+    /// 校验签名，成功时返回会话 id
+    pub fn verify<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let (id, signature) = value.rsplit_once('.')?;
+        let expected = hex(&hmac_sha1(&self.secret, id.as_bytes()));
+        constant_eq(signature.as_bytes(), expected.as_bytes()).then_some(id)
+    }
+
+    pub fn build(self) -> Arc<Executor> {
+        let config = Arc::new(self);
+        exe!(
+            move |ctx, config| {
+                let cookie = match ctx.local.get_ref::<HttpMetadata>() {
+                    Some(meta) => meta.cookies.get(&config.cookie_name).cloned(),
+                    None => return true,
+                };
+
+                // 签名无效或会话已不存在时发放新会话
+                let mut loaded = None;
+                if let Some(id) = cookie.as_deref().and_then(|v| config.verify(v))
+                    && let Some(data) = config.store.load(id).await
+                {
+                    loaded = Some((id.to_string(), data));
+                }
+                let (id, data) = loaded.unwrap_or_else(|| (generate(), SessionData::new()));
+
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    meta.headers.insert(
+                        HeaderKey::SetCookie,
+                        format!(
+                            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+                            config.cookie_name,
+                            config.sign(&id),
+                            config.max_age
+                        ),
+                    );
+                }
+                ctx.local.set_value(Session {
+                    id,
+                    data,
+                    store: config.store.clone(),
+                });
+                true
+            },
+            |ctx| { config.clone() }
+        )
+    }
+}
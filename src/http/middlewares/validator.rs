@@ -2,14 +2,19 @@ use ahash::AHashMap;
 use std::collections::HashMap;
 use std::sync::Arc;
 use zz_validator::{
-    ast::{FieldRule, FieldType, Value},
+    ast::{Constraint, FieldRule, FieldType, Value},
     parser::Parser,
     validator::validate_object,
 };
 
 use crate::{
     exe,
-    http::{meta::HttpMetadata, protocol::status::StatusCode, types::Executor},
+    http::{
+        meta::HttpMetadata,
+        protocol::{header::HeaderKey, media_type::SubMediaType, status::StatusCode},
+        req::RawBody,
+        types::Executor,
+    },
 };
 
 /// 1. 独立转换函数：确保在 to_value_optimized 作用域内可见
@@ -33,10 +38,21 @@ fn convert_by_type(s: &str, field_type: &FieldType) -> Result<Value, String> {
             }
         }
 
+        // NaN/inf 能被 parse 接受，但会让范围比较全部失效，必须拒绝
         FieldType::Float => s
             .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
             .map(Value::Float)
-            .map_err(|_| format!("'{}' is not a valid float", s)),
+            .ok_or_else(|| format!("'{}' is not a valid float", s)),
+
+        FieldType::Date | FieldType::DateTime => parse_temporal(s, field_type),
+
+        // 查询串/表单是扁平的键值对，无法承载嵌套对象（JSON 请求体走 json_body_to_value）
+        FieldType::Object => Err(format!(
+            "'{}' cannot be an object; nested fields require a JSON body",
+            s
+        )),
 
         // String 类型及其他默认走这里
         _ => Ok(Value::String(s.to_owned())),
@@ -44,6 +60,45 @@ fn convert_by_type(s: &str, field_type: &FieldType) -> Result<Value, String> {
     res
 }
 
+/// 用 chrono 严格解析日期/时间，并规范化为 zz-validator 接受的格式
+/// - date: `YYYY-MM-DD`
+/// - datetime: RFC 3339（可带时区偏移），无时区时按 UTC 处理，统一转为 `YYYY-MM-DDTHH:MM:SSZ`
+fn parse_temporal(s: &str, field_type: &FieldType) -> Result<Value, String> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+
+    let s = s.trim();
+    match field_type {
+        FieldType::Date => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(|d| Value::String(d.format("%Y-%m-%d").to_string()))
+            .map_err(|_| format!("'{}' is not a valid date", s)),
+        _ => DateTime::parse_from_rfc3339(s)
+            .map(|dt| dt.with_timezone(&Utc))
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").map(|dt| dt.and_utc())
+            })
+            .map(|dt| Value::String(dt.to_rfc3339_opts(SecondsFormat::Secs, true)))
+            .map_err(|_| format!("'{}' is not a valid datetime", s)),
+    }
+}
+
+/// JSON 中的日期字段同样经过 chrono 校验与规范化
+fn normalize_json_temporal(value: &mut Value, rules: &[FieldRule]) -> Result<(), String> {
+    let Value::Object(obj) = value else {
+        return Ok(());
+    };
+    for rule in rules {
+        if !matches!(rule.field_type, FieldType::Date | FieldType::DateTime) {
+            continue;
+        }
+        if let Some(v) = obj.get_mut(&rule.field)
+            && let Value::String(s) = v
+        {
+            *v = parse_temporal(s, &rule.field_type)?;
+        }
+    }
+    Ok(())
+}
+
 /// 2. 优化后的值收集函数
 /// 返回 Result 以确保能够使用 ? 操作符进行短路返回（报错即停止）
 fn to_value_optimized<'a, I>(iter_provider: I, rules: &[FieldRule]) -> Result<Value, String>
@@ -56,11 +111,16 @@ where
         let field_name = &rule.field;
         if let Some(values) = iter_provider(field_name) {
             if rule.is_array {
+                // 元素按 `array<T>` 中的 T 转换，未声明时按字符串处理
+                let elem_type = rule
+                    .rule
+                    .as_ref()
+                    .map_or(&FieldType::String, |r| &r.field_type);
                 // 修复 E0277 核心：明确显式声明 Result<Vec<Value>, String>
                 // 这样 collect 才知道如何将 Result 项聚合为带结果的集合
                 let converted: Result<Vec<Value>, String> = values
                     .iter()
-                    .map(|&s| convert_by_type(s, &rule.field_type))
+                    .map(|&s| convert_by_type(s, elem_type))
                     .collect();
 
                 obj.insert(field_name.clone(), Value::Array(converted?));
@@ -74,6 +134,47 @@ where
     Ok(Value::Object(obj))
 }
 
+/// JSON 转 DSL 的 Value；null 视为字段缺失
+/// 对象中的 null 视为字段缺失；数组元素不能缺失，null 元素报错而不是被丢弃
+fn json_to_value(json: serde_json::Value) -> Result<Option<Value>, String> {
+    Ok(match json {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some(Value::Bool(b)),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(Value::Int(i)),
+            None => n.as_f64().map(Value::Float),
+        },
+        serde_json::Value::String(s) => Some(Value::String(s)),
+        serde_json::Value::Array(arr) => Some(Value::Array(
+            arr.into_iter()
+                .map(|v| json_to_value(v)?.ok_or_else(|| "null array element".to_string()))
+                .collect::<Result<_, _>>()?,
+        )),
+        serde_json::Value::Object(map) => {
+            let mut obj = HashMap::with_capacity(map.len());
+            for (k, v) in map {
+                if let Some(v) = json_to_value(v)? {
+                    obj.insert(k, v);
+                }
+            }
+            Some(Value::Object(obj))
+        }
+    })
+}
+
+/// 解析 JSON 请求体，顶层必须是对象；空请求体视为空对象
+fn json_body_to_value(body: &[u8]) -> Result<Value, String> {
+    if body.trim_ascii().is_empty() {
+        return Ok(Value::Object(HashMap::new()));
+    }
+    let json: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| format!("invalid JSON: {}", e))?;
+    match json_to_value(json)? {
+        Some(v @ Value::Object(_)) => Ok(v),
+        _ => Err("JSON body must be an object".to_string()),
+    }
+}
+
 pub fn value_to_string(v: Value) -> String {
     match v {
         Value::Bool(b) => {
@@ -98,14 +199,542 @@ pub fn value_to_string(v: Value) -> String {
     }
 }
 
+/// 两端都是整数的区间约束
+fn int_range(con: &Constraint) -> Option<(i64, i64, bool, bool)> {
+    match con {
+        Constraint::Range {
+            min: Value::Int(min),
+            max: Value::Int(max),
+            min_inclusive,
+            max_inclusive,
+        } => Some((*min, *max, *min_inclusive, *max_inclusive)),
+        _ => None,
+    }
+}
+
+/// 区间的错误描述；`fill_open_ranges` 补上的 i64 极值视为未设置，只输出设置了的一端
+fn int_bounds_str(min: i64, max: i64, min_inclusive: bool, max_inclusive: bool) -> String {
+    let lower =
+        (min != i64::MIN).then(|| format!("{} {}", if min_inclusive { ">=" } else { ">" }, min));
+    let upper =
+        (max != i64::MAX).then(|| format!("{} {}", if max_inclusive { "<=" } else { "<" }, max));
+    match (lower, upper) {
+        (Some(lower), Some(upper)) => format!("must be {} and {}", lower, upper),
+        (Some(bound), None) | (None, Some(bound)) => format!("must be {}", bound),
+        (None, None) => "out of range".to_string(),
+    }
+}
+
+fn is_plain_int(rule: &FieldRule) -> bool {
+    rule.field_type == FieldType::Int && rule.union_types.is_none()
+}
+
+/// zz-validator 把 i64 转成 f64 再比较，超过 2^53 时会误判；
+/// 从交给它的规则中移除整数区间（含嵌套对象与数组元素）
+fn strip_int_ranges(rule: &mut FieldRule) {
+    if is_plain_int(rule)
+        && let Some(c) = rule.constraints.as_mut()
+    {
+        c.items.retain(|con| int_range(con).is_none());
+    }
+    if let Some(sub) = rule.rule.as_mut() {
+        strip_int_ranges(sub);
+    }
+    rule.children
+        .iter_mut()
+        .flatten()
+        .for_each(strip_int_ranges);
+}
+
+/// 以 i64 精确校验整数区间，遍历方式与 zz-validator 的 validate_field 一致
+fn check_int_ranges(value: &Value, rule: &FieldRule) -> Result<(), String> {
+    let val = match value {
+        Value::Object(obj) => match obj.get(&rule.field) {
+            Some(v) => v,
+            None => return Ok(()),
+        },
+        v => v,
+    };
+
+    if is_plain_int(rule)
+        && let Value::Int(i) = val
+    {
+        let ranges = rule.constraints.iter().flat_map(|c| &c.items);
+        for (min, max, min_inc, max_inc) in ranges.filter_map(int_range) {
+            let min_ok = if min_inc { *i >= min } else { *i > min };
+            let max_ok = if max_inc { *i <= max } else { *i < max };
+            if !min_ok || !max_ok {
+                return Err(format!(
+                    "{} value {} {}",
+                    rule.field,
+                    i,
+                    int_bounds_str(min, max, min_inc, max_inc)
+                ));
+            }
+        }
+    }
+
+    if let Some(sub) = &rule.rule {
+        match val {
+            Value::Array(arr) => arr.iter().try_for_each(|v| check_int_ranges(v, sub))?,
+            Value::Object(_) => check_int_ranges(val, sub)?,
+            _ => {}
+        }
+    }
+    if let (Some(children), Value::Object(_)) = (&rule.children, val) {
+        children.iter().try_for_each(|c| check_int_ranges(val, c))?;
+    }
+    Ok(())
+}
+
+/// `array<T>[min,max]` 中作用于数组长度（而非元素）的区间
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayLength {
+    /// 字段路径，嵌套字段如 `profile.tags`、`items[].tags`
+    pub field: String,
+    pub min: usize,
+    pub max: usize,
+    pub min_inclusive: bool,
+    pub max_inclusive: bool,
+}
+
+impl ArrayLength {
+    pub fn check(&self, len: usize) -> Result<(), String> {
+        if in_bounds(
+            len,
+            self.min,
+            self.max,
+            self.min_inclusive,
+            self.max_inclusive,
+        ) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Field '{}' array length {} out of range {}",
+                self.field,
+                len,
+                bounds_str(self.min, self.max, self.min_inclusive, self.max_inclusive),
+            ))
+        }
+    }
+
+    /// 在请求值中找到该字段（含嵌套对象与数组元素）并检查长度
+    pub fn check_value(&self, value: &Value) -> Result<(), String> {
+        visit_path(value, &self.field, &mut |v| match v {
+            Value::Array(arr) => self.check(arr.len()),
+            _ => Ok(()),
+        })
+    }
+
+    /// 解析 `[1,5]`、`(0,10]` 这样的区间
+    fn parse(field: &str, range: &str) -> Option<Self> {
+        let (min, max, min_inclusive, max_inclusive) = parse_bounds(range)?;
+        Some(Self {
+            field: field.to_string(),
+            min,
+            max,
+            min_inclusive,
+            max_inclusive,
+        })
+    }
+}
+
+/// 字符串长度的计量方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// Unicode 标量值个数
+    Chars,
+    /// UTF-8 字节数
+    Bytes,
+}
+
+/// `string chars[2,20]` / `string bytes[2,20]` 显式指定的字符串长度区间；
+/// 未加限定的 `string[2,20]` 仍由 zz-validator 按字节数校验
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLength {
+    /// 字段路径，格式同 [`ArrayLength::field`]
+    pub field: String,
+    pub unit: LengthUnit,
+    pub min: usize,
+    pub max: usize,
+    pub min_inclusive: bool,
+    pub max_inclusive: bool,
+}
+
+impl StringLength {
+    pub fn check(&self, s: &str) -> Result<(), String> {
+        let (len, unit) = match self.unit {
+            LengthUnit::Chars => (s.chars().count(), "char"),
+            LengthUnit::Bytes => (s.len(), "byte"),
+        };
+        if in_bounds(
+            len,
+            self.min,
+            self.max,
+            self.min_inclusive,
+            self.max_inclusive,
+        ) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Field '{}' {} length {} out of range {}",
+                self.field,
+                unit,
+                len,
+                bounds_str(self.min, self.max, self.min_inclusive, self.max_inclusive),
+            ))
+        }
+    }
+
+    /// 在请求值中找到该字段（含嵌套对象与数组元素）并检查长度
+    pub fn check_value(&self, value: &Value) -> Result<(), String> {
+        visit_path(value, &self.field, &mut |v| match v {
+            Value::String(s) => self.check(s),
+            _ => Ok(()),
+        })
+    }
+}
+
+/// 解析 `[1,5]`、`(0,10]` 这样的非负整数区间
+fn parse_bounds(range: &str) -> Option<(usize, usize, bool, bool)> {
+    let min_inclusive = range.starts_with('[');
+    let max_inclusive = range.ends_with(']');
+    let (min, max) = range[1..range.len() - 1].split_once(',')?;
+    Some((
+        min.trim().parse().ok()?,
+        max.trim().parse().ok()?,
+        min_inclusive,
+        max_inclusive,
+    ))
+}
+
+fn in_bounds(len: usize, min: usize, max: usize, min_inclusive: bool, max_inclusive: bool) -> bool {
+    let min_ok = if min_inclusive { len >= min } else { len > min };
+    let max_ok = if max_inclusive { len <= max } else { len < max };
+    min_ok && max_ok
+}
+
+fn bounds_str(min: usize, max: usize, min_inclusive: bool, max_inclusive: bool) -> String {
+    format!(
+        "{}{}, {}{}",
+        if min_inclusive { '[' } else { '(' },
+        min,
+        max,
+        if max_inclusive { ']' } else { ')' },
+    )
+}
+
+/// 取出 `name:` / `name?:` 前缀中的字段名
+fn trailing_field_name(head: &str) -> &str {
+    let head = head.trim_end();
+    let head = head.strip_suffix(':').unwrap_or(head).trim_end();
+    let head = head.strip_suffix('?').unwrap_or(head);
+    let start = head
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &head[start..]
+}
+
+/// 计算 DSL 片段末尾所处类型对应的字段路径：对象子字段以 `.` 连接，
+/// 数组元素记为 `[]`，如 `(items:array<object(name:string` → `items[].name`
+fn field_path(dsl: &str) -> String {
+    // (子字段的前缀, 当前类型所属的路径)
+    let (mut base, mut target) = (String::new(), String::new());
+    let mut frames: Vec<(String, String)> = Vec::new();
+    let mut rest = dsl;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let (word, after) = rest.split_at(end);
+            let next = after.trim_start();
+            if next.starts_with([':', '?']) {
+                target = if base.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{}.{}", base, word)
+                };
+            } else if word == "object" && next.starts_with('(') {
+                frames.push((base.clone(), target.clone()));
+                base = target.clone();
+                rest = &next[1..];
+                continue;
+            } else if word == "array" && next.starts_with('<') {
+                frames.push((base.clone(), target.clone()));
+                target.push_str("[]");
+                rest = &next[1..];
+                continue;
+            }
+            rest = after;
+            continue;
+        }
+        match c {
+            '"' => {
+                rest = &rest[quoted_len(rest)..];
+                continue;
+            }
+            ')' | '>' => {
+                if let Some((b, t)) = frames.pop() {
+                    (base, target) = (b, t);
+                }
+            }
+            // 最外层的 `(` 包裹字段列表，其余 `(` / `[` 是区间或 regex 等的参数
+            '(' if frames.is_empty() && target.is_empty() => {}
+            '(' | '[' => {
+                let mut tail = &rest[1..];
+                while let Some(pos) = tail.find(['"', ')', ']']) {
+                    if tail[pos..].starts_with('"') {
+                        tail = &tail[pos + quoted_len(&tail[pos..])..];
+                    } else {
+                        tail = &tail[pos..];
+                        break;
+                    }
+                }
+                rest = tail.get(1..).unwrap_or("");
+                continue;
+            }
+            _ => {}
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    target
+}
+
+/// 按 [`field_path`] 生成的路径取值并逐个检查；路径上缺失或类型不符的值跳过
+fn visit_path<'a>(
+    value: &'a Value,
+    path: &str,
+    check: &mut impl FnMut(&'a Value) -> Result<(), String>,
+) -> Result<(), String> {
+    if path.is_empty() {
+        return check(value);
+    }
+    if let Some(rest) = path.strip_prefix("[]") {
+        let rest = rest.strip_prefix('.').unwrap_or(rest);
+        return match value {
+            Value::Array(arr) => arr.iter().try_for_each(|v| visit_path(v, rest, check)),
+            _ => Ok(()),
+        };
+    }
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    let (name, rest) = path.split_at(end);
+    let rest = rest.strip_prefix('.').unwrap_or(rest);
+    match value {
+        Value::Object(obj) => match obj.get(name) {
+            Some(v) => visit_path(v, rest, check),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// `tail` 以引号开头时，返回含两侧引号的字面量长度；未闭合时取到末尾
+fn quoted_len(tail: &str) -> usize {
+    let mut escaped = false;
+    tail[1..]
+        .char_indices()
+        .find(|&(_, c)| {
+            let close = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            close
+        })
+        .map_or(tail.len(), |(i, _)| i + 2)
+}
+
+/// 单边区间缺失一侧的取值：(下界, 上界)，按区间前的类型决定
+fn open_range_bounds(kind: &str) -> Option<(String, String)> {
+    match kind {
+        "int" => Some((i64::MIN.to_string(), i64::MAX.to_string())),
+        "float" => Some((format!("{:e}", f64::MIN), format!("{:e}", f64::MAX))),
+        "string" => Some(("0".into(), i64::MAX.to_string())),
+        // `array<T>` 之后的区间是数组长度，`chars` / `bytes` 之后是字符串长度
+        ">" | "chars" | "bytes" => Some(("0".into(), usize::MAX.to_string())),
+        _ => None,
+    }
+}
+
+/// zz-validator 的区间要求两端都有值，这里把 `int[0,]`、`string[,20]` 这样的
+/// 单边区间补全为该类型的极值；补上的一侧总是闭区间
+pub fn fill_open_ranges(dsl: &str) -> String {
+    let mut out = String::with_capacity(dsl.len());
+    let mut rest = dsl;
+
+    while let Some(pos) = rest.find(['"', '[', '(']) {
+        let (head, tail) = rest.split_at(pos);
+        out.push_str(head);
+
+        // 引号内的内容（如正则）原样保留
+        if tail.starts_with('"') {
+            let end = quoted_len(tail);
+            out.push_str(&tail[..end]);
+            rest = &tail[end..];
+            continue;
+        }
+
+        let before = out.trim_end();
+        let kind = if before.ends_with('>') {
+            ">"
+        } else {
+            trailing_field_name(before)
+        };
+        let filled = tail.find([']', ')']).and_then(|close| {
+            let (min, max) = tail[1..close].split_once(',')?;
+            let (min, max) = (min.trim(), max.trim());
+            if !min.is_empty() && !max.is_empty() || max.contains(',') {
+                return None;
+            }
+            let (lo, hi) = open_range_bounds(kind)?;
+            let (open, min) = if min.is_empty() {
+                ('[', lo)
+            } else {
+                (tail.as_bytes()[0] as char, min.to_string())
+            };
+            let (close_c, max) = if max.is_empty() {
+                (']', hi)
+            } else {
+                (tail.as_bytes()[close] as char, max.to_string())
+            };
+            Some((format!("{}{},{}{}", open, min, max, close_c), close))
+        });
+        match filled {
+            Some((range, close)) => {
+                out.push_str(&range);
+                rest = &tail[close + 1..];
+            }
+            None => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// zz-validator 的区间只作用于标量，这里把 `array<T>` 之后的区间剥离出来，
+/// 作为数组长度约束单独校验；`array<int[0,100]>` 内部的区间仍然属于元素。
+/// 嵌套在 `object(...)` 或数组元素中的数组按完整路径记录
+pub fn extract_array_lengths(dsl: &str) -> (String, Vec<ArrayLength>) {
+    let mut out = String::with_capacity(dsl.len());
+    let mut lengths = Vec::new();
+    let mut rest = dsl;
+
+    // `>` 只会用来闭合 `array<`，其后的区间即为数组长度
+    while let Some(pos) = rest.find(['"', '>']) {
+        let (head, tail) = rest.split_at(pos);
+        out.push_str(head);
+
+        if tail.starts_with('"') {
+            let end = quoted_len(tail);
+            out.push_str(&tail[..end]);
+            rest = &tail[end..];
+            continue;
+        }
+        out.push('>');
+        rest = &tail[1..];
+
+        let after = rest.trim_start();
+        if after.starts_with(['[', '('])
+            && let Some(close) = after.find([']', ')'])
+            && let Some(len) = ArrayLength::parse(&field_path(&out), &after[..=close])
+        {
+            lengths.push(len);
+            rest = &after[close + 1..];
+        }
+    }
+    out.push_str(rest);
+
+    (out, lengths)
+}
+
+/// 剥离 `string chars[..]` / `string bytes[..]` 中的长度限定，单独校验；
+/// 引号内的内容（如正则）原样保留，嵌套字段与数组元素按完整路径记录
+pub fn extract_string_lengths(dsl: &str) -> (String, Vec<StringLength>) {
+    let mut out = String::with_capacity(dsl.len());
+    let mut lengths = Vec::new();
+    let mut rest = dsl;
+
+    while let Some(pos) = rest.find(|c: char| c == '"' || c.is_alphabetic() || c == '_') {
+        let (head, tail) = rest.split_at(pos);
+        out.push_str(head);
+
+        if tail.starts_with('"') {
+            let end = quoted_len(tail);
+            out.push_str(&tail[..end]);
+            rest = &tail[end..];
+            continue;
+        }
+
+        let word_end = tail
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(tail.len());
+        let (word, after) = tail.split_at(word_end);
+        let unit = match word {
+            "chars" => Some(LengthUnit::Chars),
+            "bytes" => Some(LengthUnit::Bytes),
+            _ => None,
+        };
+        let range = after.trim_start();
+        // 只处理紧跟在 `string` 之后的限定，其余交给 Parser
+        let ty = out.trim_end();
+        if let Some(unit) = unit
+            && ty.ends_with("string")
+            && range.starts_with(['[', '('])
+            && let Some(close) = range.find([']', ')'])
+            && let Some((min, max, min_inclusive, max_inclusive)) = parse_bounds(&range[..=close])
+        {
+            lengths.push(StringLength {
+                field: field_path(ty),
+                unit,
+                min,
+                max,
+                min_inclusive,
+                max_inclusive,
+            });
+            out.truncate(out.trim_end().len());
+            rest = &range[close + 1..];
+        } else {
+            out.push_str(word);
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+
+    (out, lengths)
+}
+
+/// 校验失败（400）后如何处理连接
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// 请求体已完整读取，连接可继续复用（默认）
+    #[default]
+    KeepAlive,
+    /// 回复 `Connection: close` 并在响应后关闭连接
+    Close,
+}
+
 pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
+    to_validator_with(dsl_map, OnError::default())
+}
+
+/// 同 [`to_validator`]，并指定校验失败时的连接策略
+pub fn to_validator_with(dsl_map: AHashMap<String, String>, on_error: OnError) -> Arc<Executor> {
     // 1️⃣ 注册期：预解析规则
     let mut compiled_vec = Vec::new();
     for (source, dsl) in dsl_map {
         if !dsl.trim().is_empty() {
+            let (dsl, str_lengths) = extract_string_lengths(&fill_open_ranges(&dsl));
+            let (dsl, lengths) = extract_array_lengths(&dsl);
             match Parser::parse_rules(&dsl) {
                 Ok(rules) => {
-                    compiled_vec.push((source, rules));
+                    // 整数区间交给 check_int_ranges 精确比较，不经过 f64
+                    let mut lossy = rules.clone();
+                    lossy.iter_mut().for_each(strip_int_ranges);
+                    compiled_vec.push((source, lossy, rules, lengths, str_lengths));
                 }
                 Err(e) => {
                     tracing::error!("DSL Parse Error [{}]: {:?}", source, e);
@@ -119,6 +748,9 @@ pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
     exe!(|ctx, data| { data }, |ctx| {
         let compiled = compiled.clone();
 
+        // JSON 请求体由路由预先读取到 RawBody
+        let raw_body = ctx.local.get_ref::<RawBody>().map(|b| b.0.clone());
+
         // 获取 Metadata 原地修改
         let meta = ctx
             .local
@@ -130,7 +762,7 @@ pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
         let mut params = meta.params.clone().expect("AEX FATAL: HttpMetadata.params container must be pre-initialized by the protocol layer");
         let mut res = true;
 
-        for (source, rules) in compiled.as_ref() {
+        for (source, rules, exact, lengths, str_lengths) in compiled.as_ref() {
             // 2️⃣ 执行转换逻辑
             let value_result = match source.as_str() {
                 "params" => to_value_optimized(
@@ -143,6 +775,10 @@ pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
                     },
                     rules,
                 ),
+                "body" if meta.content_type.sub_type == SubMediaType::Json => {
+                    json_body_to_value(raw_body.as_deref().unwrap_or_default())
+                        .and_then(|mut v| normalize_json_temporal(&mut v, rules).map(|_| v))
+                }
                 "body" => to_value_optimized(
                     |key| {
                         params
@@ -170,12 +806,17 @@ pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
             // 3️⃣ 处理转换与校验结果
             match value_result {
                 Ok(mut value) => {
-                    // 执行 zz-validator 校验
-                    if let Err(e) = validate_object(&mut value, rules) {
+                    // 执行 zz-validator 校验，再检查数组与字符串长度
+                    let checked = validate_object(&mut value, rules)
+                        .map_err(|e| e.to_string())
+                        .and_then(|_| exact.iter().try_for_each(|r| check_int_ranges(&value, r)))
+                        .and_then(|_| lengths.iter().try_for_each(|l| l.check_value(&value)))
+                        .and_then(|_| str_lengths.iter().try_for_each(|l| l.check_value(&value)));
+                    if let Err(e) = checked {
                         let mut err_msg = String::with_capacity(64);
                         err_msg.push_str(source);
                         err_msg.push_str(" validate error: ");
-                        err_msg.push_str(&e.to_string());
+                        err_msg.push_str(&e);
 
                         meta.status = StatusCode::BadRequest;
                         meta.body = err_msg.into_bytes();
@@ -183,8 +824,10 @@ pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
                         break;
                     }
 
+                    // JSON 保持原始结构，不回写到 form
                     if let Value::Object(obj) = value {
                         match source.as_str() {
+                            "body" if meta.content_type.sub_type == SubMediaType::Json => {}
                             "query" => {
                                 for (k, v) in obj {
                                     params.query.insert(
@@ -239,6 +882,8 @@ pub fn to_validator(dsl_map: AHashMap<String, String>) -> Arc<Executor> {
         // 4️⃣ 统一写回 Params
         if res {
             meta.params = Some(params);
+        } else if on_error == OnError::Close {
+            meta.headers.insert(HeaderKey::Connection, "close");
         }
 
         res
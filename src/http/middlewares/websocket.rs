@@ -2,26 +2,41 @@ use crate::{
     connection::context::Context,
     http::{
         meta::HttpMetadata,
-        protocol::{header::HeaderKey, header::Headers, method::HttpMethod},
+        protocol::{header::HeaderKey, header::Headers, method::HttpMethod, status::StatusCode},
         types::Executor,
-        websocket::{BinaryHandler, TextHandler, WSCodec, WSFrame},
+        websocket::{
+            BinaryHandler, BoundedWSCodec, HandlerAction, MessageDeflater, MessageInflater,
+            PerMessageDeflate, TextHandler, WSCloseError, WSExtensionOffers, WSFrame,
+        },
     },
 };
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD;
+use dashmap::DashMap;
 use futures::{FutureExt, StreamExt};
+use serde::{Serialize, de::DeserializeOwned};
 use sha1::{Digest, Sha1};
 use std::{
+    collections::HashMap,
+    net::IpAddr,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
     task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
 };
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
 use tokio::sync::Mutex;
 use tokio_util::codec::Framed;
+use tokio_util::sync::CancellationToken;
 
 use futures::future::BoxFuture;
 
+/// 写任务单次合并写出的最大帧数
+const WRITE_BATCH: usize = 64;
+
 /// 用于组合 Context 中的 reader 和 writer
 struct CombinedStream {
     reader: Box<dyn tokio::io::AsyncRead + Send + Unpin>,
@@ -53,6 +68,176 @@ impl WsSenderList {
     }
 }
 
+/// WebSocket 连接 id，在同一个 GlobalContext 内唯一
+pub type ConnId = u64;
+
+/// 当前连接的 id，握手后写入 `ctx.local`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WsConnId(pub ConnId);
+
+/// 连接级状态槽，由 `with_state` 在升级时初始化；处理器执行期间取出，以 `&mut S` 传入
+struct WsState<S>(Option<S>);
+
+/// 升级后初始化连接状态
+type StateInit = Arc<dyn Fn(&mut Context) + Send + Sync>;
+
+/// 当前连接的写端句柄，握手后写入 `ctx.local`。
+///
+/// 可克隆并在处理器返回后继续持有，用于主动推送；
+/// 连接断开后所有发送返回 false。
+#[derive(Clone)]
+pub struct WebSocketSender {
+    tx: tokio::sync::mpsc::UnboundedSender<WSFrame>,
+    /// 文本与二进制消息超过该字节数时自动分片发送
+    fragment_size: Option<usize>,
+}
+
+impl WebSocketSender {
+    pub fn new(tx: tokio::sync::mpsc::UnboundedSender<WSFrame>) -> Self {
+        Self {
+            tx,
+            fragment_size: None,
+        }
+    }
+
+    /// 开启自动分片，`send_text` / `send_binary` 超过 `size` 字节时按 `size` 切分
+    pub fn with_fragment_size(mut self, size: usize) -> Self {
+        self.fragment_size = Some(size);
+        self
+    }
+
+    /// 从 `ctx.local` 取出当前连接的句柄
+    pub fn from_ctx(ctx: &Context) -> Option<Self> {
+        ctx.local.get_value::<Self>()
+    }
+
+    pub fn send(&self, frame: WSFrame) -> bool {
+        self.tx.send(frame).is_ok()
+    }
+
+    pub fn send_text(&self, text: impl Into<String>) -> bool {
+        let text = text.into();
+        match self.fragment_size {
+            Some(size) if text.len() > size => self.send_fragmented(0x1, text.into_bytes(), size),
+            _ => self.send(WSFrame::Text(text)),
+        }
+    }
+
+    pub fn send_binary(&self, data: impl Into<Vec<u8>>) -> bool {
+        let data = data.into();
+        match self.fragment_size {
+            Some(size) if data.len() > size => self.send_fragmented(0x2, data, size),
+            _ => self.send(WSFrame::Binary(data)),
+        }
+    }
+
+    /// 将一条消息按 `chunk_size` 字节拆成首帧（FIN=0）、后续帧与末帧（FIN=1）发送；
+    /// opcode 为 0x1（文本）或 0x2（二进制）
+    pub fn send_fragmented(
+        &self,
+        opcode: u8,
+        payload: impl Into<Vec<u8>>,
+        chunk_size: usize,
+    ) -> bool {
+        self.send(WSFrame::Fragmented(opcode, chunk_size, payload.into()))
+    }
+
+    /// 序列化为 JSON 后以文本帧发送，序列化失败返回 false
+    pub fn send_json<T: Serialize>(&self, value: &T) -> bool {
+        match serde_json::to_string(value) {
+            Ok(text) => self.send_text(text),
+            Err(_) => false,
+        }
+    }
+
+    pub fn send_ping(&self, payload: impl Into<Vec<u8>>) -> bool {
+        self.send(WSFrame::Ping(payload.into()))
+    }
+
+    /// 发送关闭帧，之后写任务不再发送任何数据
+    pub fn close(&self, code: u16, reason: Option<&str>) -> bool {
+        self.send(WSFrame::Close(code, reason.map(|r| r.to_string())))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// 按连接 id 索引的 WebSocket 写端注册表，支持广播与定向发送。
+///
+/// 握手后 `run` 会把当前连接注册进来，并把 hub 写入 `ctx.local`，
+/// 处理器可通过 `ctx.local.get_value::<WebSocketHub>()` 取得。
+#[derive(Clone, Default)]
+pub struct WebSocketHub {
+    next_id: Arc<AtomicU64>,
+    peers: Arc<Mutex<HashMap<ConnId, tokio::sync::mpsc::UnboundedSender<WSFrame>>>>,
+}
+
+impl WebSocketHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个连接的写端，返回分配的 id
+    pub async fn register(&self, tx: tokio::sync::mpsc::UnboundedSender<WSFrame>) -> ConnId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peers.lock().await.insert(id, tx);
+        id
+    }
+
+    pub async fn unregister(&self, id: ConnId) {
+        self.peers.lock().await.remove(&id);
+    }
+
+    /// 向所有连接广播，顺带清理已断开的连接，返回成功投递数
+    pub async fn broadcast(&self, frame: WSFrame) -> usize {
+        let mut peers = self.peers.lock().await;
+        peers.retain(|_, tx| tx.send(frame.clone()).is_ok());
+        peers.len()
+    }
+
+    /// 向除 `except` 之外的所有连接广播，返回成功投递数
+    pub async fn broadcast_except(&self, except: ConnId, frame: WSFrame) -> usize {
+        let mut sent = 0;
+        let mut peers = self.peers.lock().await;
+        peers.retain(|id, tx| {
+            if *id == except {
+                return true;
+            }
+            let ok = tx.send(frame.clone()).is_ok();
+            sent += ok as usize;
+            ok
+        });
+        sent
+    }
+
+    /// 向指定连接发送，连接不存在或已断开时返回 false
+    pub async fn send_to(&self, id: ConnId, frame: WSFrame) -> bool {
+        let mut peers = self.peers.lock().await;
+        match peers.get(&id) {
+            Some(tx) if tx.send(frame).is_ok() => true,
+            Some(_) => {
+                peers.remove(&id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub async fn len(&self) -> usize {
+        self.peers.lock().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.peers.lock().await.is_empty()
+    }
+
+    pub async fn ids(&self) -> Vec<ConnId> {
+        self.peers.lock().await.keys().copied().collect()
+    }
+}
+
 impl AsyncRead for CombinedStream {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -82,39 +267,320 @@ impl AsyncWrite for CombinedStream {
     }
 }
 
+/// WebSocket 流量统计的某一时刻快照
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WsMetricsSnapshot {
+    pub frames_received: u64,
+    pub frames_sent: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    pub active_connections: usize,
+}
+
+/// 同一个 `WebSocket`（及其克隆）下所有连接共享的计数器；字节数只计负载
+#[derive(Debug, Default)]
+struct WsMetrics {
+    frames_received: AtomicU64,
+    frames_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    active_connections: AtomicUsize,
+}
+
+impl WsMetrics {
+    fn record_received(&self, frame: &WSFrame) {
+        self.frames_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(frame.payload_len() as u64, Ordering::Relaxed);
+    }
+
+    fn record_sent(&self, payload_len: usize) {
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent
+            .fetch_add(payload_len as u64, Ordering::Relaxed);
+    }
+}
+
+/// 连接存活期间计入 active_connections
+struct ActiveGuard(Arc<WsMetrics>);
+
+impl ActiveGuard {
+    fn new(metrics: Arc<WsMetrics>) -> Self {
+        metrics.active_connections.fetch_add(1, Ordering::Relaxed);
+        Self(metrics)
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 #[derive(Clone)]
 pub struct WebSocket {
     pub on_text: Option<TextHandler>,
     pub on_binary: Option<BinaryHandler>,
+    /// 允许握手的 Origin 列表，None 表示不限制
+    pub allowed_origins: Option<Vec<String>>,
+    /// 窗口内允许的 Ping 数量，超出后以 1008 关闭；None 表示不限制
+    pub ping_limit: Option<(u32, Duration)>,
+    /// 同一 IP 允许的并发连接数，None 表示不限制
+    pub max_per_ip: Option<usize>,
+    /// 发送的文本/二进制消息超过该字节数时自动分片，None 表示总是单帧
+    pub fragment_size: Option<usize>,
+    /// 启用 permessage-deflate 时服务端要求的参数，None 表示不协商压缩
+    pub permessage_deflate: Option<PerMessageDeflate>,
+    /// 服务端发起关闭后等待对端回显关闭帧的最长时间
+    pub close_timeout: Duration,
+    /// 每个连接升级时初始化的状态，见 `with_state`
+    state_init: Option<StateInit>,
+    /// 各 IP 当前的连接数，克隆后共享
+    ip_counts: Arc<DashMap<IpAddr, usize>>,
+    metrics: Arc<WsMetrics>,
+}
+
+/// 持有一个 IP 的连接名额，释放时计数减一
+struct IpSlot {
+    ip: IpAddr,
+    counts: Arc<DashMap<IpAddr, usize>>,
+}
+
+impl Drop for IpSlot {
+    fn drop(&mut self) {
+        self.counts.remove_if_mut(&self.ip, |_, n| {
+            *n -= 1;
+            *n == 0
+        });
+    }
 }
 
 impl WebSocket {
+    /// 默认每秒最多回复 32 个 Ping
+    pub const DEFAULT_PING_LIMIT: (u32, Duration) = (32, Duration::from_secs(1));
+    /// 默认等待关闭回显 5 秒
+    pub const DEFAULT_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
     pub fn new() -> Self {
         Self {
             on_text: None,
             on_binary: None,
+            allowed_origins: None,
+            ping_limit: Some(Self::DEFAULT_PING_LIMIT),
+            max_per_ip: None,
+            fragment_size: None,
+            permessage_deflate: None,
+            close_timeout: Self::DEFAULT_CLOSE_TIMEOUT,
+            state_init: None,
+            ip_counts: Arc::new(DashMap::new()),
+            metrics: Arc::new(WsMetrics::default()),
+        }
+    }
+
+    /// 收发帧数、负载字节数与当前连接数的快照
+    pub fn metrics(&self) -> WsMetricsSnapshot {
+        let m = &self.metrics;
+        WsMetricsSnapshot {
+            frames_received: m.frames_received.load(Ordering::Relaxed),
+            frames_sent: m.frames_sent.load(Ordering::Relaxed),
+            bytes_received: m.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: m.bytes_sent.load(Ordering::Relaxed),
+            active_connections: m.active_connections.load(Ordering::Relaxed),
         }
     }
 
-    /// 设置文本消息处理器
-    pub fn on_text<F>(mut self, handler: F) -> Self
+    /// 限制同一 IP 的并发连接数，超出时握手返回 429
+    pub fn max_connections_per_ip(mut self, max: usize) -> Self {
+        self.max_per_ip = Some(max);
+        self
+    }
+
+    /// 发送超过 `size` 字节的文本/二进制消息时按 `size` 分片
+    pub fn fragment_size(mut self, size: usize) -> Self {
+        self.fragment_size = Some(size);
+        self
+    }
+
+    /// 启用 permessage-deflate：客户端提议该扩展时协商压缩，`config` 中为 true 的
+    /// no_context_takeover 参数总会写入响应
+    pub fn permessage_deflate(mut self, config: PerMessageDeflate) -> Self {
+        self.permessage_deflate = Some(config);
+        self
+    }
+
+    /// 设置关闭握手的超时：发出关闭帧后最多等待该时长，随后直接断开
+    pub fn close_timeout(mut self, timeout: Duration) -> Self {
+        self.close_timeout = timeout;
+        self
+    }
+
+    /// 某个 IP 当前已升级的连接数
+    pub fn connections_from(&self, ip: IpAddr) -> usize {
+        self.ip_counts.get(&ip).map(|n| *n).unwrap_or(0)
+    }
+
+    /// 为 `ip` 占用一个名额，已满时返回 None
+    fn acquire_ip_slot(&self, ip: IpAddr) -> Option<IpSlot> {
+        let mut count = self.ip_counts.entry(ip).or_insert(0);
+        if self.max_per_ip.is_some_and(|max| *count >= max) {
+            return None;
+        }
+        *count += 1;
+        Some(IpSlot {
+            ip,
+            counts: self.ip_counts.clone(),
+        })
+    }
+
+    /// 设置 Ping 洪泛阈值：`window` 内超过 `max` 个 Ping 即关闭连接
+    pub fn ping_limit(mut self, max: u32, window: Duration) -> Self {
+        self.ping_limit = Some((max, window));
+        self
+    }
+
+    /// 关闭 Ping 洪泛保护，对每个 Ping 都回复 Pong
+    pub fn no_ping_limit(mut self) -> Self {
+        self.ping_limit = None;
+        self
+    }
+
+    /// 限制允许握手的 Origin（不区分大小写），其余来源返回 403
+    pub fn allowed_origins<I, S>(mut self, origins: I) -> Self
     where
-        F: Fn(&WebSocket, &mut Context, String) -> BoxFuture<'static, bool> + Send + Sync + 'static,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
     {
-        self.on_text = Some(Arc::new(handler));
+        self.allowed_origins = Some(origins.into_iter().map(Into::into).collect());
         self
     }
 
-    /// 设置二进制消息处理器
-    pub fn on_binary<F>(mut self, handler: F) -> Self
+    /// 请求的 Origin 是否允许握手；设置了白名单时缺少 Origin 视为不允许
+    pub fn origin_allowed(&self, headers: &Headers) -> bool {
+        let Some(allowed) = &self.allowed_origins else {
+            return true;
+        };
+        headers
+            .get(&HeaderKey::Origin)
+            .map(|origin| {
+                allowed
+                    .iter()
+                    .any(|a| a.eq_ignore_ascii_case(origin.trim()))
+            })
+            .unwrap_or(false)
+    }
+
+    /// 当前连接的 id，仅在 `run` 完成注册后可用
+    pub fn conn_id(ctx: &Context) -> Option<ConnId> {
+        ctx.local.get_value::<WsConnId>().map(|WsConnId(id)| id)
+    }
+
+    /// 客户端在握手时提出的扩展，升级后可用；未携带该头时为空列表
+    pub fn extensions(ctx: &Context) -> Option<&WSExtensionOffers> {
+        ctx.local.get_ref::<WSExtensionOffers>()
+    }
+
+    /// 设置文本消息处理器；返回 `bool` 或 [`HandlerAction`]
+    pub fn on_text<F, R>(mut self, handler: F) -> Self
+    where
+        F: Fn(&WebSocket, &mut Context, String) -> BoxFuture<'static, R> + Send + Sync + 'static,
+        R: Into<HandlerAction> + 'static,
+    {
+        self.on_text = Some(Arc::new(move |ws, ctx, text| {
+            handler(ws, ctx, text).map(Into::into).boxed()
+        }));
+        self
+    }
+
+    /// 设置 JSON 消息处理器：文本帧解码为 `T` 后交给 handler，
+    /// 解码失败时以 1007 关闭连接。与 `on_text` 互相覆盖
+    pub fn on_json<T, F, R>(self, handler: F) -> Self
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(&WebSocket, &mut Context, T) -> BoxFuture<'static, R> + Send + Sync + 'static,
+        R: Into<HandlerAction> + 'static,
+    {
+        self.on_text(
+            move |ws, ctx, text| match serde_json::from_str::<T>(&text) {
+                Ok(msg) => handler(ws, ctx, msg).map(Into::into).boxed(),
+                Err(_) => async { HandlerAction::Close(1007, "Invalid JSON".into()) }.boxed(),
+            },
+        )
+    }
+
+    /// 设置二进制消息处理器；返回 `bool` 或 [`HandlerAction`]
+    pub fn on_binary<F, R>(mut self, handler: F) -> Self
+    where
+        F: Fn(&WebSocket, &mut Context, Vec<u8>) -> BoxFuture<'static, R> + Send + Sync + 'static,
+        R: Into<HandlerAction> + 'static,
+    {
+        self.on_binary = Some(Arc::new(move |ws, ctx, data| {
+            handler(ws, ctx, data).map(Into::into).boxed()
+        }));
+        self
+    }
+
+    /// 为每个连接分配一个类型化的状态，升级时由 `init` 创建，
+    /// 在整个连接期间保留，并以 `&mut S` 传给 `on_text_with_state` / `on_binary_with_state`
+    pub fn with_state<S, F>(mut self, init: F) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(&Context) -> S + Send + Sync + 'static,
+    {
+        self.state_init = Some(Arc::new(move |ctx: &mut Context| {
+            let state = init(ctx);
+            ctx.local.set_value(WsState(Some(state)));
+        }));
+        self
+    }
+
+    /// 取出连接状态交给 `f`，返回后立即放回；状态缺失时以 1011 关闭
+    fn with_conn_state<S, R>(
+        ctx: &mut Context,
+        f: impl FnOnce(&mut Context, &mut S) -> BoxFuture<'static, R>,
+    ) -> BoxFuture<'static, HandlerAction>
+    where
+        S: Send + Sync + 'static,
+        R: Into<HandlerAction> + 'static,
+    {
+        let Some(mut state) = ctx.local.get_mut::<WsState<S>>().and_then(|s| s.0.take()) else {
+            tracing::error!(target: "aex", "WebSocket state missing; call with_state first");
+            return async { HandlerAction::Close(1011, "Missing connection state".into()) }.boxed();
+        };
+        let fut = f(ctx, &mut state);
+        if let Some(slot) = ctx.local.get_mut::<WsState<S>>() {
+            slot.0 = Some(state);
+        }
+        fut.map(Into::into).boxed()
+    }
+
+    /// 同 `on_text`，并传入 `with_state` 创建的连接状态
+    pub fn on_text_with_state<S, F, R>(self, handler: F) -> Self
     where
-        F: Fn(&WebSocket, &mut Context, Vec<u8>) -> BoxFuture<'static, bool>
+        S: Send + Sync + 'static,
+        F: Fn(&WebSocket, &mut Context, &mut S, String) -> BoxFuture<'static, R>
             + Send
             + Sync
             + 'static,
+        R: Into<HandlerAction> + 'static,
     {
-        self.on_binary = Some(Arc::new(handler));
-        self
+        self.on_text(move |ws, ctx, text| {
+            Self::with_conn_state(ctx, |ctx, state| handler(ws, ctx, state, text))
+        })
+    }
+
+    /// 同 `on_binary`，并传入 `with_state` 创建的连接状态
+    pub fn on_binary_with_state<S, F, R>(self, handler: F) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(&WebSocket, &mut Context, &mut S, Vec<u8>) -> BoxFuture<'static, R>
+            + Send
+            + Sync
+            + 'static,
+        R: Into<HandlerAction> + 'static,
+    {
+        self.on_binary(move |ws, ctx, data| {
+            Self::with_conn_state(ctx, |ctx, state| handler(ws, ctx, state, data))
+        })
     }
 
     /// 设置统一的帧处理器 (兼容旧API)
@@ -138,33 +604,51 @@ impl WebSocket {
             .get(&HeaderKey::Upgrade)
             .map(|v| v.eq_ignore_ascii_case("websocket"))
             .unwrap_or(false);
+        // Connection 是逗号分隔的 token 列表，如 `keep-alive, Upgrade`，需整词匹配
         let connection = headers
             .get(&HeaderKey::Connection)
-            .map(|v| v.to_ascii_lowercase().contains("upgrade"))
+            .map(|v| {
+                v.split(',')
+                    .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+            })
             .unwrap_or(false);
         upgrade && connection
     }
 
-    /// 完成 WebSocket 握手
+    /// `Sec-WebSocket-Key` 必须是 16 字节随机值的 base64 编码（RFC 6455 4.1）
+    pub fn is_valid_key(key: &str) -> bool {
+        STANDARD
+            .decode(key.trim())
+            .is_ok_and(|decoded| decoded.len() == 16)
+    }
+
+    /// 完成 WebSocket 握手；`extensions` 为协商成功的 `Sec-WebSocket-Extensions` 响应值
     pub async fn handshake(
         writer: &mut (dyn AsyncWrite + Send + Unpin),
         headers: &Headers,
+        extensions: Option<&str>,
     ) -> anyhow::Result<()> {
         let key = headers
             .get(&HeaderKey::SecWebSocketKey)
             .ok_or_else(|| anyhow::anyhow!("missing Sec-WebSocket-Key"))?;
+        if !Self::is_valid_key(key) {
+            anyhow::bail!("invalid Sec-WebSocket-Key");
+        }
 
         let mut sha = Sha1::new();
         sha.update(key.as_bytes());
         sha.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
         let accept_key = STANDARD.encode(sha.finalize());
 
+        let extensions = extensions
+            .map(|e| format!("Sec-WebSocket-Extensions: {}\r\n", e))
+            .unwrap_or_default();
         let response = format!(
             "HTTP/1.1 101 Switching Protocols\r\n\
             Upgrade: websocket\r\n\
             Connection: Upgrade\r\n\
-            Sec-WebSocket-Accept: {}\r\n\r\n",
-            accept_key
+            Sec-WebSocket-Accept: {}\r\n{}\r\n",
+            accept_key, extensions
         );
 
         writer.write_all(response.as_bytes()).await?;
@@ -184,7 +668,7 @@ impl WebSocket {
             .ok_or_else(|| anyhow::anyhow!("Writer missing"))?;
 
         let io = CombinedStream { reader, writer };
-        let framed = Framed::new(io, WSCodec);
+        let framed = Framed::new(io, BoundedWSCodec::new(ctx.global.max_body_size));
 
         let (mut sink, mut stream) = framed.split();
 
@@ -198,58 +682,264 @@ impl WebSocket {
             }
         }
 
+        // 注册到 hub，分配连接 id
+        let hub = match ctx.global.get::<WebSocketHub>().await {
+            Some(hub) => hub,
+            None => {
+                let hub = WebSocketHub::new();
+                ctx.global.set(hub.clone()).await;
+                hub
+            }
+        };
+        let conn_id = hub.register(out_tx.clone()).await;
+        ctx.local.set_value(WsConnId(conn_id));
+        ctx.local.set_value(hub.clone());
+        let mut sender = WebSocketSender::new(out_tx.clone());
+        if let Some(size) = ws.fragment_size {
+            sender = sender.with_fragment_size(size);
+        }
+        ctx.local.set_value(sender);
+        if let Some(init) = &ws.state_init {
+            init(ctx);
+        }
+
+        let _active = ActiveGuard::new(ws.metrics.clone());
+
+        // 握手时协商的 permessage-deflate 参数：写任务压缩文本/二进制消息，读循环解压
+        let deflate = ctx.local.get_value::<PerMessageDeflate>();
+        let mut deflater = deflate.map(|d| MessageDeflater::new(d.server_no_context_takeover));
+        let inflater = deflate.map(|d| MessageInflater::new(d.client_no_context_takeover));
+
         // 后台写任务：将外部推送的消息发到 WebSocket
+        let metrics = ws.metrics.clone();
+        let close_sent = CancellationToken::new();
+        let close_signal = close_sent.clone();
         tokio::spawn(async move {
             use futures::SinkExt;
-            while let Some(frame) = out_rx.recv().await {
-                if let Err(e) = sink.send(frame).await {
+            'batch: while let Some(frame) = out_rx.recv().await {
+                // 合并已在队列中的帧，整批只 flush 一次，减少系统调用
+                let mut next = Some(frame);
+                let mut batched = 0;
+                while let Some(frame) = next.take() {
+                    let is_close = matches!(frame, WSFrame::Close(..));
+                    let frame = match (deflater.as_mut(), frame) {
+                        (Some(d), WSFrame::Text(text)) => match d.compress(text.as_bytes()) {
+                            Ok(data) => WSFrame::Compressed(0x1, data),
+                            Err(_) => WSFrame::Text(text),
+                        },
+                        (Some(d), WSFrame::Binary(data)) => match d.compress(&data) {
+                            Ok(compressed) => WSFrame::Compressed(0x2, compressed),
+                            Err(_) => WSFrame::Binary(data),
+                        },
+                        (_, frame) => frame,
+                    };
+                    let len = frame.payload_len();
+                    if let Err(e) = sink.feed(frame).await {
+                        tracing::debug!("WS send error: {:?}", e);
+                        break 'batch;
+                    }
+                    metrics.record_sent(len);
+                    // 关闭帧之后不再发送任何数据
+                    if is_close {
+                        let _ = sink.flush().await;
+                        close_signal.cancel();
+                        break 'batch;
+                    }
+                    batched += 1;
+                    if batched < WRITE_BATCH {
+                        next = out_rx.try_recv().ok();
+                    }
+                }
+                if let Err(e) = sink.flush().await {
                     tracing::debug!("WS send error: {:?}", e);
                     break;
                 }
             }
         });
 
-        while let Some(result) = stream.next().await {
-            let frame = match result {
-                Ok(f) => f,
+        let result = Self::read_loop(ws, ctx, &mut stream, &out_tx, &close_sent, inflater).await;
+        hub.unregister(conn_id).await;
+        result
+    }
+
+    async fn read_loop<S>(
+        ws: &WebSocket,
+        ctx: &mut Context,
+        stream: &mut S,
+        out_tx: &tokio::sync::mpsc::UnboundedSender<WSFrame>,
+        close_sent: &CancellationToken,
+        mut inflater: Option<MessageInflater>,
+    ) -> anyhow::Result<()>
+    where
+        S: futures::Stream<Item = anyhow::Result<WSFrame>> + Unpin,
+    {
+        let mut ping_window = (Instant::now(), 0u32);
+        let mut partial = None;
+        loop {
+            let result = tokio::select! {
+                result = stream.next() => match result {
+                    Some(result) => result,
+                    None => break,
+                },
+                // 服务端已发出关闭帧（如 sender.close）
+                _ = close_sent.cancelled() => {
+                    Self::await_close_echo(ws, stream).await;
+                    break;
+                }
+            };
+            let limit = ctx.global.max_body_size;
+            let frame = match result.and_then(|f| {
+                ws.metrics.record_received(&f);
+                Self::reassemble(f, &mut partial, limit)?
+                    .map(|f| Self::inflate(f, inflater.as_mut(), limit))
+                    .transpose()
+            }) {
+                Ok(Some(f)) => f,
+                // 分片消息尚未结束
+                Ok(None) => continue,
                 Err(e) => {
+                    // 协议错误先回复对应的关闭码（如非法 UTF-8 文本回 1007）
+                    if let Some(close) = e.downcast_ref::<WSCloseError>() {
+                        let _ = out_tx.send(WSFrame::Close(close.code, Some(close.reason.into())));
+                    }
                     return Err(anyhow::anyhow!("Protocol error: {}", e));
                 }
             };
 
-            let close_connection = match frame {
-                WSFrame::Text(text) => {
-                    if let Some(ref handler) = ws.on_text {
-                        handler(ws, ctx, text).await
-                    } else {
-                        true
-                    }
-                }
-                WSFrame::Binary(data) => {
-                    if let Some(ref handler) = ws.on_binary {
-                        handler(ws, ctx, data).await
-                    } else {
-                        true
-                    }
-                }
+            let action = match frame {
+                WSFrame::Text(text) => match ws.on_text {
+                    Some(ref handler) => handler(ws, ctx, text).await,
+                    None => HandlerAction::Continue,
+                },
+                WSFrame::Binary(data) => match ws.on_binary {
+                    Some(ref handler) => handler(ws, ctx, data).await,
+                    None => HandlerAction::Continue,
+                },
                 WSFrame::Ping(p) => {
+                    if let Some((max, window)) = ws.ping_limit {
+                        if ping_window.0.elapsed() > window {
+                            ping_window = (Instant::now(), 0);
+                        }
+                        ping_window.1 += 1;
+                        if ping_window.1 > max {
+                            let _ =
+                                out_tx.send(WSFrame::Close(1008, Some("Too many pings".into())));
+                            return Err(anyhow::anyhow!("Ping flood"));
+                        }
+                    }
                     let _ = out_tx.send(WSFrame::Pong(p));
-                    true
+                    HandlerAction::Continue
                 }
-                WSFrame::Close(_code, _reason) => {
-                    // 连接关闭，不回复
+                WSFrame::Close(code, _reason) => {
+                    // 对端发起关闭：回显关闭帧（1005 不得出现在线上，改回 1000），等写任务 flush 后断开
+                    let code = if code == 1005 { 1000 } else { code };
+                    let _ = out_tx.send(WSFrame::Close(code, None));
+                    let _ = tokio::time::timeout(ws.close_timeout, close_sent.cancelled()).await;
                     break;
                 }
-                _ => true,
+                _ => HandlerAction::Continue,
             };
 
-            if !close_connection {
+            if let HandlerAction::Close(code, reason) = action {
+                // 处理器要求断开：以其指定的关闭码发起关闭（已发过关闭帧时写任务会忽略）并等待回显
+                let reason = (!reason.is_empty()).then_some(reason);
+                let _ = out_tx.send(WSFrame::Close(code, reason));
+                Self::await_close_echo(ws, stream).await;
                 break;
             }
         }
         Ok(())
     }
 
+    /// 拼接分片消息，未结束时返回 None。
+    /// 分片未结束时收到新的数据帧、或没有进行中的消息时收到后续帧均以 1002 拒绝
+    fn reassemble(
+        frame: WSFrame,
+        partial: &mut Option<(u8, bool, Vec<u8>)>,
+        limit: usize,
+    ) -> anyhow::Result<Option<WSFrame>> {
+        let (fin, data) = match frame {
+            WSFrame::FragmentStart(opcode, rsv1, data) => {
+                if partial.is_some() {
+                    return Err(WSCloseError::new(1002, "Expected continuation frame").into());
+                }
+                if data.len() > limit {
+                    return Err(WSCloseError::new(1009, "Message too big").into());
+                }
+                *partial = Some((opcode, rsv1, data));
+                return Ok(None);
+            }
+            WSFrame::Continuation(data) => (false, data),
+            WSFrame::FragmentEnd(data) => (true, data),
+            WSFrame::Text(_) | WSFrame::Binary(_) | WSFrame::Compressed(..)
+                if partial.is_some() =>
+            {
+                return Err(WSCloseError::new(1002, "Expected continuation frame").into());
+            }
+            frame => return Ok(Some(frame)),
+        };
+
+        let Some((_, _, buf)) = partial.as_mut() else {
+            return Err(WSCloseError::new(1002, "Unexpected continuation frame").into());
+        };
+        if buf.len() + data.len() > limit {
+            return Err(WSCloseError::new(1009, "Message too big").into());
+        }
+        buf.extend_from_slice(&data);
+        if !fin {
+            return Ok(None);
+        }
+
+        let Some((opcode, rsv1, buf)) = partial.take() else {
+            return Ok(None);
+        };
+        match (rsv1, opcode) {
+            (true, _) => Ok(Some(WSFrame::Compressed(opcode, buf))),
+            (false, 0x1) => String::from_utf8(buf)
+                .map(|text| Some(WSFrame::Text(text)))
+                .map_err(|_| WSCloseError::new(1007, "Invalid UTF-8 in text frame").into()),
+            _ => Ok(Some(WSFrame::Binary(buf))),
+        }
+    }
+
+    /// 解压 permessage-deflate 消息；未协商压缩或控制帧带 RSV1 时以 1002 拒绝
+    fn inflate(
+        frame: WSFrame,
+        inflater: Option<&mut MessageInflater>,
+        limit: usize,
+    ) -> anyhow::Result<WSFrame> {
+        let WSFrame::Compressed(opcode, data) = frame else {
+            return Ok(frame);
+        };
+        let Some(inflater) = inflater.filter(|_| matches!(opcode, 0x1 | 0x2)) else {
+            return Err(WSCloseError::new(1002, "Unexpected RSV1 bit").into());
+        };
+        let data = inflater.decompress(&data, limit)?;
+        if opcode == 0x2 {
+            return Ok(WSFrame::Binary(data));
+        }
+        String::from_utf8(data)
+            .map(WSFrame::Text)
+            .map_err(|_| WSCloseError::new(1007, "Invalid UTF-8 in text frame").into())
+    }
+
+    /// 关闭握手：丢弃其余帧，直到收到对端的关闭帧、连接断开或超时
+    async fn await_close_echo<S>(ws: &WebSocket, stream: &mut S)
+    where
+        S: futures::Stream<Item = anyhow::Result<WSFrame>> + Unpin,
+    {
+        let echo = async {
+            while let Some(Ok(frame)) = stream.next().await {
+                if matches!(frame, WSFrame::Close(..)) {
+                    break;
+                }
+            }
+        };
+        if tokio::time::timeout(ws.close_timeout, echo).await.is_err() {
+            tracing::debug!("WS close handshake timed out");
+        }
+    }
+
     /// 生成 WebSocket 中件间
     pub fn to_middleware(ws: WebSocket) -> Box<Executor> {
         let ws = Arc::new(ws);
@@ -268,15 +958,66 @@ impl WebSocket {
                     return true;
                 }
 
+                // 缺失或格式错误的 key 直接拒绝，不计算 accept 值
+                let key_valid = meta
+                    .headers
+                    .get(&HeaderKey::SecWebSocketKey)
+                    .is_some_and(|key| Self::is_valid_key(key));
+                if !key_valid {
+                    if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                        meta.status = StatusCode::BadRequest;
+                        meta.body = b"Invalid Sec-WebSocket-Key".to_vec();
+                    }
+                    return false;
+                }
+
+                // 跨域握手直接拒绝，不返回 101
+                if !ws.origin_allowed(&meta.headers) {
+                    if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                        meta.status = StatusCode::Forbidden;
+                        meta.body = b"Forbidden".to_vec();
+                    }
+                    return false;
+                }
+
+                // 名额随连接结束释放
+                let _slot = match ws.acquire_ip_slot(ctx.addr.ip()) {
+                    Some(slot) => slot,
+                    None => {
+                        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                            meta.status = StatusCode::TooManyRequests;
+                            meta.body = b"Too Many Requests".to_vec();
+                        }
+                        return false;
+                    }
+                };
+
                 // 初始化全局 WS 发送器列表
                 if ctx.global.get::<WsSenderList>().await.is_none() {
                     ctx.global.set(WsSenderList::new()).await;
                 }
 
+                // 原样记录客户端的扩展提议，供处理器查看
+                let offers = meta
+                    .headers
+                    .get(&HeaderKey::SecWebSocketExtensions)
+                    .map(|v| WSExtensionOffers::parse(v))
+                    .unwrap_or_default();
+                ctx.local.set_value(offers);
+
+                // 协商 permessage-deflate，成功时写入 ctx.local 供 run 使用
+                let deflate = ws.permessage_deflate.and_then(|config| {
+                    config.negotiate(meta.headers.get(&HeaderKey::SecWebSocketExtensions)?)
+                });
+                if let Some(deflate) = deflate {
+                    ctx.local.set_value(deflate);
+                }
+
                 // 进行握手
                 {
+                    let extensions = deflate.map(|d| d.response_header());
                     let w = ctx.writer.as_deref_mut().unwrap();
-                    if let Err(e) = Self::handshake(w, &meta.headers).await {
+                    if let Err(e) = Self::handshake(w, &meta.headers, extensions.as_deref()).await {
                         tracing::warn!("WS Handshake Error: {:?}", e);
                         return false;
                     }
@@ -293,6 +1034,12 @@ impl WebSocket {
         })
     }
 
+    /// 对端可以发送的关闭码（RFC 6455 7.4）
+    /// 1004 为保留值，1005/1006/1015 只用于本地表示，不得出现在线路上
+    pub fn is_valid_close_code(code: u16) -> bool {
+        matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999)
+    }
+
     /// 严格按照 RFC 6455 解析 Close 帧负载，返回借用的 &str 以优化性能
     pub fn parse_close_payload(payload: &[u8]) -> anyhow::Result<(u16, Option<&str>)> {
         let len = payload.len();
@@ -309,6 +1056,9 @@ impl WebSocket {
 
         // 3. 提取状态码 (Big-Endian)
         let code = u16::from_be_bytes([payload[0], payload[1]]);
+        if !Self::is_valid_close_code(code) {
+            return Err(WSCloseError::new(1002, "Invalid close code").into());
+        }
 
         // 4. 解析原因 (必须是有效的 UTF-8)
         let reason = if len > 2 {
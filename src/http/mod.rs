@@ -6,15 +6,19 @@
 //!
 //! - `router`: Trie-tree based HTTP router
 //! - `types`: Executor type definition
+//! - `extract`: Extractor-style handler arguments (`Path`, `Query`, `Json`, `State`)
 //! - `meta`: HTTP request/response metadata
 //! - `req`: Request parsing
 //! - `res`: Response handling
 //! - `params`: URL path/query/form parameters
+//! - `sse`: Server-Sent Events streams
+//! - `testing`: WebSocket test client helpers (`test-utils` feature)
 //! - `websocket`: WebSocket support
 //! - `macros`: HTTP method macros (get!, post!, etc.)
 //! - `middlewares`: Built-in middleware implementations
 //! - `protocol`: HTTP protocol types (method, status, headers, etc.)
 
+pub mod extract;
 pub mod macros;
 pub mod meta;
 pub mod middlewares;
@@ -23,5 +27,8 @@ pub mod protocol;
 pub mod req;
 pub mod res;
 pub mod router;
+pub mod sse;
+#[cfg(feature = "test-utils")]
+pub mod testing;
 pub mod types;
 pub mod websocket;
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use ahash::AHashMap;
 
 #[derive(Debug, Clone, Default)]
@@ -41,6 +43,12 @@ impl SmallParams {
         self.entries.is_empty()
     }
 
+    /// 回退到指定长度，供路由匹配回溯时撤销参数
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+    }
+
     #[inline]
     pub fn clear(&mut self) {
         self.entries.clear();
@@ -53,6 +61,13 @@ impl From<SmallParams> for AHashMap<String, String> {
     }
 }
 
+/// 拆分请求目标为 (路径, 查询串)：丢弃 `#` 之后的片段，只按第一个 `?` 切分，
+/// 查询串中后续的 `?` 原样保留
+pub fn split_url(url: &str) -> (&str, &str) {
+    let url = url.split_once('#').map_or(url, |(before, _)| before);
+    url.split_once('?').unwrap_or((url, ""))
+}
+
 #[derive(Debug, Clone)]
 pub struct Params {
     pub url: String,
@@ -63,10 +78,7 @@ pub struct Params {
 
 impl Params {
     pub fn new(url: String) -> Self {
-        let query = url
-            .split_once('?')
-            .map(|(_, qs)| Self::parse_pairs(qs))
-            .unwrap_or_default();
+        let query = Self::parse_pairs(split_url(&url).1);
 
         Self {
             url,
@@ -76,6 +88,12 @@ impl Params {
         }
     }
 
+    /// 解析 `a=1&b=2` 形式的键值对（查询串与表单共用），并做百分号解码：
+    /// - 无 `=` 的裸键（`flag`）视为存在、值为空串
+    /// - `a=` 的值为空串
+    /// - 只按第一个 `=` 切分，`a=1=2` 的值为 `1=2`
+    /// - 空片段（如 `a=1&&b=2` 中间的空串）被忽略
+    /// - 重复的键按出现顺序保留全部值
     pub fn parse_pairs(pairs: &str) -> AHashMap<String, Vec<String>> {
         let mut map: AHashMap<String, Vec<String>> = AHashMap::new();
         for (k, v) in form_urlencoded::parse(pairs.as_bytes()) {
@@ -87,4 +105,22 @@ impl Params {
     pub fn set_form(&mut self, form: &str) {
         self.form = Some(Self::parse_pairs(form));
     }
+
+    /// 取出路径参数并解析为目标类型，缺失或解析失败返回 None
+    pub fn param<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.data.as_ref()?.get(name)?.parse().ok()
+    }
+
+    /// 取出第一个查询参数并解析为目标类型，缺失或解析失败返回 None
+    pub fn query_as<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.query.get(key)?.first()?.parse().ok()
+    }
+
+    /// 取出重复的查询参数（如 `?id=1&id=2`），跳过无法解析的值
+    pub fn query_vec<T: FromStr>(&self, key: &str) -> Vec<T> {
+        self.query
+            .get(key)
+            .map(|values| values.iter().filter_map(|v| v.parse().ok()).collect())
+            .unwrap_or_default()
+    }
 }
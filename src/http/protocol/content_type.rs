@@ -31,13 +31,27 @@ impl ContentType {
         }
     }
 
-    /// 转回字符串
-    pub fn to_string(&self) -> String {
-        let mut s = format!("{}/{}", self.top_level.as_str(), self.sub_type.as_str());
-        for (k, v) in &self.parameters {
-            s.push_str(&format!("; {}={}", k, v));
+    /// 设置 charset 参数（已存在则替换）
+    pub fn with_charset(self, charset: &str) -> Self {
+        self.with_param("charset", charset)
+    }
+
+    /// 设置任意参数，如 `boundary`；同名参数（不区分大小写）会被替换
+    pub fn with_param(mut self, key: &str, value: &str) -> Self {
+        match self
+            .parameters
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        {
+            Some(param) => param.1 = value.to_string(),
+            None => self.parameters.push((key.to_string(), value.to_string())),
         }
-        s
+        self
+    }
+
+    /// 生成响应头的值，如 `application/json; charset=utf-8`
+    pub fn to_header_value(&self) -> String {
+        self.to_string()
     }
 
     /// 语义化判断
@@ -46,6 +60,29 @@ impl ContentType {
     }
 }
 
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.top_level.as_str(), self.sub_type.as_str())?;
+        for (k, v) in &self.parameters {
+            // 含分隔符或空白的值需要加引号
+            let needs_quote = v.is_empty()
+                || v.chars()
+                    .any(|c| c.is_ascii_whitespace() || "()<>@,;:\\\"/[]?=".contains(c));
+            if needs_quote {
+                write!(
+                    f,
+                    "; {}=\"{}\"",
+                    k,
+                    v.replace('\\', "\\\\").replace('"', "\\\"")
+                )?;
+            } else {
+                write!(f, "; {}={}", k, v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Default for ContentType {
     fn default() -> Self {
         Self {
@@ -57,9 +94,13 @@ impl Default for ContentType {
 }
 
 impl ContentType {
-    /// 显式创建一个 text/plain 的默认对象
-    pub fn new() -> Self {
-        Self::default()
+    /// 用于出站响应的构造器，配合 `with_charset` / `with_param` 使用
+    pub fn new(top_level: MediaType, sub_type: SubMediaType) -> Self {
+        Self {
+            top_level,
+            sub_type,
+            parameters: Vec::new(),
+        }
     }
 
     /// 也可以定义一个通用的二进制流默认值
@@ -137,6 +137,11 @@ define_header_keys! {
     XForwardedHost => "X-Forwarded-Host",
     XForwardedProto => "X-Forwarded-Proto",
 
+    // ===== Security =====
+    ReferrerPolicy => "Referrer-Policy",
+    XContentTypeOptions => "X-Content-Type-Options",
+    XFrameOptions => "X-Frame-Options",
+
     // ===== Misc =====
     DNT => "DNT",
     KeepAlive => "Keep-Alive",
@@ -185,6 +190,44 @@ impl Headers {
         self.0.get(key)
     }
 
+    /// 追加一个值：已存在时按 RFC 7230 以 `, ` 拼接（Cookie 以 `; ` 拼接）
+    pub fn append(&mut self, key: HeaderKey, value: impl Into<String>) {
+        let value = value.into();
+        let sep = if key == HeaderKey::Cookie { "; " } else { ", " };
+        match self.0.get_mut(&key) {
+            Some(existing) => {
+                existing.push_str(sep);
+                existing.push_str(&value);
+            }
+            None => {
+                self.0.insert(key, value);
+            }
+        }
+    }
+
+    /// 按逗号拆分的全部值（引号内的逗号不拆分），不存在时为空。
+    /// 日期等本身含逗号的单值 Header 应使用 `get`
+    pub fn get_all(&self, key: &HeaderKey) -> Vec<&str> {
+        let Some(value) = self.0.get(key) else {
+            return Vec::new();
+        };
+        let mut values = Vec::new();
+        let (mut start, mut quoted) = (0, false);
+        for (i, c) in value.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ',' if !quoted => {
+                    values.push(value[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        values.push(value[start..].trim());
+        values.retain(|v| !v.is_empty());
+        values
+    }
+
     /// 移除 Header
     pub fn remove(&mut self, key: &HeaderKey) -> Option<String> {
         self.0.remove(key)
@@ -2,5 +2,6 @@ pub mod content_type;
 pub mod header;
 pub mod media_type;
 pub mod method;
+pub mod range;
 pub mod status;
 pub mod version;
@@ -0,0 +1,75 @@
+/// 单个字节区间（闭区间），对应 `Content-Range: bytes start-end/total`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl ByteRange {
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, total)
+    }
+}
+
+/// `Range` 请求头的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// 无 Range 头、格式无法识别或多区间：按完整内容返回 200
+    Full,
+    /// 可满足的单区间：返回 206
+    Partial(ByteRange),
+    /// 区间超出内容长度：返回 416
+    Unsatisfiable,
+}
+
+impl RangeRequest {
+    /// 按内容总长度解析 `bytes=start-end`、`bytes=start-` 与 `bytes=-suffix`
+    pub fn parse(header: Option<&str>, total: u64) -> Self {
+        let Some(spec) = header.map(str::trim).and_then(|h| h.strip_prefix("bytes=")) else {
+            return Self::Full;
+        };
+        if spec.contains(',') {
+            return Self::Full;
+        }
+        let Some((start, end)) = spec.trim().split_once('-') else {
+            return Self::Full;
+        };
+        let (start, end) = (start.trim(), end.trim());
+
+        let range = if start.is_empty() {
+            // 后缀区间：最后 N 个字节
+            let Ok(suffix) = end.parse::<u64>() else {
+                return Self::Full;
+            };
+            if suffix == 0 || total == 0 {
+                return Self::Unsatisfiable;
+            }
+            ByteRange {
+                start: total.saturating_sub(suffix),
+                end: total - 1,
+            }
+        } else {
+            let Ok(start) = start.parse::<u64>() else {
+                return Self::Full;
+            };
+            let end = if end.is_empty() {
+                total.saturating_sub(1)
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.min(total.saturating_sub(1)),
+                    _ => return Self::Full,
+                }
+            };
+            if start >= total {
+                return Self::Unsatisfiable;
+            }
+            ByteRange { start, end }
+        };
+        Self::Partial(range)
+    }
+}
@@ -99,6 +99,16 @@ impl StatusCode {
         }
     }
 
+    /// StatusCode 枚举转 u16
+    pub fn as_u16(&self) -> u16 {
+        *self as u16
+    }
+
+    /// 状态行使用的标准原因短语，如 `404` 对应 `Not Found`
+    pub fn reason_phrase(&self) -> &'static str {
+        self.to_str()
+    }
+
     /// 从 u16 转 StatusCode 枚举
     pub fn from_u16(code: u16) -> Option<Self> {
         match code {
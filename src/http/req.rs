@@ -1,10 +1,19 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use ahash::AHashMap;
 
 use anyhow::{Context, bail};
-use tokio::io::AsyncBufReadExt;
+use bytes::Bytes;
+use futures::{StreamExt, stream::BoxStream};
+use ipnet::IpNet;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+use tokio_util::io::StreamReader;
 
 use crate::{
-    connection::context::{BoxReader, LocalTypeMap},
+    connection::context::{AexReader, BoxReader, BoxWriter, LocalTypeMap},
     constants::http::*,
     http::{
         meta::HttpMetadata,
@@ -13,24 +22,262 @@ use crate::{
         protocol::{
             content_type::ContentType,
             header::{HeaderKey, Headers},
-            media_type::MediaType,
+            media_type::{MediaType, SubMediaType},
             method::HttpMethod,
             status::StatusCode,
             version::HttpVersion,
         },
+        res::{ContinueSent, encode_continue},
     },
 };
 
+/// 原始请求体，首次读取后缓存在 `ctx.local` 中，之后不再读取 socket
+#[derive(Debug, Clone, Default)]
+pub struct RawBody(pub Vec<u8>);
+
+/// 分块请求体累计长度超出上限，由路由转换为 413
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyTooLarge {
+    pub limit: usize,
+}
+
+impl std::fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request body exceeds {} bytes", self.limit)
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// 请求头数量或总字节数超出上限，由路由转换为 431
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderTooLarge;
+
+impl std::fmt::Display for HeaderTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Request header section exceeds {} headers or {} bytes",
+            MAX_HEADER_COUNT, MAX_HEADER_SIZE
+        )
+    }
+}
+
+impl std::error::Error for HeaderTooLarge {}
+
+/// 流式请求体，实现 `AsyncRead`，见 [`Request::body_stream`]
+pub type BodyStream<'a> = StreamReader<BoxStream<'a, std::io::Result<Bytes>>, Bytes>;
+
+/// 流式请求体每次读取的最大字节数
+const BODY_STREAM_CHUNK: usize = 16 * 1024;
+
+/// `body_stream` 创建时写入 `ctx.local`，读到请求体末尾后置位；
+/// 路由据此判断连接上是否还有未读完的请求体
+#[derive(Clone, Default)]
+pub(crate) struct StreamedBody(pub(crate) Arc<AtomicBool>);
+
+impl StreamedBody {
+    pub(crate) fn finished(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+enum BodyState {
+    /// Content-Length 剩余字节数
+    Length(usize),
+    /// 等待下一个分块大小行，已读取的累计字节数
+    ChunkHeader(usize),
+    /// 当前分块剩余字节数与累计字节数
+    ChunkData(usize, usize),
+}
+
+/// 解析分块大小行，忽略分块扩展 `;name=value`
+fn parse_chunk_size(line: &[u8]) -> Option<usize> {
+    let line = std::str::from_utf8(line).ok()?;
+    let size = line.split(';').next()?.trim();
+    usize::from_str_radix(size, 16).ok()
+}
+
+fn invalid_data(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// 读取一行，超过 `limit` 字节视为非法；连接提前关闭返回 UnexpectedEof
+async fn read_limited_line(reader: &mut AexReader, limit: usize) -> std::io::Result<Vec<u8>> {
+    let mut line = Vec::new();
+    let n = (&mut *reader)
+        .take(limit as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if n == 0 {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    if line.len() > limit {
+        return Err(invalid_data("Chunk line too long"));
+    }
+    Ok(line)
+}
+
+/// 读取流式请求体的下一段，请求体结束时返回 None
+async fn next_body_chunk(
+    reader: &mut AexReader,
+    state: BodyState,
+    limit: usize,
+) -> std::io::Result<Option<(Bytes, BodyState)>> {
+    match state {
+        BodyState::Length(0) => Ok(None),
+        BodyState::Length(remaining) => {
+            let mut buf = vec![0u8; remaining.min(BODY_STREAM_CHUNK)];
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            buf.truncate(n);
+            Ok(Some((buf.into(), BodyState::Length(remaining - n))))
+        }
+        BodyState::ChunkHeader(read) => {
+            let line = read_limited_line(reader, MAX_REQUEST_LINE_SIZE).await?;
+            let size = parse_chunk_size(&line).ok_or_else(|| invalid_data("Invalid chunk size"))?;
+            if size == 0 {
+                // 丢弃 trailer 头部，直到空行
+                loop {
+                    let line = read_limited_line(reader, MAX_HEADER_SIZE).await?;
+                    if line == LINE_DELIMITER || line == b"\n" {
+                        return Ok(None);
+                    }
+                }
+            }
+            if size > limit.saturating_sub(read) {
+                return Err(std::io::Error::other(BodyTooLarge { limit }));
+            }
+            Box::pin(next_body_chunk(
+                reader,
+                BodyState::ChunkData(size, read),
+                limit,
+            ))
+            .await
+        }
+        BodyState::ChunkData(remaining, read) => {
+            let mut buf = vec![0u8; remaining.min(BODY_STREAM_CHUNK)];
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            buf.truncate(n);
+            let next = if remaining == n {
+                let mut crlf = [0u8; 2];
+                reader.read_exact(&mut crlf).await?;
+                if crlf != *LINE_DELIMITER {
+                    return Err(invalid_data("Missing chunk terminator"));
+                }
+                BodyState::ChunkHeader(read + n)
+            } else {
+                BodyState::ChunkData(remaining - n, read + n)
+            };
+            Ok(Some((buf.into(), next)))
+        }
+    }
+}
+
+/// 按 `Accept` 头计算某个类型的 q 值：取最具体的匹配项（`a/b` > `a/*` > `*/*`），
+/// 没有匹配时为 0
+fn accept_quality(accept: &str, top: MediaType, sub: SubMediaType) -> f32 {
+    let mut best: Option<(u8, f32)> = None;
+    for item in accept.split(',') {
+        let mut parts = item.split(';');
+        let Some((t, s)) = parts.next().and_then(|r| r.trim().split_once('/')) else {
+            continue;
+        };
+        let (t, s) = (t.trim(), s.trim());
+        let specificity = match (t, s) {
+            ("*", "*") => 0,
+            (t, "*") if t.eq_ignore_ascii_case(top.as_str()) => 1,
+            (t, s)
+                if t.eq_ignore_ascii_case(top.as_str()) && s.eq_ignore_ascii_case(sub.as_str()) =>
+            {
+                2
+            }
+            _ => continue,
+        };
+        let q = parts
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|v| v.trim().parse::<f32>().ok())
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+        if best.map(|(b, _)| specificity > b).unwrap_or(true) {
+            best = Some((specificity, q));
+        }
+    }
+    best.map(|(_, q)| q).unwrap_or(0.0)
+}
+
+/// 还原经过反向代理的客户端地址。
+///
+/// 只有直连对端属于 `trusted_proxies` 时才读取转发头：优先 `X-Forwarded-For`，
+/// 其次 `Forwarded` 的 `for=`；从右往左跳过可信代理，取第一个不可信地址。
+/// 链路上全是可信代理时取最左侧地址；转发头缺失或无法解析时返回对端地址
+pub fn real_ip(peer: IpAddr, headers: &Headers, trusted_proxies: &[IpNet]) -> IpAddr {
+    let trusted = |ip: &IpAddr| {
+        let ip = ip.to_canonical();
+        trusted_proxies.iter().any(|net| net.contains(&ip))
+    };
+    if !trusted(&peer) {
+        return peer;
+    }
+
+    let chain: Vec<IpAddr> = if let Some(xff) = headers.get(&HeaderKey::XForwardedFor) {
+        xff.split(',').filter_map(parse_forwarded_node).collect()
+    } else if let Some(fwd) = headers.get(&HeaderKey::Forwarded) {
+        fwd.split(',')
+            .flat_map(|element| element.split(';'))
+            .filter_map(|pair| {
+                let (k, v) = pair.split_once('=')?;
+                k.trim().eq_ignore_ascii_case("for").then_some(v)
+            })
+            .filter_map(parse_forwarded_node)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    chain
+        .iter()
+        .rev()
+        .find(|ip| !trusted(ip))
+        .or(chain.first())
+        .copied()
+        .unwrap_or(peer)
+}
+
+/// 解析转发链中的单个节点：`1.2.3.4`、`1.2.3.4:80`、`"[::1]:80"`、`::1`；
+/// `unknown` 与混淆标识返回 None
+fn parse_forwarded_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    let inner = node.strip_prefix('[')?.split(']').next()?;
+    inner.parse().ok()
+}
+
 pub struct Request<'a> {
     pub reader: &'a mut Option<BoxReader>,
     pub local: &'a mut LocalTypeMap,
+    /// 首次读取请求体时用于发送 `100 Continue`
+    writer: Option<&'a mut Option<BoxWriter>>,
+    peer_addr: Option<SocketAddr>,
+    /// `read_body` 解码 chunked 请求体时的上限
+    max_body_size: usize,
     buf: Vec<u8>,
 }
 
 impl<'a> Request<'a> {
     pub async fn parse_to_local(&mut self) -> anyhow::Result<()> {
         let (method, path) = {
-            let line = self.read_line_with_limit().await?;
+            let line = self.read_line_with_limit(MAX_REQUEST_LINE_SIZE).await?;
             if line.len() > MAX_REQUEST_LINE_SIZE {
                 bail!("Request line too long: {} bytes", line.len());
             }
@@ -46,24 +293,10 @@ impl<'a> Request<'a> {
             (method, path_str.to_string())
         };
 
-        let headers_map = self.parse_headers_from_reader().await?;
+        let headers = self.parse_headers_from_reader().await?;
 
         let version = HttpVersion::Http11;
 
-        if headers_map.len() > MAX_HEADER_COUNT {
-            bail!("Too many headers: {}", headers_map.len());
-        }
-
-        let header_size: usize = headers_map
-            .iter()
-            .map(|(k, v)| k.as_str().len() + v.len())
-            .sum();
-        if header_size > MAX_HEADER_SIZE {
-            bail!("Total header size too large: {} bytes", header_size);
-        }
-
-        let headers = Headers::from(headers_map);
-
         // 3.2 Content-Type & Multipart Boundary
         let content_type = headers
             .get(&HeaderKey::ContentType)
@@ -111,9 +344,10 @@ impl<'a> Request<'a> {
             cookies,
             is_websocket: WebSocket::check(method, &headers),
             params: None,
+            matched_route: None,
             status: StatusCode::Ok, // 默认状态码为 200
             body: Vec::new(),       // 默认空消息体
-            headers: Headers::from(headers),
+            headers,
         };
 
         self.local.set_value(meta);
@@ -141,10 +375,14 @@ impl<'a> Request<'a> {
         map
     }
 
-    async fn read_line_with_limit(&mut self) -> anyhow::Result<&[u8]> {
+    /// 读取一行，最多读取 `limit + 1` 字节；调用方据此判断是否超长
+    async fn read_line_with_limit(&mut self, limit: usize) -> anyhow::Result<&[u8]> {
         self.buf.clear();
         if let Some(r) = self.reader.as_deref_mut() {
-            let n = r.read_until(b'\n', &mut self.buf).await?;
+            let n = r
+                .take(limit as u64 + 1)
+                .read_until(b'\n', &mut self.buf)
+                .await?;
             if n == 0 {
                 bail!("Connection closed");
             }
@@ -154,18 +392,40 @@ impl<'a> Request<'a> {
         }
     }
 
-    async fn parse_headers_from_reader(&mut self) -> anyhow::Result<AHashMap<HeaderKey, String>> {
-        let mut map = AHashMap::with_capacity(16);
+    /// 重复的 Header 按 `Headers::append` 合并；Content-Length 重复且不一致时报错。
+    /// 边读边累计行数与字节数，超出上限立即以 [`HeaderTooLarge`] 中止
+    async fn parse_headers_from_reader(&mut self) -> anyhow::Result<Headers> {
+        let mut map = Headers::new();
+        let mut total = 0usize;
+        let mut count = 0usize;
         loop {
-            let line = self.read_line_with_limit().await?;
+            let remaining = MAX_HEADER_SIZE - total;
+            let line = self.read_line_with_limit(remaining).await?;
+            total += line.len();
+            if total > MAX_HEADER_SIZE {
+                return Err(HeaderTooLarge.into());
+            }
             let line = std::str::from_utf8(line)?.trim_end_matches(|c| c == '\r' || c == '\n');
             if line.is_empty() {
                 break;
             }
+            count += 1;
+            if count > MAX_HEADER_COUNT {
+                return Err(HeaderTooLarge.into());
+            }
             if let Some(pos) = line.find(':')
                 && let Some(key) = HeaderKey::from_str(line[..pos].trim())
             {
-                map.insert(key, line[pos + 1..].trim().to_string());
+                let value = line[pos + 1..].trim();
+                if key == HeaderKey::ContentLength
+                    && let Some(existing) = map.get(&key)
+                {
+                    if existing != value {
+                        bail!("Conflicting Content-Length headers");
+                    }
+                    continue;
+                }
+                map.append(key, value);
             }
         }
         Ok(map)
@@ -179,6 +439,59 @@ impl<'a> Request<'a> {
             .unwrap_or(HttpMethod::GET)
     }
 
+    /// 对端地址，由 `Context::req` 填入
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// 客户端真实地址，规则见 [`real_ip`]；没有对端地址时为 `0.0.0.0`
+    pub fn real_ip(&self, trusted_proxies: &[IpNet]) -> IpAddr {
+        let peer = self
+            .peer_addr
+            .map(|a| a.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        match self.local.get_ref::<HttpMetadata>() {
+            Some(meta) => real_ip(peer, &meta.headers, trusted_proxies),
+            None => peer,
+        }
+    }
+
+    /// 客户端是否接受该类型；没有 Accept 头时视为全部接受
+    pub fn accepts(&self, top: MediaType, sub: SubMediaType) -> bool {
+        match self.accept_header() {
+            Some(accept) => accept_quality(&accept, top, sub) > 0.0,
+            None => true,
+        }
+    }
+
+    /// 按 Accept 的 q 值从候选类型中选出最合适的一个，q 相同时按候选顺序
+    pub fn preferred(&self, available: &[ContentType]) -> Option<ContentType> {
+        let Some(accept) = self.accept_header() else {
+            return available.first().cloned();
+        };
+        let mut best: Option<(&ContentType, f32)> = None;
+        for ct in available {
+            let q = accept_quality(&accept, ct.top_level, ct.sub_type);
+            if q > 0.0 && best.map(|(_, b)| q > b).unwrap_or(true) {
+                best = Some((ct, q));
+            }
+        }
+        best.map(|(ct, _)| ct.clone())
+    }
+
+    fn accept_header(&self) -> Option<String> {
+        self.local
+            .get_ref::<HttpMetadata>()
+            .and_then(|m| m.headers.get(&HeaderKey::Accept).cloned())
+    }
+
+    /// 命中的路由模板（如 `/users/:id`），用于日志与指标分组
+    pub fn matched_route(&self) -> Option<String> {
+        self.local
+            .get_ref::<HttpMetadata>()
+            .and_then(|m| m.matched_route.clone())
+    }
+
     /// 快速获取所有的 Params
     pub fn params(&self) -> Option<Params> {
         self.local
@@ -206,12 +519,280 @@ impl<'a> Request<'a> {
             .and_then(|f| f.get(key).and_then(|v| v.first().cloned()))
     }
 
+    /// 请求头的值，重复的头按出现顺序以 `, ` 拼接
+    pub fn header(&self, key: &HeaderKey) -> Option<&str> {
+        self.local
+            .get_ref::<HttpMetadata>()
+            .and_then(|m| m.headers.get(key))
+            .map(String::as_str)
+    }
+
+    /// 取出请求头并去掉首尾空白后解析为目标类型，缺失或解析失败返回 None
+    pub fn header_as<T: FromStr>(&self, key: &HeaderKey) -> Option<T> {
+        self.header(key)?.trim().parse().ok()
+    }
+
+    /// Content-Length 声明的请求体长度，缺省为 0
+    pub fn content_length(&self) -> usize {
+        self.header_as(&HeaderKey::ContentLength).unwrap_or(0)
+    }
+
+    /// 已读取的请求体，未读取时返回 None
+    pub fn body(&self) -> Option<Vec<u8>> {
+        self.local.get_ref::<RawBody>().map(|b| b.0.clone())
+    }
+
+    /// 已读取的原始请求体，未读取时为空
+    pub fn bytes(&self) -> &[u8] {
+        self.local
+            .get_ref::<RawBody>()
+            .map(|b| b.0.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 以 UTF-8 解码已读取的请求体
+    pub fn text(&self) -> anyhow::Result<String> {
+        let text = std::str::from_utf8(self.bytes()).context("Request body is not valid UTF-8")?;
+        Ok(text.to_string())
+    }
+
+    /// 全部表单字段；路由未解析过时按 urlencoded 解析已读取的请求体并回写到 params
+    pub fn form_map(&mut self) -> AHashMap<String, Vec<String>> {
+        if let Some(form) = self.params().and_then(|p| p.form) {
+            return form;
+        }
+
+        let form = Params::parse_pairs(&String::from_utf8_lossy(self.bytes()));
+        if let Some(params) = self
+            .local
+            .get_mut::<HttpMetadata>()
+            .and_then(|m| m.params.as_mut())
+        {
+            params.form = Some(form.clone());
+        }
+        form
+    }
+
+    /// 读取 Content-Length 指定长度的请求体，已读取过则直接返回缓存；
+    /// 超过 `with_max_body_size` 设置的上限时在分配内存前以 [`BodyTooLarge`] 失败，
+    /// chunked 请求按同一上限解码
+    pub async fn read_body(&mut self) -> anyhow::Result<Vec<u8>> {
+        if let Some(body) = self.body() {
+            return Ok(body);
+        }
+        if self
+            .local
+            .get_ref::<HttpMetadata>()
+            .is_some_and(|m| m.is_chunked)
+        {
+            return self.read_chunked_body(self.max_body_size).await;
+        }
+
+        let length = self.content_length();
+        if length > self.max_body_size {
+            return Err(BodyTooLarge {
+                limit: self.max_body_size,
+            }
+            .into());
+        }
+        let mut bytes = vec![0u8; length];
+        if !bytes.is_empty() {
+            self.send_continue().await?;
+            let r = self.reader.as_deref_mut().context("Reader taken!")?;
+            r.read_exact(&mut bytes).await?;
+        }
+        self.local.set_value(RawBody(bytes.clone()));
+        Ok(bytes)
+    }
+
+    /// 解码 `Transfer-Encoding: chunked` 请求体，已读取过则直接返回缓存。
+    /// 每个分块读取前检查累计长度，超出 `limit` 立即以 [`BodyTooLarge`] 中止
+    pub async fn read_chunked_body(&mut self, limit: usize) -> anyhow::Result<Vec<u8>> {
+        if let Some(body) = self.body() {
+            return Ok(body);
+        }
+
+        self.send_continue().await?;
+        let mut body = Vec::new();
+        loop {
+            let line = self.read_line_with_limit(MAX_REQUEST_LINE_SIZE).await?;
+            if line.len() > MAX_REQUEST_LINE_SIZE {
+                bail!("Chunk size line too long");
+            }
+            let size = parse_chunk_size(line).context("Invalid chunk size")?;
+
+            if size == 0 {
+                break;
+            }
+            if size > limit.saturating_sub(body.len()) {
+                return Err(BodyTooLarge { limit }.into());
+            }
+
+            let r = self.reader.as_deref_mut().context("Reader taken!")?;
+            let start = body.len();
+            body.resize(start + size, 0);
+            r.read_exact(&mut body[start..]).await?;
+            let mut crlf = [0u8; 2];
+            r.read_exact(&mut crlf).await?;
+            if crlf != *LINE_DELIMITER {
+                bail!("Missing chunk terminator");
+            }
+        }
+
+        // 丢弃 trailer 头部，直到空行
+        loop {
+            let line = self.read_line_with_limit(MAX_HEADER_SIZE).await?;
+            if line.len() > MAX_HEADER_SIZE {
+                bail!("Chunk trailer too long");
+            }
+            if line == LINE_DELIMITER || line == b"\n" {
+                break;
+            }
+        }
+
+        self.local.set_value(RawBody(body.clone()));
+        Ok(body)
+    }
+
+    /// 客户端等待 `100 Continue` 时返回待发送的中间响应，并标记为已发送
+    fn take_continue(&mut self) -> Option<Vec<u8>> {
+        let version = self
+            .local
+            .get_ref::<HttpMetadata>()
+            .filter(|m| m.expects_continue())?
+            .version;
+        if self.writer.is_none() || self.local.get_ref::<ContinueSent>().is_some() {
+            return None;
+        }
+        self.local.set_value(ContinueSent);
+        Some(encode_continue(version))
+    }
+
+    /// 客户端声明 `Expect: 100-continue` 时，在首次读取请求体前发送 `100 Continue`
+    async fn send_continue(&mut self) -> anyhow::Result<()> {
+        let Some(buf) = self.take_continue() else {
+            return Ok(());
+        };
+        let w = self
+            .writer
+            .as_deref_mut()
+            .and_then(|w| w.as_deref_mut())
+            .context("Writer not available")?;
+        w.write_all(&buf).await?;
+        w.flush().await?;
+        Ok(())
+    }
+
+    /// 请求体已被完整读取：缓存为 [`RawBody`]，或经 `body_stream` 读到末尾
+    pub(crate) fn body_consumed(&self) -> bool {
+        self.local.get_ref::<RawBody>().is_some()
+            || self
+                .local
+                .get_ref::<StreamedBody>()
+                .is_some_and(|s| s.finished())
+    }
+
+    /// 丢弃连接上尚未读取的 `length` 字节请求体，使连接可以继续读取下一个请求
+    pub(crate) async fn discard_body(&mut self, length: usize) -> anyhow::Result<()> {
+        let r = self.reader.as_deref_mut().context("Reader taken!")?;
+        let skipped =
+            tokio::io::copy(&mut (&mut *r).take(length as u64), &mut tokio::io::sink()).await?;
+        if skipped < length as u64 {
+            bail!("Connection closed before request body was fully sent");
+        }
+        Ok(())
+    }
+
+    /// 以 `AsyncRead` 流的方式读取请求体，不在内存中缓存完整内容。
+    ///
+    /// 读取范围由 Content-Length 或分块编码界定，超过 `limit` 时以 [`BodyTooLarge`] 失败。
+    /// 路由需要通过 `RouteBuilder::stream_body` 关闭请求体预读；
+    /// 请求体已被读取过时直接返回缓存的内容
+    pub fn body_stream(&mut self, limit: usize) -> anyhow::Result<BodyStream<'_>> {
+        if let Some(body) = self.body() {
+            let once = futures::stream::once(async move { Ok(Bytes::from(body)) });
+            return Ok(StreamReader::new(once.boxed()));
+        }
+
+        let chunked = self
+            .local
+            .get_ref::<HttpMetadata>()
+            .is_some_and(|m| m.is_chunked);
+        let length = self.content_length();
+        if !chunked && length > limit {
+            return Err(BodyTooLarge { limit }.into());
+        }
+
+        let finished = StreamedBody::default();
+        self.local.set_value(finished.clone());
+        // `100 Continue` 在首次读取流时才发送
+        let pending = match self.take_continue() {
+            Some(buf) => {
+                let w = self
+                    .writer
+                    .as_deref_mut()
+                    .and_then(|w| w.as_deref_mut())
+                    .context("Writer not available")?;
+                Some((w, buf))
+            }
+            None => None,
+        };
+        let reader = self.reader.as_deref_mut().context("Reader taken!")?;
+        let state = if chunked {
+            BodyState::ChunkHeader(0)
+        } else {
+            BodyState::Length(length)
+        };
+
+        let stream = futures::stream::try_unfold(
+            (reader, state, pending),
+            move |(reader, state, pending)| {
+                let finished = finished.clone();
+                async move {
+                    if let Some((w, buf)) = pending {
+                        w.write_all(&buf).await?;
+                        w.flush().await?;
+                    }
+                    match next_body_chunk(reader, state, limit).await? {
+                        Some((chunk, next)) => Ok(Some((chunk, (reader, next, None)))),
+                        None => {
+                            finished.0.store(true, Ordering::Release);
+                            Ok(None)
+                        }
+                    }
+                }
+            },
+        );
+        Ok(StreamReader::new(stream.boxed()))
+    }
+
     /// 创建一个新的 Request 实例
     pub fn new(reader: &'a mut Option<BoxReader>, local: &'a mut LocalTypeMap) -> Self {
         Self {
             reader,
             local,
+            writer: None,
+            peer_addr: None,
+            max_body_size: MAX_BODY_SIZE,
             buf: Vec::with_capacity(1024),
         }
     }
+
+    /// 绑定响应写入端，客户端声明 `Expect: 100-continue` 时在首次读取请求体前发送 `100 Continue`
+    pub fn with_writer(mut self, writer: &'a mut Option<BoxWriter>) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
+    /// 绑定对端地址
+    pub fn with_peer_addr(mut self, addr: SocketAddr) -> Self {
+        self.peer_addr = Some(addr);
+        self
+    }
+
+    /// 设置 `read_body` 解码 chunked 请求体时的上限，默认 `MAX_BODY_SIZE`
+    pub fn with_max_body_size(mut self, limit: usize) -> Self {
+        self.max_body_size = limit;
+        self
+    }
 }
@@ -1,10 +1,21 @@
-use tokio::io::AsyncWriteExt;
+use std::{io::SeekFrom, path::Path};
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    connection::context::{BoxWriter, LocalTypeMap},
+    connection::{
+        context::{BoxWriter, DisconnectWatch, LocalTypeMap},
+        global::GlobalContext,
+    },
+    constants::http::SECURITY_HEADERS,
     http::{
         meta::HttpMetadata,
-        protocol::{header::HeaderKey, header::Headers, status::StatusCode, version::HttpVersion},
+        protocol::{
+            header::HeaderKey, header::Headers, media_type::MediaType, method::HttpMethod,
+            range::RangeRequest, status::StatusCode, version::HttpVersion,
+        },
+        sse::SseStream,
     },
 };
 
@@ -14,14 +25,206 @@ fn build_status_line(status: StatusCode, version: HttpVersion) -> Vec<u8> {
         HttpVersion::Http11 => b"HTTP/1.1 ".to_vec(),
         HttpVersion::Http20 => b"HTTP/2.0 ".to_vec(),
     };
-    let status_str = status.to_str();
     let mut buf = prefix;
-    buf.extend_from_slice((status as u16).to_string().as_bytes());
+    buf.extend_from_slice(status.as_u16().to_string().as_bytes());
     buf.push(b' ');
-    buf.extend_from_slice(status_str.as_bytes());
+    buf.extend_from_slice(status.reason_phrase().as_bytes());
+    buf
+}
+
+/// 204 / 304 / 1xx 不允许携带 body 与 Content-Length
+fn is_bodiless(status: StatusCode) -> bool {
+    matches!(status, StatusCode::NoContent | StatusCode::NotModified) || status.as_u16() < 200
+}
+
+/// 配置的 Server 头与安全 Header，由路由在每个请求开始时写入 `ctx.local`，
+/// 所有写出响应头的路径都在提交前补充，处理器已设置的不覆盖
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DefaultHeaders {
+    server: Option<String>,
+    security: bool,
+}
+
+impl DefaultHeaders {
+    pub(crate) fn new(global: &GlobalContext) -> Self {
+        Self {
+            server: global.server_header.clone(),
+            security: global.security_headers,
+        }
+    }
+
+    /// `headers` 中尚未设置的默认头部
+    fn missing<'h>(&'h self, headers: &'h Headers) -> impl Iterator<Item = (&'h str, &'h str)> {
+        let server = self
+            .server
+            .as_deref()
+            .filter(|_| !headers.contains(&HeaderKey::Server))
+            .map(|v| ("Server", v));
+        let security = SECURITY_HEADERS
+            .iter()
+            .filter(|_| self.security)
+            .filter(|(k, _)| !headers.contains(&HeaderKey::from(*k)))
+            .map(|(k, v)| (*k, *v));
+        server.into_iter().chain(security)
+    }
+
+    /// 把尚未设置的默认头部写入 `headers`
+    pub(crate) fn apply(&self, headers: &mut Headers) {
+        let missing: Vec<(String, String)> = self
+            .missing(headers)
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        for (k, v) in missing {
+            headers.insert(HeaderKey::from(k), v);
+        }
+    }
+}
+
+/// 写出除 Content-Length / Transfer-Encoding 外的头部，以及 `ctx.local` 中尚未设置的默认头部；
+/// 响应体的分帧头部由调用方按实际写出方式追加
+fn encode_headers(buf: &mut Vec<u8>, headers: &Headers, local: &LocalTypeMap) {
+    let mut push = |k: &str, v: &str| {
+        buf.extend_from_slice(k.as_bytes());
+        buf.extend_from_slice(b": ");
+        buf.extend_from_slice(v.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    };
+    for (k, v) in headers {
+        if matches!(k, HeaderKey::ContentLength | HeaderKey::TransferEncoding) {
+            continue;
+        }
+        push(k.as_str(), v);
+    }
+    if let Some(defaults) = local.get_ref::<DefaultHeaders>() {
+        for (k, v) in defaults.missing(headers) {
+            push(k, v);
+        }
+    }
+}
+
+/// 编码状态行与头部（含空行）；`body_hint` 用于预分配
+fn encode_head(
+    headers: &Headers,
+    local: &LocalTypeMap,
+    status: StatusCode,
+    version: HttpVersion,
+    content_length: &str,
+    body_hint: usize,
+) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(256 + headers.len() * 64 + body_hint);
+    buf.extend_from_slice(&build_status_line(status, version));
+    buf.extend_from_slice(b"\r\n");
+
+    // body 不是分块编码，分块响应请使用 `stream`
+    encode_headers(&mut buf, headers, local);
+
+    if !is_bodiless(status) {
+        buf.extend_from_slice(b"Content-Length: ");
+        buf.extend_from_slice(content_length.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+
+    buf.extend_from_slice(b"\r\n");
+    buf
+}
+
+/// `100 Continue` 中间响应：状态行加空行
+pub(crate) fn encode_continue(version: HttpVersion) -> Vec<u8> {
+    let mut buf = build_status_line(StatusCode::Continue, version);
+    buf.extend_from_slice(b"\r\n\r\n");
+    buf
+}
+
+/// 已发送 `100 Continue`，同一请求不再重复发送
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ContinueSent;
+
+/// 响应已由处理器直接写出（如分块流），之后不再发送常规响应
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseCommitted;
+
+/// 已写出分块编码的响应头，后续分块可直接追加；
+/// HEAD 与 204 / 304 响应没有响应体，`bodiless` 时分块数据被丢弃
+#[derive(Debug, Clone, Copy)]
+struct ChunkedHead {
+    bodiless: bool,
+}
+
+impl ChunkedHead {
+    fn bodiless(local: &LocalTypeMap) -> bool {
+        local.get_ref::<ChunkedHead>().is_some_and(|h| h.bodiless)
+    }
+}
+
+/// 已写出结束分块，之后不再追加任何数据
+#[derive(Debug, Clone, Copy)]
+struct ChunkedFinished;
+
+/// 编码一个分块：十六进制长度 + CRLF + 数据 + CRLF
+fn encode_chunk(data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(data.len() + 16);
+    buf.extend_from_slice(format!("{:X}\r\n", data.len()).as_bytes());
+    buf.extend_from_slice(data);
+    buf.extend_from_slice(b"\r\n");
     buf
 }
 
+/// 写出结束分块并标记完成；重复调用不再写出，否则会污染同一连接上的下一个响应
+async fn write_last_chunk(writer: &mut BoxWriter, local: &mut LocalTypeMap) -> anyhow::Result<()> {
+    if local.get_ref::<ChunkedFinished>().is_some() {
+        return Ok(());
+    }
+    local.set_value(ChunkedFinished);
+    if ChunkedHead::bodiless(local) {
+        return Ok(());
+    }
+    writer.write_all(b"0\r\n\r\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// 分块响应写入器，每个分块写出后立即 flush；结束时必须调用 `finish`
+pub struct ChunkedWriter<'a> {
+    writer: &'a mut BoxWriter,
+    local: &'a mut LocalTypeMap,
+    /// 处理器调用过 `Context::watch_disconnect` 时，对端断开后取消
+    disconnect: Option<CancellationToken>,
+}
+
+impl ChunkedWriter<'_> {
+    /// 对端是否已断开；未开启 `watch_disconnect` 时总是 false
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnect.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+
+    /// 等待对端断开；未开启 `watch_disconnect` 时永不返回
+    pub async fn disconnected(&self) {
+        match &self.disconnect {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// 写出一个分块，空数据会被忽略（空分块表示结束）；对端已断开时返回错误
+    pub async fn send(&mut self, data: impl AsRef<[u8]>) -> anyhow::Result<()> {
+        if self.is_disconnected() {
+            anyhow::bail!("Client disconnected");
+        }
+        let data = data.as_ref();
+        if data.is_empty() || ChunkedHead::bodiless(self.local) {
+            return Ok(());
+        }
+        self.writer.write_all(&encode_chunk(data)).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    /// 写出结束分块
+    pub async fn finish(self) -> anyhow::Result<()> {
+        write_last_chunk(self.writer, self.local).await
+    }
+}
+
 pub struct Response<'a> {
     pub writer: &'a mut Option<BoxWriter>,
     pub local: &'a mut LocalTypeMap,
@@ -35,37 +238,225 @@ impl<'a> Response<'a> {
         status: StatusCode,
         version: HttpVersion,
     ) -> anyhow::Result<()> {
+        // Content-Length 总是按实际写出的 body 计算，忽略处理器设置的旧值；
+        // HEAD 响应没有 body，保留处理器声明的长度（如 send_file）
+        let is_head = self
+            .local
+            .get_ref::<HttpMetadata>()
+            .is_some_and(|m| m.method == HttpMethod::HEAD);
+        let content_length = headers
+            .get(&HeaderKey::ContentLength)
+            .filter(|_| is_head)
+            .cloned()
+            .unwrap_or_else(|| body.len().to_string());
+        let bodiless = is_bodiless(status);
+
+        let mut buf = encode_head(
+            headers,
+            self.local,
+            status,
+            version,
+            &content_length,
+            body.len(),
+        );
+        if !bodiless {
+            buf.extend_from_slice(body);
+        }
+
+        let w = self
+            .writer
+            .as_deref_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        w.write_all(&buf).await?;
+        w.flush().await?;
+
+        Ok(())
+    }
+
+    /// 发送 `100 Continue` 中间响应，告知客户端可以继续发送请求体；每个请求只发送一次
+    pub async fn send_continue(&mut self) -> anyhow::Result<()> {
+        if self.local.get_ref::<ContinueSent>().is_some() {
+            return Ok(());
+        }
+        let version = self
+            .local
+            .get_ref::<HttpMetadata>()
+            .map(|m| m.version)
+            .unwrap_or(HttpVersion::Http11);
         let w = self
             .writer
             .as_deref_mut()
             .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
 
-        let mut buf = Vec::with_capacity(256 + headers.len() * 64);
+        self.local.set_value(ContinueSent);
+        w.write_all(&encode_continue(version)).await?;
+        w.flush().await?;
+        Ok(())
+    }
+
+    /// 以 `Transfer-Encoding: chunked` 写出状态行与头部，返回分块写入器
+    pub async fn stream(mut self) -> anyhow::Result<ChunkedWriter<'a>> {
+        self.write_chunked_head().await?;
+        let Response { writer, local } = self;
+        let disconnect = local
+            .get_ref::<DisconnectWatch>()
+            .map(|DisconnectWatch(token)| token.clone());
+        let writer = writer
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        Ok(ChunkedWriter {
+            writer,
+            local,
+            disconnect,
+        })
+    }
+
+    /// 写出状态行与 `Transfer-Encoding: chunked` 头部，并标记响应已提交
+    async fn write_chunked_head(&mut self) -> anyhow::Result<()> {
+        let (status, version, headers, bodiless) = {
+            let meta = self
+                .local
+                .get_mut::<HttpMetadata>()
+                .ok_or_else(|| anyhow::anyhow!("HttpMetadata not found"))?;
+            meta.headers.remove(&HeaderKey::ContentLength);
+            // 204 / 304 不允许 Transfer-Encoding；HEAD 保留与 GET 相同的头部，但不写分块
+            if is_bodiless(meta.status) {
+                meta.headers.remove(&HeaderKey::TransferEncoding);
+            } else {
+                meta.headers
+                    .insert(HeaderKey::TransferEncoding, "chunked".to_string());
+            }
+            let bodiless = is_bodiless(meta.status) || meta.method == HttpMethod::HEAD;
+            let headers = std::mem::replace(&mut meta.headers, Headers::new());
+            (meta.status, meta.version, headers, bodiless)
+        };
+        self.local.set_value(ResponseCommitted);
+        self.local.set_value(ChunkedHead { bodiless });
 
-        let status_line = build_status_line(status, version);
-        buf.extend_from_slice(&status_line);
+        let mut buf = build_status_line(status, version);
+        buf.extend_from_slice(b"\r\n");
+        encode_headers(&mut buf, &headers, self.local);
+        if headers.contains(&HeaderKey::TransferEncoding) {
+            buf.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+        }
         buf.extend_from_slice(b"\r\n");
 
-        for (k, v) in headers {
-            buf.extend_from_slice(k.as_str().as_bytes());
-            buf.extend_from_slice(b": ");
-            buf.extend_from_slice(v.as_bytes());
-            buf.extend_from_slice(b"\r\n");
+        let writer = self
+            .writer
+            .as_deref_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        writer.write_all(&buf).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// 以分块编码写出一段响应体，适合总长度未知的场景（如代理转发）。
+    ///
+    /// 首次调用时先写出状态行与头部；空数据会被忽略。全部写完后必须调用 `finish_chunked`
+    pub async fn write_chunk(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if self.local.get_ref::<ChunkedFinished>().is_some() {
+            anyhow::bail!("Chunked response already finished");
+        }
+        if self.local.get_ref::<ChunkedHead>().is_none() {
+            if self.is_committed() {
+                anyhow::bail!("Response already committed");
+            }
+            self.write_chunked_head().await?;
+        }
+        if data.is_empty() || ChunkedHead::bodiless(self.local) {
+            return Ok(());
         }
+        let writer = self
+            .writer
+            .as_deref_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        writer.write_all(&encode_chunk(data)).await?;
+        writer.flush().await?;
+        Ok(())
+    }
 
-        buf.extend_from_slice(b"Content-Length: ");
-        buf.extend_from_slice(body.len().to_string().as_bytes());
-        buf.extend_from_slice(b"\r\n");
+    /// 写出结束分块；没有写过任何分块时发送空的分块响应
+    pub async fn finish_chunked(&mut self) -> anyhow::Result<()> {
+        if self.local.get_ref::<ChunkedFinished>().is_some() {
+            return Ok(());
+        }
+        self.write_chunk(&[]).await?;
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        write_last_chunk(writer, self.local).await
+    }
 
-        buf.extend_from_slice(b"\r\n");
-        buf.extend_from_slice(body);
+    /// 开启 `text/event-stream` 事件流，连接保持到流关闭为止
+    pub async fn sse(mut self) -> anyhow::Result<SseStream<'a>> {
+        self.set_header(HeaderKey::ContentType, "text/event-stream")
+            .set_header(HeaderKey::CacheControl, "no-cache")
+            .set_header("X-Accel-Buffering", "no");
+        Ok(SseStream::new(self.stream().await?))
+    }
 
-        w.write_all(&buf).await?;
-        w.flush().await?;
+    /// 发送文件，支持 `Range` 单区间请求（206 / 416）与 HEAD（只写头部）
+    pub async fn send_file(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::open(path).await?;
+        let total = file.metadata().await?.len();
 
+        // 处理器预先设置的头部（Cache-Control、ETag、Content-Disposition 等）一并发出
+        let (range, method, version, mut headers) = {
+            let meta = self
+                .local
+                .get_mut::<HttpMetadata>()
+                .ok_or_else(|| anyhow::anyhow!("HttpMetadata not found"))?;
+            let range = RangeRequest::parse(
+                meta.headers.get(&HeaderKey::Range).map(|s| s.as_str()),
+                total,
+            );
+            let headers = std::mem::replace(&mut meta.headers, Headers::new());
+            (range, meta.method, meta.version, headers)
+        };
+
+        if !headers.contains(&HeaderKey::ContentType) {
+            headers.insert(HeaderKey::ContentType, MediaType::guess(path));
+        }
+        headers.insert(HeaderKey::AcceptRanges, "bytes");
+        let (status, start, len) = match range {
+            RangeRequest::Full => (StatusCode::Ok, 0, total),
+            RangeRequest::Partial(r) => {
+                headers.insert(HeaderKey::ContentRange, r.content_range(total));
+                (StatusCode::PartialContent, r.start, r.len())
+            }
+            RangeRequest::Unsatisfiable => {
+                headers.insert(HeaderKey::ContentRange, format!("bytes */{}", total));
+                (StatusCode::RangeNotSatisfiable, 0, 0)
+            }
+        };
+
+        self.local.set_value(ResponseCommitted);
+        let head = encode_head(&headers, self.local, status, version, &len.to_string(), 0);
+        let w = self
+            .writer
+            .as_deref_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        w.write_all(&head).await?;
+
+        // 按固定大小的缓冲区边读边写，大文件不整体载入内存
+        if method != HttpMethod::HEAD && len > 0 {
+            file.seek(SeekFrom::Start(start)).await?;
+            let copied = tokio::io::copy(&mut (&mut file).take(len), w).await?;
+            if copied < len {
+                anyhow::bail!("File truncated while sending: {}", path.display());
+            }
+        }
+        w.flush().await?;
         Ok(())
     }
 
+    /// 处理器是否已自行写出响应
+    pub fn is_committed(&self) -> bool {
+        self.local.get_ref::<ResponseCommitted>().is_some()
+    }
+
     pub fn set_header(&mut self, key: impl Into<HeaderKey>, value: impl Into<String>) -> &mut Self {
         if let Some(meta) = self.local.get_mut::<HttpMetadata>() {
             meta.headers.insert(key.into(), value.into());
@@ -73,14 +464,28 @@ impl<'a> Response<'a> {
         self
     }
 
+    /// 处理器写出分块头后没有调用 `finish` 就返回时补写结束分块，
+    /// 否则客户端会一直等待响应结束，连接也无法复用
+    async fn finish_open_chunked(&mut self) -> anyhow::Result<()> {
+        if self.local.get_ref::<ChunkedHead>().is_none() {
+            return Ok(());
+        }
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Writer not available"))?;
+        write_last_chunk(writer, self.local).await
+    }
+
     pub async fn send_response(&mut self) -> anyhow::Result<()> {
+        if self.is_committed() {
+            return self.finish_open_chunked().await;
+        }
         let (status, version, body, headers) = {
             let meta = self
                 .local
                 .get_mut::<HttpMetadata>()
                 .ok_or_else(|| anyhow::anyhow!("HttpMetadata not found"))?;
-            meta.headers
-                .insert(HeaderKey::ContentLength, meta.body.len().to_string());
             let body = std::mem::take(&mut meta.body);
             let headers = std::mem::replace(&mut meta.headers, Headers::new());
             (meta.status, meta.version, body, headers)
@@ -89,6 +494,9 @@ impl<'a> Response<'a> {
     }
 
     pub async fn send_failure(&mut self) -> anyhow::Result<()> {
+        if self.is_committed() {
+            return self.finish_open_chunked().await;
+        }
         let (status, version, body, headers) = {
             let meta = self
                 .local
@@ -100,8 +508,6 @@ impl<'a> Response<'a> {
             if meta.body.is_empty() {
                 meta.body = b"Error".to_vec();
             }
-            meta.headers
-                .insert(HeaderKey::ContentLength, meta.body.len().to_string());
             let body = std::mem::take(&mut meta.body);
             let headers = std::mem::replace(&mut meta.headers, Headers::new());
             (meta.status, meta.version, body, headers)
@@ -10,25 +10,32 @@
 //! | Param | `/api/users/:id` | Captures `:id` as parameter |
 //! | Wildcard | `/static/*` | Matches any remaining path |
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
+use futures::FutureExt;
+use regex::Regex;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use tokio::io::AsyncReadExt;
+use tokio::io::AsyncBufReadExt;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
-use crate::connection::context::Context;
+use crate::connection::context::{Context, DisconnectWatch};
 use crate::http::meta::HttpMetadata;
-use crate::http::params::{Params, SmallParams};
+use crate::http::params::{Params, SmallParams, split_url};
 use crate::http::protocol::header::HeaderKey;
 use crate::http::protocol::media_type::SubMediaType;
 use crate::http::protocol::method::HttpMethod;
 use crate::http::protocol::status::StatusCode;
 use crate::http::protocol::version::HttpVersion;
+use crate::http::req::{BodyTooLarge, HeaderTooLarge, StreamedBody};
+use crate::http::res::DefaultHeaders;
 use crate::http::types::Executor;
 
 #[derive(Debug, Clone)]
 pub enum NodeType {
     Static(String),
-    Param(String),
+    /// 参数名与可选的约束，如 `:id(\d+)`，约束按整段匹配
+    Param(String, Option<Regex>),
     Wildcard,
 }
 
@@ -37,19 +44,50 @@ impl NodeType {
         matches!(self, NodeType::Static(_))
     }
     pub fn is_param(&self) -> bool {
-        matches!(self, NodeType::Param(_))
+        matches!(self, NodeType::Param(..))
     }
     pub fn is_wildcard(&self) -> bool {
         matches!(self, NodeType::Wildcard)
     }
 }
 
+/// 注册路由时检测到的冲突
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteConflict {
+    /// 同一路径与方法已注册过处理器
+    Duplicate { pattern: String, method: String },
+    /// 同一位置上的参数名或约束与已注册的不同，如 `/users/:id` 与 `/users/:uid/posts`
+    ParamMismatch { pattern: String, existing: String },
+    /// 参数段与通配符都能匹配同一个单段路径，如 `/a/:id` 与 `/a/*`
+    Ambiguous { pattern: String, other: String },
+}
+
+impl std::fmt::Display for RouteConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteConflict::Duplicate { pattern, method } => {
+                write!(f, "{} {} is already registered", method, pattern)
+            }
+            RouteConflict::ParamMismatch { pattern, existing } => {
+                write!(f, "{} conflicts with parameter {}", pattern, existing)
+            }
+            RouteConflict::Ambiguous { pattern, other } => {
+                write!(f, "{} is ambiguous with {}", pattern, other)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteConflict {}
+
 pub struct RouteBuilder<'a> {
     router: &'a mut Router,
     method: &'static str,
     path: String,
     handler: Arc<Executor>,
     middlewares: Vec<Arc<Executor>>,
+    afters: Vec<Arc<Executor>>,
+    stream_body: bool,
 }
 
 impl<'a> RouteBuilder<'a> {
@@ -65,84 +103,42 @@ impl<'a> RouteBuilder<'a> {
             path,
             handler,
             middlewares: Vec::new(),
+            afters: Vec::new(),
+            stream_body: false,
         }
     }
 
+    /// Skip body pre-reading for this route. The handler reads the body
+    /// incrementally via `Request::body_stream`.
+    pub fn stream_body(mut self) -> Self {
+        self.stream_body = true;
+        self
+    }
+
     /// Add middleware to the route. Middlewares are executed before the handler.
     pub fn middleware(mut self, mw: Arc<Executor>) -> Self {
         self.middlewares.push(mw);
         self
     }
 
+    /// Add an after-hook to the route. After-hooks run once the handler
+    /// completes, even when a middleware interrupted the request.
+    pub fn after(mut self, hook: Arc<Executor>) -> Self {
+        self.afters.push(hook);
+        self
+    }
+
     /// Register the route with the router.
     pub fn register(self) {
-        let segments: Vec<&str> = self.path.split('/').filter(|s| !s.is_empty()).collect();
-
-        let method_key = self.method.to_uppercase();
-
-        if segments.is_empty() {
-            let router = &mut *self.router;
-            if router.handlers.is_none() {
-                router.handlers = Some(AHashMap::with_capacity(8));
-            }
-            router
-                .handlers
-                .as_mut()
-                .unwrap()
-                .insert(method_key.clone(), self.handler.clone());
-            if !self.middlewares.is_empty() {
-                if router.middlewares.is_none() {
-                    router.middlewares = Some(AHashMap::with_capacity(4));
-                }
-                router
-                    .middlewares
-                    .as_mut()
-                    .unwrap()
-                    .insert(method_key, self.middlewares.clone());
-            }
-            return;
-        }
-
-        let mut current: &mut Router = self.router;
-        for seg in &segments {
-            current = if *seg == "*" {
-                current
-                    .wildcard
-                    .get_or_insert_with(|| Box::new(Router::new(NodeType::Wildcard)))
-            } else if seg.starts_with(':') {
-                let (_, router) = current.param.get_or_insert_with(|| {
-                    (
-                        seg[1..].to_string(),
-                        Box::new(Router::new(NodeType::Param(seg[1..].into()))),
-                    )
-                });
-                &mut **router
-            } else {
-                current
-                    .statics
-                    .entry(seg.to_string())
-                    .or_insert_with(|| Router::new(NodeType::Static(seg.to_string())))
-            };
+        let middlewares = (!self.middlewares.is_empty()).then_some(self.middlewares);
+        self.router
+            .insert(&self.path, Some(self.method), self.handler, middlewares);
+        if !self.afters.is_empty() {
+            self.router
+                .insert_afters(&self.path, Some(self.method), self.afters);
         }
-
-        if current.handlers.is_none() {
-            current.handlers = Some(AHashMap::with_capacity(8));
-        }
-        current
-            .handlers
-            .as_mut()
-            .unwrap()
-            .insert(method_key.clone(), self.handler.clone());
-
-        if !self.middlewares.is_empty() {
-            if current.middlewares.is_none() {
-                current.middlewares = Some(AHashMap::with_capacity(4));
-            }
-            current
-                .middlewares
-                .as_mut()
-                .unwrap()
-                .insert(method_key, self.middlewares.clone());
+        if self.stream_body {
+            self.router.insert_streaming(&self.path, Some(self.method));
         }
     }
 }
@@ -155,6 +151,18 @@ pub struct Router {
     pub wildcard: Option<Box<Router>>,
     pub middlewares: Option<AHashMap<String, Vec<Arc<Executor>>>>,
     pub handlers: Option<AHashMap<String, Arc<Executor>>>,
+    /// 后置执行器，处理器（或被中断的中间件）之后总会执行，返回值被忽略
+    pub afters: Option<AHashMap<String, Vec<Arc<Executor>>>>,
+    /// 流式读取请求体的方法，路由不预读请求体，由处理器调用 `body_stream`
+    pub streaming: Option<AHashSet<String>>,
+    /// 注册时的路由模板（仅终点节点），如 `/users/:id`
+    pub pattern: Option<String>,
+    /// 未命中任何路由时的处理器（仅根节点生效）
+    pub not_found: Option<Arc<Executor>>,
+    /// 响应为 5xx 时的处理器（仅根节点生效）
+    pub error_handler: Option<Arc<Executor>>,
+    /// 全局中间件，命中任意路由时先于路由中间件执行（仅根节点生效）
+    pub global_middlewares: Vec<Arc<Executor>>,
 }
 
 impl Router {
@@ -167,9 +175,36 @@ impl Router {
             wildcard: None,
             middlewares: None,
             handlers: None,
+            afters: None,
+            streaming: None,
+            pattern: None,
+            not_found: None,
+            error_handler: None,
+            global_middlewares: Vec::new(),
         }
     }
 
+    /// 设置自定义 404 处理器，调用前状态码已置为 404
+    pub fn set_not_found(&mut self, handler: Arc<Executor>) -> &mut Self {
+        self.not_found = Some(handler);
+        self
+    }
+
+    /// 设置自定义 5xx 处理器，在处理器返回（或 panic）后状态码为 5xx 时调用
+    pub fn set_error_handler(&mut self, handler: Arc<Executor>) -> &mut Self {
+        self.error_handler = Some(handler);
+        self
+    }
+
+    /// 添加全局中间件，对所有命中的路由生效。
+    ///
+    /// 执行顺序：全局中间件（按添加顺序）→ 路由中间件 → 处理器；
+    /// 任一中间件返回 false 即中断，后置执行器照常运行。未命中路由时不执行
+    pub fn use_global(&mut self, mw: Arc<Executor>) -> &mut Self {
+        self.global_middlewares.push(mw);
+        self
+    }
+
     #[cfg(feature = "router-cache")]
     pub fn finalize(&mut self) {
         if let Some((_, ref mut child)) = self.param {
@@ -183,25 +218,6 @@ impl Router {
         }
     }
 
-    #[inline]
-    fn match_seg<'a>(&'a self, seg: &str, params: &mut SmallParams) -> Option<&'a Router> {
-        // 1. Static match first
-        if let Some(node) = self.statics.get(seg) {
-            return Some(node);
-        }
-
-        // 2. Param match
-        if let Some((ref name, ref node)) = self.param {
-            if node.node_type.is_param() {
-                params.insert(name.clone(), (*seg).to_string());
-                return Some(node);
-            }
-        }
-
-        // 3. Wildcard matches remaining path
-        self.wildcard.as_ref().map(|n| n.as_ref())
-    }
-
     /// Fluent route registration: GET method.
     pub fn get(&mut self, path: &str, handler: Arc<Executor>) -> RouteBuilder<'_> {
         RouteBuilder::new(self, "GET", path.to_string(), handler)
@@ -243,6 +259,9 @@ impl Router {
     }
 
     /// Register a handler for a specific path and method.
+    ///
+    /// 与已有路由冲突时（见 [`Router::check_conflict`]）记录一条 warn 日志后照常注册，
+    /// 重复注册会覆盖原处理器；需要拒绝冲突时使用 [`Router::try_insert`]
     pub fn insert(
         &mut self,
         path: &str,
@@ -250,8 +269,152 @@ impl Router {
         handler: Arc<Executor>,
         middlewares: Option<Vec<Arc<Executor>>>,
     ) {
+        if let Err(conflict) = self.check_conflict(path, method) {
+            tracing::warn!(target: "aex", "route conflict: {}", conflict);
+        }
+        self.insert_unchecked(path, method, handler, middlewares);
+    }
+
+    /// 与 `insert` 相同，但检测到冲突时不注册并返回错误
+    pub fn try_insert(
+        &mut self,
+        path: &str,
+        method: Option<&str>,
+        handler: Arc<Executor>,
+        middlewares: Option<Vec<Arc<Executor>>>,
+    ) -> Result<(), RouteConflict> {
+        self.check_conflict(path, method)?;
+        self.insert_unchecked(path, method, handler, middlewares);
+        Ok(())
+    }
+
+    /// 检查注册 `path` + `method` 是否与已有路由冲突：
+    /// 同一方法重复注册、同一位置参数名或约束不一致、参数段与通配符同时作为终点
+    pub fn check_conflict(&self, path: &str, method: Option<&str>) -> Result<(), RouteConflict> {
+        let method_key = method.unwrap_or("*").to_uppercase();
         let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let pattern = format!("/{}", segments.join("/"));
+        let ambiguous = |other: &Router| RouteConflict::Ambiguous {
+            pattern: pattern.clone(),
+            other: other.pattern.clone().unwrap_or_default(),
+        };
+
+        let mut current = self;
+        for (i, seg) in segments.iter().enumerate() {
+            let last = i + 1 == segments.len();
+            let next = if *seg == "*" {
+                if let Some((_, param)) = &current.param
+                    && last
+                    && param.handlers.is_some()
+                {
+                    return Err(ambiguous(param));
+                }
+                current.wildcard.as_deref()
+            } else if let Some(param) = seg.strip_prefix(':') {
+                let (name, constraint) = Self::parse_param(param);
+                if let Some((_, existing)) = &current.param
+                    && let NodeType::Param(existing_name, existing_constraint) = &existing.node_type
+                    && (*existing_name != name
+                        || existing_constraint.as_ref().map(Regex::as_str)
+                            != constraint.as_ref().map(Regex::as_str))
+                {
+                    return Err(RouteConflict::ParamMismatch {
+                        pattern,
+                        existing: existing.segment(),
+                    });
+                }
+                if let Some(wildcard) = &current.wildcard
+                    && last
+                    && wildcard.handlers.is_some()
+                {
+                    return Err(ambiguous(wildcard));
+                }
+                current.param.as_ref().map(|(_, node)| &**node)
+            } else {
+                current.statics.get(*seg)
+            };
+            match next {
+                Some(node) => current = node,
+                // 新分支，后续不会与已有路由重叠
+                None => return Ok(()),
+            }
+        }
+
+        if current
+            .handlers
+            .as_ref()
+            .is_some_and(|h| h.contains_key(&method_key))
+        {
+            return Err(RouteConflict::Duplicate {
+                pattern,
+                method: method_key,
+            });
+        }
+        Ok(())
+    }
+
+    fn insert_unchecked(
+        &mut self,
+        path: &str,
+        method: Option<&str>,
+        handler: Arc<Executor>,
+        middlewares: Option<Vec<Arc<Executor>>>,
+    ) {
         let method_key = method.unwrap_or("*").to_uppercase();
+        let node = self.node_mut(path);
+        if node.handlers.is_none() {
+            node.handlers = Some(AHashMap::with_capacity(8));
+        }
+        node.handlers
+            .as_mut()
+            .unwrap()
+            .insert(method_key.clone(), handler);
+
+        // 设置中间件
+        if let Some(mws) = middlewares {
+            if node.middlewares.is_none() {
+                node.middlewares = Some(AHashMap::with_capacity(4));
+            }
+            node.middlewares.as_mut().unwrap().insert(method_key, mws);
+        }
+    }
+
+    /// 为多个方法注册同一个处理器与中间件
+    pub fn insert_methods(
+        &mut self,
+        path: &str,
+        methods: &[&str],
+        handler: Arc<Executor>,
+        middlewares: Option<Vec<Arc<Executor>>>,
+    ) {
+        for method in methods {
+            self.insert(path, Some(method), handler.clone(), middlewares.clone());
+        }
+    }
+
+    /// 为指定路径与方法注册后置执行器，追加到已有列表之后
+    pub fn insert_afters(&mut self, path: &str, method: Option<&str>, afters: Vec<Arc<Executor>>) {
+        let method_key = method.unwrap_or("*").to_uppercase();
+        let node = self.node_mut(path);
+        node.afters
+            .get_or_insert_with(|| AHashMap::with_capacity(4))
+            .entry(method_key)
+            .or_default()
+            .extend(afters);
+    }
+
+    /// 将指定路径与方法标记为流式请求体路由
+    pub fn insert_streaming(&mut self, path: &str, method: Option<&str>) {
+        let method_key = method.unwrap_or("*").to_uppercase();
+        self.node_mut(path)
+            .streaming
+            .get_or_insert_with(AHashSet::new)
+            .insert(method_key);
+    }
+
+    /// 沿路径找到（必要时创建）终点节点
+    fn node_mut(&mut self, path: &str) -> &mut Router {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
 
         let mut current = self;
         for seg in &segments {
@@ -259,11 +422,12 @@ impl Router {
                 current
                     .wildcard
                     .get_or_insert_with(|| Box::new(Router::new(NodeType::Wildcard)))
-            } else if seg.starts_with(':') {
+            } else if let Some(param) = seg.strip_prefix(':') {
                 let (_, router) = current.param.get_or_insert_with(|| {
+                    let (name, constraint) = Self::parse_param(param);
                     (
-                        seg[1..].to_string(),
-                        Box::new(Router::new(NodeType::Param(seg[1..].into()))),
+                        name.clone(),
+                        Box::new(Router::new(NodeType::Param(name, constraint))),
                     )
                 });
                 &mut **router
@@ -275,52 +439,148 @@ impl Router {
             };
         }
 
-        let node = current;
-        if node.handlers.is_none() {
-            node.handlers = Some(AHashMap::with_capacity(8));
+        if current.pattern.is_none() {
+            current.pattern = Some(format!("/{}", segments.join("/")));
         }
-        node.handlers
-            .as_mut()
-            .unwrap()
-            .insert(method_key.clone(), handler);
+        current
+    }
 
-        // 设置中间件
-        if let Some(mws) = middlewares {
-            if node.middlewares.is_none() {
-                node.middlewares = Some(AHashMap::with_capacity(4));
+    /// 拆分 `name(regex)`；约束非法时在注册阶段直接 panic
+    fn parse_param(param: &str) -> (String, Option<Regex>) {
+        match param.split_once('(') {
+            Some((name, rest)) if rest.ends_with(')') => {
+                let pattern = &rest[..rest.len() - 1];
+                let re = Regex::new(&format!("^(?:{})$", pattern))
+                    .unwrap_or_else(|e| panic!("invalid constraint for :{}: {}", name, e));
+                (name.to_string(), Some(re))
             }
-            node.middlewares.as_mut().unwrap().insert(method_key, mws);
+            _ => (param.to_string(), None),
         }
     }
 
-    /// 匹配路径（迭代版本，无回溯）
-    #[inline]
+    /// 匹配路径（回溯版本）
+    ///
+    /// 优先级：静态 > 参数 > 通配符。只有静态与参数分支都无法完整匹配到
+    /// 已注册处理器的节点时，才回退到通配符。
     pub fn match_route<'a>(
         &'a self,
         segs: &[&str],
         params: &mut SmallParams,
     ) -> Option<&'a Router> {
-        let mut current = self;
-        for seg in segs {
-            let next = current.match_seg(seg, params)?;
-            if matches!(next.node_type, NodeType::Wildcard) {
-                return Some(next);
+        let Some((seg, rest)) = segs.split_first() else {
+            return self.handlers.as_ref().map(|_| self);
+        };
+
+        // 1. Static match first
+        if let Some(found) = self
+            .statics
+            .get(*seg)
+            .and_then(|node| node.match_route(rest, params))
+        {
+            return Some(found);
+        }
+
+        // 2. Param match (skipped when the constraint rejects the segment),
+        //    undo the binding when the branch fails
+        if let Some((ref name, ref node)) = self.param
+            && !matches!(&node.node_type, NodeType::Param(_, Some(re)) if !re.is_match(seg))
+        {
+            let mark = params.len();
+            params.insert(name.clone(), (*seg).to_string());
+            if let Some(found) = node.match_route(rest, params) {
+                return Some(found);
+            }
+            params.truncate(mark);
+        }
+
+        // 3. Wildcard matches remaining path
+        self.wildcard.as_deref()
+    }
+
+    /// 规范化路径并拆分为段：忽略空段（`//`）与 `.`，`..` 回退一级；
+    /// 越过根目录时返回 None
+    pub fn normalize_path(path: &str) -> Option<Vec<&str>> {
+        let mut segments = Vec::new();
+        for seg in path.split('/') {
+            match seg {
+                "" | "." => {}
+                ".." => {
+                    segments.pop()?;
+                }
+                _ => segments.push(seg),
+            }
+        }
+        Some(segments)
+    }
+
+    /// 列出所有已注册的路由：`(路由模板, 方法, 是否有路由中间件)`，按模板与方法排序。
+    /// 模板由各节点的 `NodeType` 还原，参数约束写回为 `:name(regex)`，
+    /// 匹配所有方法的路由方法为 `*`
+    pub fn routes(&self) -> Vec<(String, String, bool)> {
+        let mut routes = Vec::new();
+        self.collect_routes(&mut Vec::new(), &mut routes);
+        routes.sort();
+        routes
+    }
+
+    /// 以表格形式打印 `routes()` 的结果，便于调试
+    pub fn print_routes(&self) {
+        for (pattern, method, has_middleware) in self.routes() {
+            let mw = if has_middleware { " [middleware]" } else { "" };
+            println!("{:<8}{}{}", method, pattern, mw);
+        }
+    }
+
+    fn collect_routes(&self, segments: &mut Vec<String>, out: &mut Vec<(String, String, bool)>) {
+        if let Some(handlers) = &self.handlers {
+            let pattern = format!("/{}", segments.join("/"));
+            for method in handlers.keys() {
+                let has_middleware = self
+                    .middlewares
+                    .as_ref()
+                    .is_some_and(|m| m.get(method).is_some_and(|mws| !mws.is_empty()));
+                out.push((pattern.clone(), method.clone(), has_middleware));
             }
-            current = next;
         }
-        Some(current)
+
+        let children = self
+            .statics
+            .values()
+            .chain(self.param.as_ref().map(|(_, node)| &**node))
+            .chain(self.wildcard.as_deref());
+        for child in children {
+            segments.push(child.segment());
+            child.collect_routes(segments, out);
+            segments.pop();
+        }
+    }
+
+    /// 由节点类型还原注册时的路径段
+    fn segment(&self) -> String {
+        match &self.node_type {
+            NodeType::Static(name) => name.clone(),
+            NodeType::Param(name, None) => format!(":{}", name),
+            NodeType::Param(name, Some(re)) => {
+                // 注册时包装为 `^(?:...)$`，还原时去掉
+                let raw = re.as_str();
+                let inner = raw
+                    .strip_prefix("^(?:")
+                    .and_then(|r| r.strip_suffix(")$"))
+                    .unwrap_or(raw);
+                format!(":{}({})", name, inner)
+            }
+            NodeType::Wildcard => "*".to_string(),
+        }
     }
 
     /// 从路由树中查找处理器（供 HTTP/2 使用）
     /// 返回: bool - 路由是否存在
     pub fn has_route(&self, method: &str, path: &str) -> bool {
-        let pure_path = path.split('?').next().unwrap_or("");
+        let (pure_path, _) = split_url(path);
 
-        let segments: Vec<&str> = pure_path
-            .trim_start_matches('/')
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .collect();
+        let Some(segments) = Self::normalize_path(pure_path) else {
+            return false;
+        };
 
         let mut params = crate::http::params::SmallParams::with_capacity(8.min(segments.len()));
 
@@ -342,89 +602,297 @@ impl Router {
     // 执行路由
     // --------------------------------------
 
+    /// 执行路由；中间件或处理器 panic 时转换为 500 并关闭连接，不影响服务器
     pub async fn on_request(&self, ctx: &mut Context) -> bool {
+        let started = std::time::Instant::now();
+        // 流式响应、send_file 等会在处理器内提交响应头，默认头部需在此之前就位
+        ctx.local.set_value(DefaultHeaders::new(&ctx.global));
+        ctx.cancel_token = CancellationToken::new();
+        let deadline = ctx.global.request_timeout.map(|timeout| {
+            let token = ctx.cancel_token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                token.cancel();
+            })
+        });
+        let ok = self.route_catching(ctx).await;
+        if let Some(timer) = deadline {
+            timer.abort();
+        }
+        // 请求结束后停止断开监测任务
+        if ctx.local.get_ref::<DisconnectWatch>().is_some() {
+            ctx.cancel_token.cancel();
+        }
+
+        let failed = ctx
+            .local
+            .get_ref::<HttpMetadata>()
+            .is_some_and(|m| m.status as u16 >= 500);
+        if failed && let Some(handler) = &self.error_handler {
+            let _ = AssertUnwindSafe(handler(ctx)).catch_unwind().await;
+        }
+        Self::apply_default_headers(ctx);
+        Self::warn_if_slow(ctx, started.elapsed());
+        ok
+    }
+
+    /// 写入配置的 Server 头与安全 Header，已存在的不覆盖；
+    /// HTTP/2 等直接读取 `meta.headers` 构建响应的调用方依赖这里
+    fn apply_default_headers(ctx: &mut Context) {
+        let Some(defaults) = ctx.local.get_ref::<DefaultHeaders>().cloned() else {
+            return;
+        };
+        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+            defaults.apply(&mut meta.headers);
+        }
+    }
+
+    /// 超过 `GlobalContext::slow_request_threshold` 时记录一条 warn 日志
+    fn warn_if_slow(ctx: &Context, elapsed: std::time::Duration) {
+        let Some(threshold) = ctx.global.slow_request_threshold else {
+            return;
+        };
+        if elapsed <= threshold {
+            return;
+        }
+        if let Some(meta) = ctx.local.get_ref::<HttpMetadata>() {
+            tracing::warn!(
+                target: "aex",
+                method = meta.method.to_str(),
+                path = %meta.path,
+                route = meta.matched_route.as_deref().unwrap_or("-"),
+                elapsed_ms = elapsed.as_millis() as u64,
+                "slow request"
+            );
+        }
+    }
+
+    async fn route_catching(&self, ctx: &mut Context) -> bool {
+        let mut matched = None;
+        let ok = match AssertUnwindSafe(self.route(ctx, &mut matched))
+            .catch_unwind()
+            .await
+        {
+            Ok(ok) => ok,
+            Err(panic) => {
+                tracing::error!(target: "aex", "handler panicked: {}", Self::panic_reason(&*panic));
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    meta.status = StatusCode::InternalServerError;
+                    meta.headers.insert(HeaderKey::Connection, "close");
+                    meta.body = b"Internal Server Error".to_vec();
+                }
+                false
+            }
+        };
+
+        // 中间件或处理器 panic 后仍执行后置执行器（日志、指标等），且能看到 500 响应
+        if let Some((node, method_key)) = matched
+            && let Err(panic) = AssertUnwindSafe(node.run_afters(&method_key, ctx))
+                .catch_unwind()
+                .await
+        {
+            tracing::error!(target: "aex", "after hook panicked: {}", Self::panic_reason(&*panic));
+        }
+        ok
+    }
+
+    fn panic_reason(panic: &(dyn std::any::Any + Send)) -> String {
+        panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    }
+
+    /// 匹配路由并执行；命中的节点与方法写入 `matched`，供调用方在之后执行后置执行器
+    async fn route<'r>(
+        &'r self,
+        ctx: &mut Context,
+        matched: &mut Option<(&'r Router, String)>,
+    ) -> bool {
         let pure_path = {
             let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
-            meta.path.split('?').next().unwrap_or("").to_string()
+            split_url(&meta.path).0.to_string()
         };
 
-        let segments: Vec<&str> = pure_path
-            .trim_start_matches('/')
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .collect();
+        let Some(segments) = Self::normalize_path(&pure_path) else {
+            if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                meta.status = StatusCode::BadRequest;
+                meta.body = b"Bad Request".to_vec();
+            }
+            return false;
+        };
 
         let mut path_params = SmallParams::with_capacity(segments.len().min(8));
 
-        if let Some(node) = self.match_route(&segments, &mut path_params) {
-            let (path_full, method, is_form, length) = {
-                let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
-                let is_form = meta
-                    .content_type
-                    .to_string()
-                    .contains(SubMediaType::UrlEncoded.as_str());
-                let length = meta
-                    .headers
-                    .get(&crate::http::protocol::header::HeaderKey::ContentLength)
-                    .and_then(|s| s.parse::<usize>().ok())
-                    .unwrap_or(0);
-                (meta.path.clone(), meta.method, is_form, length)
-            };
-            let mut params = Params::new(path_full);
-
-            if !path_params.is_empty() {
-                params.data = Some(path_params.into());
+        match self.match_route(&segments, &mut path_params) {
+            Some(node) => {
+                let method_key = ctx.req().method().to_str().to_uppercase();
+                let method_key = &matched.insert((node, method_key)).1;
+                Self::dispatch(node, method_key, path_params, &self.global_middlewares, ctx).await
             }
-
-            if is_form && length > 0 {
-                let mut body_bytes = vec![0u8; length];
-                if let Some(r) = ctx.reader.as_deref_mut() {
-                    let _ = r.read_exact(&mut body_bytes).await.is_ok();
-                    params.set_form(&String::from_utf8_lossy(&body_bytes));
-                } else {
-                    return false;
+            None => {
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    meta.status = StatusCode::NotFound;
+                }
+                match &self.not_found {
+                    Some(handler) => handler(ctx).await,
+                    None => true,
                 }
             }
+        }
+    }
 
-            {
-                let meta = ctx.local.get_mut::<HttpMetadata>().unwrap();
-                meta.params = Some(params);
+    /// 执行命中节点的请求体预读、中间件与处理器
+    async fn dispatch(
+        node: &Router,
+        method_key: &str,
+        path_params: SmallParams,
+        globals: &[Arc<Executor>],
+        ctx: &mut Context,
+    ) -> bool {
+        let length = ctx.req().content_length();
+        let (path_full, is_form, is_json, is_chunked) = {
+            let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+            let content_type = meta.content_type.to_string();
+            (
+                meta.path.clone(),
+                content_type.contains(SubMediaType::UrlEncoded.as_str()),
+                meta.content_type.sub_type == SubMediaType::Json,
+                meta.is_chunked,
+            )
+        };
+        let streaming = node
+            .streaming
+            .as_ref()
+            .is_some_and(|s| s.contains(method_key) || s.contains("*"));
+        let mut params = Params::new(path_full);
+
+        if !path_params.is_empty() {
+            params.data = Some(path_params.into());
+        }
+
+        // 超出上限时不读取请求体，直接 413 并关闭连接；流式路由由处理器自行限制
+        if !streaming && length > ctx.global.max_body_size {
+            if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                meta.status = StatusCode::PayloadTooLarge;
+                meta.headers.insert(HeaderKey::Connection, "close");
             }
+            return false;
+        }
 
-            let method_key = method.to_str().to_uppercase();
-
-            // 7. 执行中间件 (Middleware)
-            if let Some(mws_map) = &node.middlewares {
-                let mws = mws_map.get(&method_key).or_else(|| mws_map.get("*"));
-                if let Some(mws) = mws {
-                    for mw in mws {
-                        if !mw(ctx).await {
-                            if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
-                                if meta.status == StatusCode::Ok {
-                                    meta.status = StatusCode::BadRequest;
-                                }
-                            }
-                            return false;
-                        }
+        // 表单与 JSON 请求体预先读取，供中间件（如 validator）直接使用；
+        // chunked 请求体总是在此解码，边读边检查上限
+        if !streaming && (is_chunked || ((is_form || is_json) && length > 0)) {
+            let read_timeout = ctx.global.body_read_timeout;
+            let limit = ctx.global.max_body_size;
+            let read = async {
+                let mut req = ctx.req();
+                if is_chunked {
+                    req.read_chunked_body(limit).await
+                } else {
+                    req.read_body().await
+                }
+            };
+            match tokio::time::timeout(read_timeout, read).await {
+                Ok(Ok(body)) if is_form => params.set_form(&String::from_utf8_lossy(&body)),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) if e.is::<BodyTooLarge>() => {
+                    if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                        meta.status = StatusCode::PayloadTooLarge;
+                        meta.headers.insert(HeaderKey::Connection, "close");
                     }
+                    return false;
+                }
+                // 提前断开或超时：请求体不完整，连接无法继续复用
+                _ => {
+                    if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                        meta.status = StatusCode::BadRequest;
+                        meta.headers.insert(HeaderKey::Connection, "close");
+                        meta.body = b"Incomplete body".to_vec();
+                    }
+                    return false;
                 }
             }
+        }
+
+        {
+            let meta = ctx.local.get_mut::<HttpMetadata>().unwrap();
+            meta.params = Some(params);
+            meta.matched_route = node.pattern.clone();
+        }
 
-            // 8. 执行最终处理器 (Handler)
-            if let Some(handlers_map) = &node.handlers {
-                let handler = handlers_map
-                    .get(&method_key)
-                    .or_else(|| handlers_map.get("*"));
-                if let Some(handler) = handler {
-                    return handler(ctx).await;
+        // 7. 执行中间件 (Middleware)：全局在前，路由在后
+        let mws = node
+            .middlewares
+            .as_ref()
+            .and_then(|m| m.get(method_key).or_else(|| m.get("*")));
+        for mw in globals.iter().chain(mws.into_iter().flatten()) {
+            if !mw(ctx).await {
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>()
+                    && meta.status == StatusCode::Ok
+                {
+                    meta.status = StatusCode::BadRequest;
                 }
+                return false;
             }
-        } else {
-            if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
-                meta.status = StatusCode::NotFound;
+        }
+
+        // WebSocket 升级请求应由中间件接管；走到处理器说明该路由不支持升级，
+        // 拒绝而不是当作普通 GET 处理（426 要求客户端升级，语义相反，故用 400）
+        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>()
+            && meta.is_websocket
+        {
+            meta.status = StatusCode::BadRequest;
+            meta.body = b"WebSocket upgrade not supported on this route".to_vec();
+            return false;
+        }
+
+        // 8. 执行最终处理器 (Handler)
+        let handler = node
+            .handlers
+            .as_ref()
+            .and_then(|h| h.get(method_key).or_else(|| h.get("*")));
+        match handler {
+            Some(handler) => handler(ctx).await,
+            None => true,
+        }
+    }
+
+    /// 执行后置执行器，忽略其返回值
+    async fn run_afters(&self, method_key: &str, ctx: &mut Context) {
+        let afters = self
+            .afters
+            .as_ref()
+            .and_then(|m| m.get(method_key).or_else(|| m.get("*")));
+        if let Some(afters) = afters {
+            for hook in afters {
+                hook(ctx).await;
             }
         }
-        true
+    }
+
+    /// 请求是否带有请求体；须在处理器写入响应头之前调用（请求与响应共用 `headers`）
+    fn has_body(ctx: &mut Context) -> bool {
+        ctx.local
+            .get_ref::<HttpMetadata>()
+            .is_some_and(|m| m.is_chunked)
+            || ctx.req().content_length() > 0
+    }
+
+    /// 处理器返回后仍留在连接上的请求体：可丢弃时返回其长度，
+    /// 否则（分块、已流式读取一部分、超出上限或未发送 100 Continue）写入 `Connection: close`
+    fn leftover_body(ctx: &mut Context, length: usize, skippable: bool) -> usize {
+        if ctx.req().body_consumed() {
+            return 0;
+        }
+        if skippable && ctx.local.get_ref::<StreamedBody>().is_none() {
+            return length;
+        }
+        if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+            meta.headers.insert(HeaderKey::Connection, "close");
+        }
+        0
     }
 
     /// Determine whether the connection should be kept alive after this request.
@@ -443,21 +911,71 @@ impl Router {
         }
     }
 
+    /// 读取并解析请求行与头部，返回 false 时应关闭连接。
+    ///
+    /// 首字节须在 `idle_timeout` 内到达，否则不回复直接关闭（新连接或 keep-alive 空闲）；
+    /// 此后须在 `header_read_timeout` 内读完，否则回复 408；请求头数量或总大小超限时回复 431
+    pub(crate) async fn read_head(ctx: &mut Context) -> bool {
+        let idle = ctx.global.idle_timeout;
+        let has_data = match ctx.reader.as_deref_mut() {
+            Some(reader) => tokio::time::timeout(idle, reader.fill_buf())
+                .await
+                .is_ok_and(|r| r.is_ok_and(|b| !b.is_empty())),
+            None => false,
+        };
+        if !has_data {
+            return false;
+        }
+
+        let timeout = ctx.global.header_read_timeout;
+        let status = match tokio::time::timeout(timeout, ctx.req().parse_to_local()).await {
+            Ok(Ok(())) => return true,
+            Ok(Err(e)) if e.is::<HeaderTooLarge>() => StatusCode::RequestHeaderFieldsTooLarge,
+            Ok(Err(_)) => return false,
+            Err(_) => StatusCode::RequestTimeout,
+        };
+        let mut meta = HttpMetadata::new();
+        meta.status = status;
+        meta.headers.insert(HeaderKey::Connection, "close");
+        ctx.local.set_value(DefaultHeaders::new(&ctx.global));
+        meta.body = status.reason_phrase().as_bytes().to_vec();
+        ctx.local.set_value(meta);
+        let _ = ctx.res().send_failure().await;
+        false
+    }
+
     pub async fn handle(self: Arc<Self>, ctx: Arc<Mutex<Context>>) -> anyhow::Result<()> {
         loop {
             let guard = ctx.lock().await;
             let mut ctx = guard;
 
-            if let Err(_) = ctx.req().parse_to_local().await {
+            if !Self::read_head(&mut ctx).await {
                 break;
             }
 
-            let keep_alive = match ctx.local.get_ref::<HttpMetadata>() {
+            let mut keep_alive = match ctx.local.get_ref::<HttpMetadata>() {
                 Some(meta) => Self::wants_keep_alive(meta),
                 None => false,
             };
 
-            if self.on_request(&mut ctx).await {
+            // 响应会覆盖共享的头部，请求体信息需在处理前取出
+            let has_body = Self::has_body(&mut ctx);
+            let length = ctx.req().content_length();
+            let skippable = ctx.local.get_ref::<HttpMetadata>().is_some_and(|m| {
+                !m.is_chunked && !m.expects_continue() && length <= ctx.global.max_body_size
+            });
+            let ok = self.on_request(&mut ctx).await;
+            // 处理器没有读完的请求体仍在连接上，继续读取会被当作下一个请求解析
+            let leftover = if has_body {
+                Self::leftover_body(&mut ctx, length, skippable)
+            } else {
+                0
+            };
+            // 请求体未读取或不完整时会写入 `Connection: close`，不能继续复用连接
+            if let Some(meta) = ctx.local.get_ref::<HttpMetadata>() {
+                keep_alive &= Self::wants_keep_alive(meta);
+            }
+            if ok {
                 ctx.res().send_response().await?;
             } else {
                 ctx.res().send_failure().await?;
@@ -466,6 +984,14 @@ impl Router {
             if !keep_alive {
                 break;
             }
+            if leftover > 0 {
+                let read_timeout = ctx.global.body_read_timeout;
+                let mut req = ctx.req();
+                let discard = tokio::time::timeout(read_timeout, req.discard_body(leftover)).await;
+                if !matches!(discard, Ok(Ok(()))) {
+                    break;
+                }
+            }
 
             ctx.local = crate::connection::context::LocalTypeMap::new();
         }
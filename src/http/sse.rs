@@ -0,0 +1,125 @@
+//! # Server-Sent Events
+//!
+//! `text/event-stream` responses built on the chunked writer.
+//!
+//! ```rust,ignore
+//! router.get("/events", exe!(|ctx| {
+//!     let (tx, rx) = tokio::sync::mpsc::channel(16);
+//!     tokio::spawn(async move {
+//!         let _ = tx.send(SseEvent::new("hello").event("greeting")).await;
+//!     });
+//!     // 对端断开后停止推送
+//!     ctx.watch_disconnect();
+//!     match ctx.res().sse().await {
+//!         Ok(stream) => stream.run(rx).await.is_ok(),
+//!         Err(_) => false,
+//!     }
+//! })).register();
+//! ```
+
+use tokio::sync::mpsc;
+
+use crate::http::res::ChunkedWriter;
+
+/// 单条 SSE 事件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub id: Option<String>,
+    pub retry: Option<u64>,
+    pub data: String,
+}
+
+impl SseEvent {
+    pub fn new(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn retry(mut self, millis: u64) -> Self {
+        self.retry = Some(millis);
+        self
+    }
+
+    /// 编码为 wire 格式，多行 data 拆成多个 `data:` 字段
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = String::with_capacity(self.data.len() + 32);
+        if let Some(event) = &self.event {
+            out.push_str("event: ");
+            out.push_str(event);
+            out.push('\n');
+        }
+        if let Some(id) = &self.id {
+            out.push_str("id: ");
+            out.push_str(id);
+            out.push('\n');
+        }
+        if let Some(retry) = self.retry {
+            out.push_str(&format!("retry: {}\n", retry));
+        }
+        for line in self.data.split('\n') {
+            out.push_str("data: ");
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+        out.into_bytes()
+    }
+}
+
+/// 已建立的事件流，每条事件写出后立即 flush
+pub struct SseStream<'a> {
+    writer: ChunkedWriter<'a>,
+}
+
+impl<'a> SseStream<'a> {
+    pub(crate) fn new(writer: ChunkedWriter<'a>) -> Self {
+        Self { writer }
+    }
+
+    pub async fn send(&mut self, event: SseEvent) -> anyhow::Result<()> {
+        self.writer.send(event.to_bytes()).await
+    }
+
+    /// 注释行，常用作心跳保持连接
+    pub async fn comment(&mut self, text: &str) -> anyhow::Result<()> {
+        self.writer.send(format!(": {}\n\n", text)).await
+    }
+
+    /// 对端是否已断开，需先调用 `Context::watch_disconnect`
+    pub fn is_disconnected(&self) -> bool {
+        self.writer.is_disconnected()
+    }
+
+    /// 持续转发通道中的事件，所有发送端关闭后结束流；
+    /// 开启 `watch_disconnect` 时对端断开会立即返回错误
+    pub async fn run(mut self, mut rx: mpsc::Receiver<SseEvent>) -> anyhow::Result<()> {
+        loop {
+            let event = tokio::select! {
+                event = rx.recv() => event,
+                _ = self.writer.disconnected() => anyhow::bail!("Client disconnected"),
+            };
+            match event {
+                Some(event) => self.send(event).await?,
+                None => break,
+            }
+        }
+        self.close().await
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        self.writer.finish().await
+    }
+}
@@ -0,0 +1,138 @@
+//! # WebSocket test support
+//!
+//! 需要启用 `test-utils` feature。提供构造客户端帧、握手请求以及一个最小的
+//! WebSocket 客户端，方便对自己的处理器做端到端测试。
+
+use std::net::SocketAddr;
+
+use anyhow::{Context, bail};
+use futures::StreamExt;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+};
+use tokio_util::codec::FramedRead;
+
+use crate::http::websocket::{WSCodec, WSFrame};
+
+/// RFC 6455 示例中的 `Sec-WebSocket-Key`
+pub const SAMPLE_KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+
+const MASK: [u8; 4] = [0x37, 0xfa, 0x21, 0x3d];
+
+/// 构造一个 FIN 帧；客户端发往服务器的帧必须 `masked`
+pub fn frame(opcode: u8, payload: &[u8], masked: bool) -> Vec<u8> {
+    let mask_bit = if masked { 0x80 } else { 0 };
+    let mut out = Vec::with_capacity(payload.len() + 14);
+    out.push(0x80 | (opcode & 0x0f));
+    match payload.len() {
+        len @ 0..=125 => out.push(mask_bit | len as u8),
+        len @ 126..=0xffff => {
+            out.push(mask_bit | 126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(mask_bit | 127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    if masked {
+        out.extend_from_slice(&MASK);
+        out.extend(payload.iter().enumerate().map(|(i, b)| b ^ MASK[i % 4]));
+    } else {
+        out.extend_from_slice(payload);
+    }
+    out
+}
+
+/// 构造指向 `path` 的升级请求
+pub fn handshake_request(path: &str) -> String {
+    format!(
+        "GET {} HTTP/1.1\r\n\
+         Host: localhost\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Key: {}\r\n\
+         Sec-WebSocket-Version: 13\r\n\r\n",
+        path, SAMPLE_KEY
+    )
+}
+
+/// 最小的 WebSocket 测试客户端
+pub struct WsClient {
+    reader: FramedRead<OwnedReadHalf, WSCodec>,
+    writer: OwnedWriteHalf,
+}
+
+impl WsClient {
+    /// 连接并完成握手，服务器未返回 101 时报错
+    pub async fn connect(addr: SocketAddr, path: &str) -> anyhow::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let (mut reader, mut writer) = stream.into_split();
+        writer.write_all(handshake_request(path).as_bytes()).await?;
+
+        // 逐字节读取响应头，避免吞掉紧随其后的帧
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let byte = reader
+                .read_u8()
+                .await
+                .context("Handshake response truncated")?;
+            head.push(byte);
+        }
+        let head = String::from_utf8_lossy(&head);
+        if !head.starts_with("HTTP/1.1 101") {
+            bail!(
+                "Handshake rejected: {}",
+                head.lines().next().unwrap_or_default()
+            );
+        }
+
+        Ok(Self {
+            reader: FramedRead::new(reader, WSCodec),
+            writer,
+        })
+    }
+
+    /// 发送任意帧（自动加掩码）
+    pub async fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> anyhow::Result<()> {
+        self.writer.write_all(&frame(opcode, payload, true)).await?;
+        Ok(())
+    }
+
+    pub async fn send_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.send_frame(0x1, text.as_bytes()).await
+    }
+
+    pub async fn send_binary(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.send_frame(0x2, data).await
+    }
+
+    pub async fn send_ping(&mut self, payload: &[u8]) -> anyhow::Result<()> {
+        self.send_frame(0x9, payload).await
+    }
+
+    pub async fn close(&mut self, code: u16) -> anyhow::Result<()> {
+        self.send_frame(0x8, &code.to_be_bytes()).await
+    }
+
+    /// 读取下一帧，连接关闭时返回 None
+    pub async fn recv(&mut self) -> anyhow::Result<Option<WSFrame>> {
+        self.reader.next().await.transpose()
+    }
+
+    /// 读取下一条文本消息，跳过 Ping/Pong
+    pub async fn recv_text(&mut self) -> anyhow::Result<Option<String>> {
+        while let Some(frame) = self.recv().await? {
+            match frame {
+                WSFrame::Text(text) => return Ok(Some(text)),
+                WSFrame::Ping(_) | WSFrame::Pong(_) => continue,
+                other => bail!("Unexpected frame: {:?}", other),
+            }
+        }
+        Ok(None)
+    }
+}
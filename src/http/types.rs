@@ -7,6 +7,7 @@ use std::sync::Arc;
 use futures::future::BoxFuture;
 
 use crate::connection::context::Context;
+use crate::http::{meta::HttpMetadata, protocol::status::StatusCode};
 
 /// Executor is the core type for handling requests and middleware.
 pub type Executor = dyn for<'a> Fn(&'a mut Context) -> BoxFuture<'a, bool> + Send + Sync;
@@ -17,6 +18,15 @@ pub type RouteHandler = Arc<Executor>;
 /// Middleware chain type alias
 pub type MiddlewareChain = Vec<Arc<Executor>>;
 
+/// `exe_try!` 的错误出口：记录日志并将响应改为 500，返回 false 终止处理
+pub fn fail_with_error(ctx: &mut Context, err: anyhow::Error) -> bool {
+    tracing::error!(target: "aex", "handler error: {:#}", err);
+    if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+        meta.error(StatusCode::InternalServerError, "Internal Server Error");
+    }
+    false
+}
+
 /// Helper function to convert a closure into an Executor.
 pub fn to_executor<F>(f: F) -> Arc<Executor>
 where
@@ -7,6 +7,7 @@ use crate::{
 };
 use bincode::{Decode, Encode};
 use bytes::{BufMut, BytesMut};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
 use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use tokio_util::codec::{Decoder, Encoder};
@@ -14,7 +15,7 @@ use tokio_util::codec::{Decoder, Encoder};
 // --- WSFrame 适配 ---
 #[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, PartialEq)]
 pub enum WSFrame {
-    /// 0x0: 分片后续帧
+    /// 0x0: 分片后续帧（FIN=0，消息未结束）
     Continuation(Vec<u8>),
     /// 0x1: 文本帧
     Text(String),
@@ -30,10 +31,40 @@ pub enum WSFrame {
     Pong(Vec<u8>),
     /// 0xB - 0xF: 预留控制位
     ReservedControl(u8, Vec<u8>),
+    /// 仅用于发送：(opcode, 分片大小, 完整负载)，编码为首帧 + 若干后续帧，
+    /// 最后一帧 FIN=1；整条消息一次写出，不会与其他数据帧交错
+    Fragmented(u8, usize, Vec<u8>),
+    /// RSV1 置位的 permessage-deflate 消息：(opcode, 压缩负载)。
+    /// 解码时原样返回由读循环解压，发送时由写任务在协商成功后生成
+    Compressed(u8, Vec<u8>),
+    /// 仅用于接收：FIN=0 的消息首帧 (opcode, RSV1, 负载)，由读循环与后续帧拼接
+    FragmentStart(u8, bool, Vec<u8>),
+    /// 仅用于接收：FIN=1 的最后一个后续帧
+    FragmentEnd(Vec<u8>),
 }
 
 impl Codec for WSFrame {}
 
+impl WSFrame {
+    /// 负载字节数（不含帧头），Close 帧计入状态码与原因
+    pub fn payload_len(&self) -> usize {
+        match self {
+            WSFrame::Text(s) => s.len(),
+            WSFrame::Binary(b)
+            | WSFrame::Continuation(b)
+            | WSFrame::Ping(b)
+            | WSFrame::Pong(b)
+            | WSFrame::ReservedNonControl(_, b)
+            | WSFrame::ReservedControl(_, b)
+            | WSFrame::Fragmented(_, _, b)
+            | WSFrame::Compressed(_, b)
+            | WSFrame::FragmentStart(_, _, b)
+            | WSFrame::FragmentEnd(b) => b.len(),
+            WSFrame::Close(_, reason) => 2 + reason.as_ref().map_or(0, |r| r.len()),
+        }
+    }
+}
+
 // --- 实现 Frame Trait ---
 impl Frame for WSFrame {
     fn payload(&self) -> Option<Vec<u8>> {
@@ -44,7 +75,11 @@ impl Frame for WSFrame {
             | WSFrame::Ping(b)
             | WSFrame::Pong(b)
             | WSFrame::ReservedNonControl(_, b)
-            | WSFrame::ReservedControl(_, b) => Some(b.clone()),
+            | WSFrame::ReservedControl(_, b)
+            | WSFrame::Fragmented(_, _, b)
+            | WSFrame::Compressed(_, b)
+            | WSFrame::FragmentStart(_, _, b)
+            | WSFrame::FragmentEnd(b) => Some(b.clone()),
             WSFrame::Close(_, _) => None,
         }
     }
@@ -67,6 +102,10 @@ impl Command for WSFrame {
             WSFrame::Ping(_) => 0x9,
             WSFrame::Pong(_) => 0xa,
             WSFrame::ReservedControl(op, _) => *op as u32,
+            WSFrame::Fragmented(op, _, _) => *op as u32,
+            WSFrame::Compressed(op, _) => *op as u32,
+            WSFrame::FragmentStart(op, _, _) => *op as u32,
+            WSFrame::FragmentEnd(_) => 0x0,
         }
     }
 
@@ -80,18 +119,102 @@ impl Command for WSFrame {
             | WSFrame::Ping(b)
             | WSFrame::Pong(b)
             | WSFrame::ReservedNonControl(_, b)
-            | WSFrame::ReservedControl(_, b) => b,
+            | WSFrame::ReservedControl(_, b)
+            | WSFrame::Fragmented(_, _, b)
+            | WSFrame::Compressed(_, b)
+            | WSFrame::FragmentStart(_, _, b)
+            | WSFrame::FragmentEnd(b) => b,
             _ => &EMPTY,
         }
     }
 }
 
+/// 解码时发现的协议错误，携带应回复给对端的关闭码（RFC 6455 7.4.1）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WSCloseError {
+    pub code: u16,
+    pub reason: &'static str,
+}
+
+impl WSCloseError {
+    pub fn new(code: u16, reason: &'static str) -> Self {
+        Self { code, reason }
+    }
+}
+
+impl std::fmt::Display for WSCloseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.reason, self.code)
+    }
+}
+
+impl std::error::Error for WSCloseError {}
+
 pub struct WSCodec;
+
+impl WSCodec {
+    /// 写出一个服务端帧（不加掩码）
+    fn put_frame(dst: &mut BytesMut, fin: bool, opcode: u8, payload: &[u8]) {
+        dst.put_u8(if fin { 0x80 } else { 0 } | (opcode & 0x0f));
+
+        let len = payload.len();
+        if len < 126 {
+            dst.put_u8(len as u8);
+        } else if len <= 65535 {
+            dst.put_u8(126);
+            dst.put_u16(len as u16);
+        } else {
+            dst.put_u8(127);
+            dst.put_u64(len as u64);
+        }
+
+        dst.extend_from_slice(payload);
+    }
+}
+/// 带单帧负载上限的 [`WSCodec`]，服务端读循环使用。
+///
+/// 帧头声明的长度超过上限时在缓冲负载之前以 1009 拒绝，
+/// 与分片消息拼接时的上限一致
+#[derive(Debug, Clone, Copy)]
+pub struct BoundedWSCodec {
+    pub max_payload: usize,
+}
+
+impl BoundedWSCodec {
+    pub fn new(max_payload: usize) -> Self {
+        Self { max_payload }
+    }
+}
+
+impl Decoder for BoundedWSCodec {
+    type Item = WSFrame;
+    type Error = anyhow::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        WSCodec::decode_with_limit(src, self.max_payload)
+    }
+}
+
+impl Encoder<WSFrame> for BoundedWSCodec {
+    type Error = anyhow::Error;
+
+    fn encode(&mut self, item: WSFrame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        WSCodec.encode(item, dst)
+    }
+}
+
 impl Decoder for WSCodec {
     type Item = WSFrame;
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Self::decode_with_limit(src, usize::MAX)
+    }
+}
+
+impl WSCodec {
+    /// 解码一帧；负载超过 `limit` 或长度无法表示时以 1009 拒绝
+    fn decode_with_limit(src: &mut BytesMut, limit: usize) -> anyhow::Result<Option<WSFrame>> {
         if src.len() < 2 {
             return Ok(None);
         }
@@ -99,11 +222,12 @@ impl Decoder for WSCodec {
         let first = src[0];
         let second = src[1];
 
-        let _fin = (first & 0x80) != 0;
+        let fin = (first & 0x80) != 0;
+        let rsv1 = (first & 0x40) != 0;
         let opcode = first & 0x0f;
         let masked = (second & 0x80) != 0;
         let mut payload_len = (second & 0x7f) as usize;
-        let mut head_len = 2;
+        let mut head_len: usize = 2;
 
         // 1. 解析扩展长度 (已支持 126/127 边界)
         if payload_len == 126 {
@@ -116,18 +240,30 @@ impl Decoder for WSCodec {
             if src.len() < 10 {
                 return Ok(None);
             }
-            payload_len = u64::from_be_bytes(src[2..10].try_into()?) as usize;
+            payload_len = usize::try_from(u64::from_be_bytes(src[2..10].try_into()?))
+                .map_err(|_| WSCloseError::new(1009, "Message too big"))?;
             head_len += 8;
         }
 
+        // 控制帧负载不得超过 125 字节（RFC 6455 5.5），保证 Pong 可以原样回显 Ping 负载
+        if opcode >= 0x8 && payload_len > 125 {
+            return Err(WSCloseError::new(1002, "Control frame too large").into());
+        }
+
         // 2. 解析 Mask 偏移
         let mask_offset = head_len;
         if masked {
             head_len += 4;
         }
 
+        // 超出上限的帧在缓冲负载前直接拒绝
+        let frame_len = head_len
+            .checked_add(payload_len)
+            .filter(|_| payload_len <= limit)
+            .ok_or_else(|| WSCloseError::new(1009, "Message too big"))?;
+
         // 3. 检查半包
-        if src.len() < head_len + payload_len {
+        if src.len() < frame_len {
             return Ok(None);
         }
 
@@ -143,10 +279,32 @@ impl Decoder for WSCodec {
             }
         }
 
-        // 6. 转换为统一枚举 (全面覆盖 Opcode)
+        // 6. 分片消息交给读循环拼接；FIN=0 的后续帧仍为 Continuation
+        if !fin {
+            return match opcode {
+                0x0 => Ok(Some(WSFrame::Continuation(payload))),
+                0x1 | 0x2 => Ok(Some(WSFrame::FragmentStart(opcode, rsv1, payload))),
+                _ if opcode >= 0x8 => {
+                    Err(WSCloseError::new(1002, "Fragmented control frame").into())
+                }
+                _ => Ok(Some(WSFrame::ReservedNonControl(opcode, payload))),
+            };
+        }
+        if opcode == 0x0 && !rsv1 {
+            return Ok(Some(WSFrame::FragmentEnd(payload)));
+        }
+
+        // 7. 压缩消息在解压前无法校验 UTF-8，交给读循环处理
+        if rsv1 {
+            return Ok(Some(WSFrame::Compressed(opcode, payload)));
+        }
+
+        // 8. 转换为统一枚举 (全面覆盖 Opcode)
         match opcode {
             0x0 => Ok(Some(WSFrame::Continuation(payload))),
-            0x1 => Ok(Some(WSFrame::Text(String::from_utf8(payload)?))),
+            0x1 => String::from_utf8(payload)
+                .map(|text| Some(WSFrame::Text(text)))
+                .map_err(|_| WSCloseError::new(1007, "Invalid UTF-8 in text frame").into()),
             0x2 => Ok(Some(WSFrame::Binary(payload))),
             0x8 => {
                 let (code, reason) = WebSocket::parse_close_payload(&payload)?;
@@ -182,32 +340,279 @@ impl Encoder<WSFrame> for WSCodec {
             WSFrame::Ping(b) => (0x9u8, b),
             WSFrame::Pong(b) => (0xau8, b),
             WSFrame::ReservedControl(op, b) => (op, b),
+            WSFrame::Fragmented(op, chunk_size, b) => {
+                let chunks: Vec<&[u8]> = b.chunks(chunk_size.max(1)).collect();
+                let last = chunks.len().saturating_sub(1);
+                if chunks.is_empty() {
+                    Self::put_frame(dst, true, op, &[]);
+                }
+                for (i, chunk) in chunks.into_iter().enumerate() {
+                    Self::put_frame(dst, i == last, if i == 0 { op } else { 0x0 }, chunk);
+                }
+                return Ok(());
+            }
+            WSFrame::Compressed(op, b) => {
+                let start = dst.len();
+                Self::put_frame(dst, true, op, &b);
+                dst[start] |= 0x40;
+                return Ok(());
+            }
+            WSFrame::FragmentStart(op, rsv1, b) => {
+                let start = dst.len();
+                Self::put_frame(dst, false, op, &b);
+                if rsv1 {
+                    dst[start] |= 0x40;
+                }
+                return Ok(());
+            }
+            WSFrame::FragmentEnd(b) => (0x0u8, b),
         };
 
-        // 目前默认 FIN = 1。如果后续要做分片发送，可根据 Continuation 逻辑动态调整
-        dst.put_u8(0x80 | (opcode & 0x0f));
+        Self::put_frame(dst, true, opcode, &payload);
+        Ok(())
+    }
+}
 
-        let len = payload.len();
-        if len < 126 {
-            dst.put_u8(len as u8);
-        } else if len <= 65535 {
-            dst.put_u8(126);
-            dst.put_u16(len as u16);
-        } else {
-            dst.put_u8(127);
-            dst.put_u64(len as u64);
+/// 客户端在 `Sec-WebSocket-Extensions` 中提出的扩展，按出现顺序保存为 (名称, 参数)。
+///
+/// 握手后写入 `ctx.local`（见 `WebSocket::extensions`），仅供处理器记录或决策，
+/// 不代表服务端已启用。名称与参数名转为小写，参数值去掉引号
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WSExtensionOffers(pub Vec<(String, ExtensionParams)>);
+
+/// 单个扩展提议的参数：(参数名, 值)，无值参数为 None
+pub type ExtensionParams = Vec<(String, Option<String>)>;
+
+impl WSExtensionOffers {
+    pub fn parse(header: &str) -> Self {
+        let offers = header
+            .split(',')
+            .filter_map(|offer| {
+                let mut parts = offer.split(';').map(str::trim);
+                let name = parts.next().filter(|n| !n.is_empty())?.to_ascii_lowercase();
+                let params = parts
+                    .filter(|p| !p.is_empty())
+                    .map(|param| match param.split_once('=') {
+                        Some((k, v)) => (
+                            k.trim().to_ascii_lowercase(),
+                            Some(v.trim().trim_matches('"').to_string()),
+                        ),
+                        None => (param.to_ascii_lowercase(), None),
+                    })
+                    .collect();
+                Some((name, params))
+            })
+            .collect();
+        Self(offers)
+    }
+
+    /// 指定名称的第一个提议的参数
+    pub fn get(&self, name: &str) -> Option<&[(String, Option<String>)]> {
+        self.0
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, params)| params.as_slice())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// permessage-deflate 扩展参数（RFC 7692）。
+///
+/// 作为服务端配置时表示强制要求的参数；协商成功后表示双方约定的参数，
+/// 写入 `ctx.local` 供 `WebSocket::run` 使用。
+/// 未设置 no_context_takeover 时压缩窗口跨消息保留，重复内容压缩率更高
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerMessageDeflate {
+    /// 服务端每条消息后重置压缩上下文
+    pub server_no_context_takeover: bool,
+    /// 客户端每条消息后重置压缩上下文（服务端解压时同步重置）
+    pub client_no_context_takeover: bool,
+}
+
+impl PerMessageDeflate {
+    pub const TOKEN: &'static str = "permessage-deflate";
+
+    /// 从 `Sec-WebSocket-Extensions` 中按顺序选取第一个可接受的 permessage-deflate 提议。
+    ///
+    /// 不支持缩小服务端窗口（`server_max_window_bits` 小于 15 的提议被拒绝）；
+    /// `client_max_window_bits` 总是可接受，解压使用最大窗口。
+    /// 未知或重复的参数使该提议无效
+    pub fn negotiate(&self, offers: &str) -> Option<PerMessageDeflate> {
+        offers.split(',').find_map(|offer| {
+            let mut params = offer.split(';').map(str::trim);
+            if !params.next()?.eq_ignore_ascii_case(Self::TOKEN) {
+                return None;
+            }
+
+            let mut agreed = *self;
+            let mut seen = Vec::new();
+            for param in params.filter(|p| !p.is_empty()) {
+                let (name, value) = match param.split_once('=') {
+                    Some((n, v)) => (n.trim(), Some(v.trim().trim_matches('"'))),
+                    None => (param, None),
+                };
+                let name = name.to_ascii_lowercase();
+                if seen.contains(&name) {
+                    return None;
+                }
+                match (name.as_str(), value) {
+                    ("server_no_context_takeover", None) => {
+                        agreed.server_no_context_takeover = true
+                    }
+                    ("client_no_context_takeover", None) => {
+                        agreed.client_no_context_takeover = true
+                    }
+                    ("server_max_window_bits", Some("15")) => {}
+                    ("client_max_window_bits", None) => {}
+                    ("client_max_window_bits", Some(bits))
+                        if bits.parse::<u8>().is_ok_and(|b| (8..=15).contains(&b)) => {}
+                    _ => return None,
+                }
+                seen.push(name);
+            }
+            Some(agreed)
+        })
+    }
+
+    /// 握手响应中的 `Sec-WebSocket-Extensions` 值
+    pub fn response_header(&self) -> String {
+        let mut value = Self::TOKEN.to_string();
+        if self.server_no_context_takeover {
+            value.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; client_no_context_takeover");
+        }
+        value
+    }
+}
+
+/// 压缩块末尾的空 stored block，发送前去掉，解压前补回
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// 发送方向的消息压缩器，context takeover 时跨消息保留滑动窗口
+pub struct MessageDeflater {
+    inner: Compress,
+    no_context_takeover: bool,
+}
+
+impl MessageDeflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        Self {
+            inner: Compress::new(Compression::default(), false),
+            no_context_takeover,
         }
+    }
 
-        dst.extend_from_slice(&payload);
-        Ok(())
+    /// 压缩一条完整消息，返回去掉 `00 00 ff ff` 尾部的负载
+    pub fn compress(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.inner.total_in();
+        loop {
+            let consumed = (self.inner.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity());
+            }
+            self.inner
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)?;
+            // 输入耗尽且输出缓冲未写满，说明同步刷新已完成
+            if (self.inner.total_in() - start) as usize == data.len() && out.len() < out.capacity()
+            {
+                break;
+            }
+        }
+        if out.ends_with(&DEFLATE_TAIL) {
+            out.truncate(out.len() - DEFLATE_TAIL.len());
+        }
+        if self.no_context_takeover {
+            self.inner.reset();
+        }
+        Ok(out)
+    }
+}
+
+/// 接收方向的消息解压器，context takeover 时跨消息保留滑动窗口
+pub struct MessageInflater {
+    inner: Decompress,
+    no_context_takeover: bool,
+}
+
+impl MessageInflater {
+    pub fn new(no_context_takeover: bool) -> Self {
+        Self {
+            inner: Decompress::new(false),
+            no_context_takeover,
+        }
+    }
+
+    /// 解压一条完整消息；解压结果超过 `limit` 字节时以 1009 中止
+    pub fn decompress(&mut self, data: &[u8], limit: usize) -> Result<Vec<u8>, WSCloseError> {
+        let mut input = Vec::with_capacity(data.len() + DEFLATE_TAIL.len());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&DEFLATE_TAIL);
+
+        let mut out = Vec::with_capacity(data.len() * 2 + 64);
+        let start = self.inner.total_in();
+        loop {
+            let consumed = (self.inner.total_in() - start) as usize;
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity());
+            }
+            let produced = out.len();
+            self.inner
+                .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+                .map_err(|_| WSCloseError::new(1007, "Invalid compressed data"))?;
+            if out.len() > limit {
+                return Err(WSCloseError::new(1009, "Message too big"));
+            }
+            let now_consumed = (self.inner.total_in() - start) as usize;
+            if now_consumed == input.len() && out.len() < out.capacity() {
+                break;
+            }
+            // 没有任何进展：输入不完整或已损坏
+            if now_consumed == consumed && out.len() == produced && out.len() < out.capacity() {
+                return Err(WSCloseError::new(1007, "Invalid compressed data"));
+            }
+        }
+        if self.no_context_takeover {
+            self.inner.reset(false);
+        }
+        Ok(out)
     }
 }
 
 pub type WebSocketHandler =
     Arc<dyn (Fn(&WebSocket, &mut Context, WSFrame) -> BoxFuture<'static, bool>) + Send + Sync>;
 
-pub type TextHandler =
-    Arc<dyn (Fn(&WebSocket, &mut Context, String) -> BoxFuture<'static, bool>) + Send + Sync>;
+/// 文本/二进制处理器的返回值：继续读取下一条消息，或以指定关闭码和原因断开
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandlerAction {
+    Continue,
+    Close(u16, String),
+}
+
+/// 兼容返回 bool 的处理器：`true` 继续，`false` 以 1000 正常关闭
+impl From<bool> for HandlerAction {
+    fn from(keep: bool) -> Self {
+        if keep {
+            HandlerAction::Continue
+        } else {
+            HandlerAction::Close(1000, String::new())
+        }
+    }
+}
+
+pub type TextHandler = Arc<
+    dyn (Fn(&WebSocket, &mut Context, String) -> BoxFuture<'static, HandlerAction>) + Send + Sync,
+>;
 
-pub type BinaryHandler =
-    Arc<dyn (Fn(&WebSocket, &mut Context, Vec<u8>) -> BoxFuture<'static, bool>) + Send + Sync>;
+pub type BinaryHandler = Arc<
+    dyn (Fn(&WebSocket, &mut Context, Vec<u8>) -> BoxFuture<'static, HandlerAction>) + Send + Sync,
+>;
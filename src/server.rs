@@ -41,11 +41,18 @@ use crate::tcp::types::RawCodec;
 use crate::udp::router::Router as UdpRouter;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
 use tokio::net::UdpSocket;
-use tokio::sync::Mutex;
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::{Mutex, Semaphore};
 use tokio_util::sync::CancellationToken;
 
+/// 连接读写缓冲区的默认容量，与 tokio 的 BufReader/BufWriter 一致
+pub const DEFAULT_IO_BUFFER_SIZE: usize = 8 * 1024;
+
+/// 超出连接上限时直接写回的响应
+const SERVICE_UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
 #[allow(dead_code)]
 type Extractor = Arc<dyn Fn(&dyn std::any::Any) -> u32 + Send + Sync>;
 
@@ -98,6 +105,11 @@ pub struct Server {
     pub globals: Arc<GlobalContext>,
     http_versions: HttpVersions,
     ws_handler: Option<WebSocket>,
+    worker_threads: Option<usize>,
+    reuse_port: bool,
+    max_connections: Option<usize>,
+    tcp_nodelay: bool,
+    io_buffer_size: usize,
 }
 
 impl Server {
@@ -111,9 +123,83 @@ impl Server {
             ))),
             http_versions: HttpVersions::v1(),
             ws_handler: None,
+            worker_threads: None,
+            reuse_port: false,
+            max_connections: None,
+            tcp_nodelay: false,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
         }
     }
 
+    /// Sets the runtime worker thread count used by `build_runtime` / `run`.
+    /// Defaults to the number of CPU cores.
+    pub fn worker_threads(mut self, n: usize) -> Self {
+        self.worker_threads = Some(n.max(1));
+        self
+    }
+
+    /// Binds one HTTP listener per worker with `SO_REUSEPORT` so the kernel
+    /// spreads accepts across cores (unix only, ignored elsewhere).
+    pub fn reuse_port(mut self, enable: bool) -> Self {
+        self.reuse_port = enable;
+        self
+    }
+
+    /// Limits concurrent HTTP connections. Connections beyond the limit
+    /// receive `503 Service Unavailable` and are closed immediately.
+    pub fn max_connections(mut self, n: usize) -> Self {
+        self.max_connections = Some(n);
+        self
+    }
+
+    /// Sets `TCP_NODELAY` on accepted sockets, disabling Nagle's algorithm
+    /// for lower latency on small writes. Off by default.
+    pub fn tcp_nodelay(mut self, enable: bool) -> Self {
+        self.tcp_nodelay = enable;
+        self
+    }
+
+    /// Sets the read/write buffer capacity of each HTTP connection.
+    /// Defaults to `DEFAULT_IO_BUFFER_SIZE` (8 KiB).
+    pub fn io_buffer_size(mut self, size: usize) -> Self {
+        self.io_buffer_size = size.max(1);
+        self
+    }
+
+    /// Applies the configured socket options to an accepted connection.
+    pub fn configure_socket(&self, socket: &tokio::net::TcpStream) -> std::io::Result<()> {
+        if self.tcp_nodelay {
+            socket.set_nodelay(true)?;
+        }
+        Ok(())
+    }
+
+    /// Effective worker count: the configured value or the number of CPU cores.
+    pub fn workers(&self) -> usize {
+        self.worker_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+    }
+
+    /// Builds a multi-thread runtime with the configured worker count.
+    pub fn build_runtime(&self) -> std::io::Result<tokio::runtime::Runtime> {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(self.workers())
+            .enable_all()
+            .build()
+    }
+
+    /// Starts the server on its own runtime and blocks the current thread.
+    pub fn run(self) -> anyhow::Result<()> {
+        let rt = self.build_runtime()?;
+        rt.block_on(async move {
+            self.start().await?;
+            std::future::pending::<anyhow::Result<()>>().await
+        })
+    }
+
     /// Returns whether WebSocket is enabled.
     pub fn has_ws(&self) -> bool {
         self.ws_handler.is_some()
@@ -213,7 +299,7 @@ impl Server {
             .is_some();
 
         if !has_tcp && !has_udp && has_http {
-            self.start_http().await;
+            self.start_http().await?;
             return Ok(());
         }
 
@@ -224,56 +310,101 @@ impl Server {
         Ok(())
     }
 
-    async fn start_http(&self) {
+    async fn start_http(&self) -> std::io::Result<()> {
         let router = self.globals.routers.get_value::<Arc<HttpRouter>>().unwrap();
-        let globals = self.globals.clone();
+        let count = if self.reuse_port { self.workers() } else { 1 };
+        // 所有监听器共享同一个连接上限
+        let limit = self.max_connections.map(|n| Arc::new(Semaphore::new(n)));
+        let server = Arc::new(self.clone());
 
-        tokio::spawn(async move {
-            let listener = match TcpListener::bind(globals.addr).await {
-                Ok(l) => l,
-                Err(e) => {
-                    tracing::error!("HTTP bind failed: {}", e);
-                    return;
-                }
-            };
-            tracing::info!("HTTP listener started on {}", globals.addr);
-
-            loop {
-                match listener.accept().await {
-                    Ok((socket, peer_addr)) => {
-                        let router = router.clone();
-                        let globals = globals.clone();
-                        tokio::spawn(async move {
-                            use tokio::io::{BufReader, BufWriter};
-
-                            let (reader, writer) = socket.into_split();
-                            let reader = Box::new(BufReader::new(reader))
-                                as Box<dyn tokio::io::AsyncBufRead + Send + Sync + Unpin>;
-                            let writer = Box::new(BufWriter::new(writer))
-                                as Box<dyn tokio::io::AsyncWrite + Send + Sync + Unpin>;
-
-                            let mut ctx = crate::connection::context::Context::new(
-                                Some(reader),
-                                Some(writer),
-                                globals,
-                                peer_addr,
-                            );
-
-                            if ctx.req().parse_to_local().await.is_ok() {
-                                if router.on_request(&mut ctx).await {
-                                    let _ = ctx.res().send_response().await;
-                                } else {
-                                    let _ = ctx.res().send_failure().await;
-                                }
-                            }
-                        });
-                    }
-                    Err(e) => {
-                        tracing::warn!("Accept error: {}", e);
+        // 先绑定全部监听器，任何一个失败都直接返回，不留下部分已启动的监听器
+        let listeners = (0..count)
+            .map(|_| self.bind_http())
+            .collect::<std::io::Result<Vec<_>>>()?;
+        for listener in listeners {
+            tracing::info!("HTTP listener started on {}", self.globals.addr);
+            tokio::spawn(Self::accept_http(
+                listener,
+                router.clone(),
+                server.clone(),
+                limit.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn bind_http(&self) -> std::io::Result<TcpListener> {
+        let addr = self.globals.addr;
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        socket.set_reuseaddr(true)?;
+        #[cfg(unix)]
+        if self.reuse_port {
+            socket.set_reuseport(true)?;
+        }
+        socket.bind(addr)?;
+        socket.listen(1024)
+    }
+
+    async fn accept_http(
+        listener: TcpListener,
+        router: Arc<HttpRouter>,
+        server: Arc<Self>,
+        limit: Option<Arc<Semaphore>>,
+    ) {
+        loop {
+            match listener.accept().await {
+                Ok((mut socket, peer_addr)) => {
+                    let permit = match limit.as_ref().map(|l| l.clone().try_acquire_owned()) {
+                        Some(Err(_)) => {
+                            tokio::spawn(async move {
+                                use tokio::io::AsyncWriteExt;
+                                let _ = socket.write_all(SERVICE_UNAVAILABLE).await;
+                                let _ = socket.shutdown().await;
+                            });
+                            continue;
+                        }
+                        Some(Ok(permit)) => Some(permit),
+                        None => None,
+                    };
+                    if let Err(e) = server.configure_socket(&socket) {
+                        tracing::debug!("Failed to configure socket: {}", e);
                     }
+                    let router = router.clone();
+                    let globals = server.globals.clone();
+                    let buffer_size = server.io_buffer_size;
+                    tokio::spawn(async move {
+                        use tokio::io::{BufReader, BufWriter};
+                        // 连接处理结束时释放名额
+                        let _permit = permit;
+
+                        let (reader, writer) = socket.into_split();
+                        let reader = Box::new(BufReader::with_capacity(buffer_size, reader))
+                            as Box<dyn tokio::io::AsyncBufRead + Send + Sync + Unpin>;
+                        let writer = Box::new(BufWriter::with_capacity(buffer_size, writer))
+                            as Box<dyn tokio::io::AsyncWrite + Send + Sync + Unpin>;
+
+                        let ctx = crate::connection::context::Context::new(
+                            Some(reader),
+                            Some(writer),
+                            globals,
+                            peer_addr,
+                        );
+
+                        // 与 start_tcp 一致：按 keep-alive 在同一连接上处理后续请求
+                        if let Err(e) = router.handle(Arc::new(Mutex::new(ctx))).await {
+                            tracing::debug!("HTTP connection error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Accept error: {}", e);
                 }
             }
-        });
+        }
     }
 
     async fn start_multi_protocol<F, C>(&self) -> anyhow::Result<()>
@@ -339,6 +470,17 @@ impl Server {
         self.start_multi_protocol::<F, C>().await
     }
 
+    /// 开启 TCP keepalive，由内核探测并回收半开连接
+    pub fn set_keepalive(socket: &tokio::net::TcpStream) -> std::io::Result<()> {
+        use crate::constants::tcp::{KEEPALIVE_IDLE_SEC, KEEPALIVE_INTERVAL_SEC};
+        use std::time::Duration;
+
+        let keepalive = socket2::TcpKeepalive::new()
+            .with_time(Duration::from_secs(KEEPALIVE_IDLE_SEC))
+            .with_interval(Duration::from_secs(KEEPALIVE_INTERVAL_SEC));
+        socket2::SockRef::from(socket).set_tcp_keepalive(&keepalive)
+    }
+
     /// TCP 核心分发循环
     pub async fn start_tcp<F, C>(&self, loop_token: CancellationToken) -> anyhow::Result<()>
     where
@@ -359,6 +501,12 @@ impl Server {
                         Ok(res) => res,
                         Err(e) => { tracing::warn!("Accept error: {}", e); continue; }
                     };
+                    if let Err(e) = Self::set_keepalive(&socket) {
+                        tracing::debug!("Failed to enable TCP keepalive: {}", e);
+                    }
+                    if let Err(e) = self.configure_socket(&socket) {
+                        tracing::debug!("Failed to configure socket: {}", e);
+                    }
 
                     let is_h2 = {
 
@@ -0,0 +1,98 @@
+use std::marker::PhantomData;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{constants::tcp::MAX_FRAME_SIZE, tcp::types::Codec};
+
+/// 长度前缀的负载超出上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTooLarge {
+    pub length: usize,
+    pub max: usize,
+}
+
+impl std::fmt::Display for FrameTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "frame length {} exceeds {}", self.length, self.max)
+    }
+}
+
+impl std::error::Error for FrameTooLarge {}
+
+/// 4 字节大端长度前缀分帧，负载交给 `T::decode` / `T::encode`。
+/// 配合 `FramedRead` / `Framed` 使用，半包时等待更多数据，一次只产出一帧
+pub struct LengthDelimitedCodec<T> {
+    max_frame_length: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> LengthDelimitedCodec<T> {
+    const HEADER_LEN: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            max_frame_length: MAX_FRAME_SIZE,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 设置单帧负载上限（不含长度前缀），默认 `MAX_FRAME_SIZE`
+    pub fn max_frame_length(mut self, max: usize) -> Self {
+        self.max_frame_length = max;
+        self
+    }
+}
+
+impl<T> Default for LengthDelimitedCodec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Codec> Decoder for LengthDelimitedCodec<T> {
+    type Item = T;
+    type Error = anyhow::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, Self::Error> {
+        if src.len() < Self::HEADER_LEN {
+            return Ok(None);
+        }
+        let length = u32::from_be_bytes([src[0], src[1], src[2], src[3]]) as usize;
+        // 先检查声明长度，避免为恶意长度预留内存
+        if length > self.max_frame_length {
+            return Err(FrameTooLarge {
+                length,
+                max: self.max_frame_length,
+            }
+            .into());
+        }
+        if src.len() < Self::HEADER_LEN + length {
+            src.reserve(Self::HEADER_LEN + length - src.len());
+            return Ok(None);
+        }
+
+        src.advance(Self::HEADER_LEN);
+        let payload = src.split_to(length);
+        <T as Codec>::decode(&payload).map(Some)
+    }
+}
+
+impl<T: Codec> Encoder<T> for LengthDelimitedCodec<T> {
+    type Error = anyhow::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let payload = Codec::encode(&item);
+        if payload.len() > self.max_frame_length {
+            return Err(FrameTooLarge {
+                length: payload.len(),
+                max: self.max_frame_length,
+            }
+            .into());
+        }
+        dst.reserve(Self::HEADER_LEN + payload.len());
+        dst.put_u32(payload.len() as u32);
+        dst.extend_from_slice(&payload);
+        Ok(())
+    }
+}
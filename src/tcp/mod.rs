@@ -4,11 +4,13 @@
 //!
 //! ## Components
 //!
+//! - `codec`: Length-delimited framing for `Codec` payloads
 //! - `router`: Command-based TCP router with frame validation
 //! - `types`: Frame and Command traits, RawCodec implementation
 //! - `listeners`: TCP connection listeners
 //! - `macros`: TCP routing macros
 
+pub mod codec;
 pub mod listeners;
 pub mod macros;
 pub mod router;
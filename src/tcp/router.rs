@@ -1,12 +1,13 @@
 use futures::future::BoxFuture;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncReadExt;
 use tokio::sync::Mutex;
 
 use crate::connection::context::Context;
 use crate::constants::tcp::MAX_FRAME_SIZE;
-use crate::tcp::types::{Codec, TCPCommand, TCPFrame};
+use crate::tcp::types::{Codec, TCPCommand, TCPFrame, is_incomplete};
 
 pub type Doer<F, C> = Box<
     dyn Fn(Arc<Mutex<Context>>, F, C) -> BoxFuture<'static, anyhow::Result<bool>>
@@ -15,9 +16,21 @@ pub type Doer<F, C> = Box<
         + 'static,
 >;
 
+/// 帧或命令解码失败时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeErrorPolicy {
+    /// 关闭当前连接（默认）
+    #[default]
+    Close,
+    /// 丢弃已缓冲的数据并继续读取
+    Skip,
+}
+
 pub struct Router<F = (), C = ()> {
     pub handlers: HashMap<u32, Vec<Doer<F, C>>>,
     extractor: Option<Arc<dyn Fn(&C) -> u32 + Send + Sync>>,
+    decode_error_policy: DecodeErrorPolicy,
+    idle_timeout: Option<Duration>,
     _phantom: std::marker::PhantomData<(F, C)>,
 }
 
@@ -26,10 +39,23 @@ impl<F, C> Router<F, C> {
         Self {
             handlers: HashMap::new(),
             extractor: None,
+            decode_error_policy: DecodeErrorPolicy::default(),
+            idle_timeout: None,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    pub fn on_decode_error(mut self, policy: DecodeErrorPolicy) -> Self {
+        self.decode_error_policy = policy;
+        self
+    }
+
+    /// 连接在 `timeout` 内没有收到任何数据即关闭，默认不限制
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
     pub fn extractor<E: Fn(&C) -> u32 + Send + Sync + 'static>(mut self, extractor: E) -> Self {
         self.extractor = Some(Arc::new(extractor));
         self
@@ -54,15 +80,12 @@ impl<F, C> Router<F, C> {
     }
 
     /// Handle a decoded frame using stored handlers.
+    /// Without an extractor the key is `Command::id()`.
     pub async fn handle_frame(&self, ctx: Arc<Mutex<Context>>, frame: F) -> anyhow::Result<bool>
     where
         F: TCPFrame,
         C: TCPCommand,
     {
-        let extractor = self
-            .extractor
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("TCP extractor not set"))?;
         if !frame.validate() {
             return Ok(false);
         }
@@ -70,11 +93,18 @@ impl<F, C> Router<F, C> {
             let key: u32;
             let c: Option<C>;
             if !frame.is_flat() {
-                if let Ok(cmd) = <C as Codec>::decode(&data) {
-                    key = (extractor)(&cmd);
-                    c = Some(cmd);
-                } else {
-                    return Ok(false);
+                match <C as Codec>::decode(data) {
+                    Ok(cmd) => {
+                        key = match &self.extractor {
+                            Some(extractor) => extractor(&cmd),
+                            None => cmd.id(),
+                        };
+                        c = Some(cmd);
+                    }
+                    Err(e) => {
+                        tracing::warn!("TCP command decode error: {:#}", e);
+                        return Ok(self.decode_error_policy == DecodeErrorPolicy::Skip);
+                    }
                 }
 
                 if let Some(any_handler) = self.handlers.get(&key) {
@@ -95,11 +125,6 @@ impl<F, C> Router<F, C> {
         F: TCPFrame,
         C: TCPCommand,
     {
-        let _ = self
-            .extractor
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("TCP extractor not set"))?;
-
         let mut session_buf: Vec<u8> = Vec::with_capacity(MAX_FRAME_SIZE + 4096);
         let mut buf = vec![0u8; MAX_FRAME_SIZE];
 
@@ -116,7 +141,17 @@ impl<F, C> Router<F, C> {
 
         loop {
             // 在不锁定 Context 的情况下进行异步读取
-            let n = match r.read(&mut buf).await {
+            let read = match self.idle_timeout {
+                Some(idle) => match tokio::time::timeout(idle, r.read(&mut buf)).await {
+                    std::result::Result::Ok(res) => res,
+                    std::result::Result::Err(_) => {
+                        tracing::debug!("TCP connection idle for {:?}, closing", idle);
+                        break;
+                    }
+                },
+                None => r.read(&mut buf).await,
+            };
+            let n = match read {
                 std::result::Result::Ok(n) => n,
                 std::result::Result::Err(e) => {
                     let mut guard = ctx.lock().await;
@@ -144,10 +179,22 @@ impl<F, C> Router<F, C> {
                             return std::result::Result::Ok(());
                         }
                     }
-                    std::result::Result::Err(_) => {
-                        // 等待更多数据
+                    // 半包：等待更多数据（缓冲不超过单帧上限）
+                    std::result::Result::Err(e)
+                        if is_incomplete(&e) && session_buf.len() <= MAX_FRAME_SIZE =>
+                    {
                         break;
                     }
+                    // 非法帧：记录后按策略关闭连接或丢弃缓冲
+                    std::result::Result::Err(e) => {
+                        tracing::warn!("TCP frame decode error: {:#}", e);
+                        if self.decode_error_policy == DecodeErrorPolicy::Close {
+                            let mut guard = ctx.lock().await;
+                            guard.reader = Some(r);
+                            return std::result::Result::Ok(());
+                        }
+                        session_buf.clear();
+                    }
                 }
             }
         }
@@ -25,7 +25,7 @@ pub trait Codec: Serialize + for<'de> Deserialize<'de> + Encode + Decode<()> + S
     fn decode_with_len(data: &[u8]) -> Result<(Self, usize)> {
         // bincode 2.0 返回 (Object, read_length)
         let (decoded, len): (Self, usize) = decode_from_slice(data, frame_config())
-            .map_err(|e| anyhow::anyhow!("decode failed: {}", e))?;
+            .map_err(|e| anyhow::Error::new(e).context("decode failed"))?;
         Ok((decoded, len))
     }
 
@@ -36,6 +36,14 @@ pub trait Codec: Serialize + for<'de> Deserialize<'de> + Encode + Decode<()> + S
     }
 }
 
+/// 解码失败是否只是数据不完整（半包），此时应等待更多数据而不是丢弃
+pub fn is_incomplete(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<bincode::error::DecodeError>(),
+        Some(bincode::error::DecodeError::UnexpectedEnd { .. })
+    )
+}
+
 pub trait Frame: Codec {
     // 生成校验数据
     fn payload(&self) -> Option<Vec<u8>>;
@@ -42,7 +42,7 @@ mod tests {
         assert_eq!(default_ct.top_level.as_str(), "text");
         assert_eq!(default_ct.sub_type.as_str(), "plain");
 
-        let new_ct = ContentType::new();
+        let new_ct = ContentType::new(MediaType::Text, SubMediaType::Plain);
         assert_eq!(new_ct, default_ct);
 
         let octet = ContentType::octet_stream();
@@ -109,6 +109,33 @@ mod tests {
         assert_eq!(ct.to_string(), "application/octet-stream; boundary=abc");
     }
 
+    #[test]
+    fn test_builder_header_value() {
+        let ct = ContentType::new(MediaType::Application, SubMediaType::Json).with_charset("utf-8");
+        assert_eq!(ct.to_header_value(), "application/json; charset=utf-8");
+        assert_eq!(format!("{}", ct), ct.to_header_value());
+
+        // 同名参数被替换而不是重复追加
+        let ct = ct.with_param("Charset", "gbk");
+        assert_eq!(ct.to_header_value(), "application/json; charset=gbk");
+
+        let ct = ContentType::new(MediaType::Multipart, SubMediaType::FormData)
+            .with_param("boundary", "----aex42")
+            .with_charset("utf-8");
+        assert_eq!(
+            ct.to_header_value(),
+            "multipart/form-data; boundary=----aex42; charset=utf-8"
+        );
+
+        // 含分隔符的值加引号，并能被 parse 还原
+        let ct = ContentType::new(MediaType::Text, SubMediaType::Plain).with_param("name", "a b;c");
+        assert_eq!(ct.to_header_value(), "text/plain; name=\"a b;c\"");
+        assert_eq!(
+            ContentType::parse("text/plain; name=\"a b\"").parameters[0].1,
+            "a b"
+        );
+    }
+
     // --- 4. 测试语义化判断 ---
     #[test]
     fn test_semantic_checks() {
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use aex::{
+        handler,
+        http::{
+            extract::{Json, Path, Query, State},
+            protocol::status::StatusCode,
+            router::{NodeType, Router},
+        },
+        server::HTTPServer,
+    };
+    use serde::{Deserialize, Serialize};
+    use tokio::time::sleep;
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u8,
+    }
+
+    #[derive(Deserialize)]
+    struct Page {
+        page: u32,
+        tag: String,
+    }
+
+    #[derive(Clone)]
+    struct Prefix(String);
+
+    async fn show(Path(id): Path<u32>) -> String {
+        format!("user {}", id + 1)
+    }
+
+    async fn update(Path(id): Path<u32>, Json(user): Json<User>) -> (StatusCode, Json<User>) {
+        let user = User {
+            name: format!("{}#{}", user.name, id),
+            age: user.age + 1,
+        };
+        (StatusCode::Created, Json(user))
+    }
+
+    async fn list(Query(q): Query<Page>, State(prefix): State<Prefix>) -> String {
+        format!("{}{}:{}", prefix.0, q.page, q.tag)
+    }
+
+    async fn start() -> String {
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/users/:id", handler!(show)).register();
+        hr.put("/users/:id", handler!(update)).register();
+        hr.get("/users", handler!(list)).register();
+
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        server.globals.set(Prefix("page ".into())).await;
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_path_extractor() {
+        let base = start().await;
+        let client = reqwest::Client::new();
+
+        let res = client
+            .get(format!("{}/users/41", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "user 42");
+
+        let res = client
+            .get(format!("{}/users/abc", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_path_and_json_extractors() {
+        let base = start().await;
+        let client = reqwest::Client::new();
+
+        let res = client
+            .put(format!("{}/users/7", base))
+            .json(&User {
+                name: "alice".into(),
+                age: 30,
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 201);
+        assert!(
+            res.headers()["content-type"]
+                .to_str()
+                .unwrap()
+                .starts_with("application/json")
+        );
+        let user: User = res.json().await.unwrap();
+        assert_eq!(
+            user,
+            User {
+                name: "alice#7".into(),
+                age: 31
+            }
+        );
+
+        // 请求体缺少字段时返回 400
+        let res = client
+            .put(format!("{}/users/7", base))
+            .body(r#"{"name":"bob"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_query_and_state_extractors() {
+        let base = start().await;
+        let client = reqwest::Client::new();
+
+        let res = client
+            .get(format!("{}/users?page=3&tag=42", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "page 3:42");
+    }
+}
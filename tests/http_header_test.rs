@@ -303,4 +303,45 @@ mod tests {
         let key = HeaderKey::from_str("authorization").unwrap();
         assert!(raw_map.contains_key(&key));
     }
+
+    // ---------- CORS / 缓存相关 header 往返 ----------
+    #[test]
+    fn test_cors_and_cache_round_trip() {
+        let cases = [
+            (
+                HeaderKey::AccessControlAllowOrigin,
+                "Access-Control-Allow-Origin",
+            ),
+            (
+                HeaderKey::AccessControlAllowMethods,
+                "Access-Control-Allow-Methods",
+            ),
+            (
+                HeaderKey::AccessControlAllowHeaders,
+                "Access-Control-Allow-Headers",
+            ),
+            (
+                HeaderKey::AccessControlAllowCredentials,
+                "Access-Control-Allow-Credentials",
+            ),
+            (HeaderKey::AccessControlMaxAge, "Access-Control-Max-Age"),
+            (HeaderKey::CacheControl, "Cache-Control"),
+            (HeaderKey::ETag, "ETag"),
+            (HeaderKey::IfNoneMatch, "If-None-Match"),
+            (HeaderKey::Vary, "Vary"),
+            (HeaderKey::Location, "Location"),
+            (HeaderKey::WWWAuthenticate, "WWW-Authenticate"),
+            (HeaderKey::RetryAfter, "Retry-After"),
+        ];
+
+        for (key, canonical) in cases {
+            assert_eq!(key.as_str(), canonical);
+            // 任意大小写都解析为具名变体，而不是 Custom，并恢复规范写法
+            for raw in [canonical.to_lowercase(), canonical.to_uppercase()] {
+                let parsed = HeaderKey::from_str(&raw).unwrap();
+                assert!(!matches!(parsed, HeaderKey::Custom(_)), "{}", raw);
+                assert_eq!(parsed.as_str(), canonical);
+            }
+        }
+    }
 }
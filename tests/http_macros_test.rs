@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use aex::{
+        all, connect_route, delete, exe, exe_try, get, head,
+        http::router::{NodeType, Router},
+        methods, options, patch, post, put, route, trace_route,
+    };
+
+    #[test]
+    fn test_method_macros_register_specific_methods() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+
+        get!(hr, "/r", exe!(|_ctx| { true }));
+        post!(hr, "/r", exe!(|_ctx| { true }));
+        put!(hr, "/r", exe!(|_ctx| { true }));
+        delete!(hr, "/r", exe!(|_ctx| { true }));
+        patch!(hr, "/r", exe!(|_ctx| { true }));
+        options!(hr, "/r", exe!(|_ctx| { true }));
+        head!(hr, "/r", exe!(|_ctx| { true }));
+
+        for m in ["GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD"] {
+            assert!(hr.has_route(m, "/r"), "{} should be registered", m);
+        }
+        // 没有 `*` 兜底，未注册的方法不会命中
+        assert!(!hr.has_route("TRACE", "/r"));
+    }
+
+    #[test]
+    fn test_patch_macro_keeps_method_specificity() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        patch!(hr, "/users/:id", exe!(|_ctx| { true }));
+
+        assert!(hr.has_route("PATCH", "/users/1"));
+        assert!(!hr.has_route("GET", "/users/1"));
+    }
+
+    #[test]
+    fn test_method_macro_with_middlewares() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        put!(
+            hr,
+            "/items",
+            exe!(|_ctx| { true }),
+            [exe!(|_ctx| { true }), exe!(|_ctx| { true })]
+        );
+
+        let node = hr.statics.get("items").unwrap();
+        let mws = node.middlewares.as_ref().unwrap();
+        assert_eq!(mws.get("PUT").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_all_and_route_macros() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        all!(hr, "/any", exe!(|_ctx| { true }));
+        route!(hr, "TRACE", "/trace", exe!(|_ctx| { true }));
+
+        assert!(hr.has_route("DELETE", "/any"));
+        assert!(hr.has_route("TRACE", "/trace"));
+        assert!(!hr.has_route("GET", "/trace"));
+    }
+
+    #[test]
+    fn test_route_macro_with_method_tuples() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        route!(hr, get!("/r", exe!(|_ctx| { true })));
+        route!(hr, post!("/r", exe!(|_ctx| { true }), []));
+        route!(hr, put!("/r", exe!(|_ctx| { true })));
+        route!(hr, delete!("/r", exe!(|_ctx| { true })));
+        route!(hr, patch!("/r", exe!(|_ctx| { true })));
+        route!(hr, options!("/r", exe!(|_ctx| { true })));
+        route!(hr, head!("/r", exe!(|_ctx| { true })));
+        route!(hr, trace_route!("/r", exe!(|_ctx| { true })));
+        route!(hr, connect_route!("/r", exe!(|_ctx| { true })));
+
+        for m in [
+            "GET", "POST", "PUT", "DELETE", "PATCH", "OPTIONS", "HEAD", "TRACE", "CONNECT",
+        ] {
+            assert!(hr.has_route(m, "/r"), "{} should be registered", m);
+        }
+
+        // 元组可以先生成再注册，中间件按顺序保留
+        let params = get!(
+            "/ws",
+            exe!(|_ctx| { true }),
+            [exe!(|_ctx| { true }), exe!(|_ctx| { true })]
+        );
+        route!(hr, params);
+        assert!(hr.has_route("GET", "/ws"));
+        assert!(!hr.has_route("POST", "/ws"));
+        let node = hr.statics.get("ws").unwrap();
+        assert_eq!(
+            node.middlewares.as_ref().unwrap().get("GET").unwrap().len(),
+            2
+        );
+
+        // 直接注册的形式同样支持 trace_route! / connect_route!
+        trace_route!(hr, "/t", exe!(|_ctx| { true }));
+        connect_route!(hr, "/t", exe!(|_ctx| { true }), [exe!(|_ctx| { true })]);
+        assert!(hr.has_route("TRACE", "/t"));
+        assert!(hr.has_route("CONNECT", "/t"));
+    }
+
+    #[test]
+    fn test_methods_macro_shares_handler() {
+        use std::sync::Arc;
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let handler = exe!(|_ctx| { true });
+        methods!(
+            hr,
+            ["GET", "put"],
+            "/items/:id",
+            handler.clone(),
+            [exe!(|_ctx| { true })]
+        );
+
+        assert!(hr.has_route("GET", "/items/1"));
+        assert!(hr.has_route("PUT", "/items/1"));
+        assert!(!hr.has_route("POST", "/items/1"));
+
+        let (_, node) = hr.statics.get("items").unwrap().param.as_ref().unwrap();
+        let handlers = node.handlers.as_ref().unwrap();
+        assert!(Arc::ptr_eq(handlers.get("GET").unwrap(), &handler));
+        assert!(Arc::ptr_eq(handlers.get("PUT").unwrap(), &handler));
+        let mws = node.middlewares.as_ref().unwrap();
+        assert_eq!(mws.get("GET").unwrap().len(), 1);
+        assert_eq!(mws.get("PUT").unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_exe_try_converts_err_to_500() {
+        use aex::server::HTTPServer;
+        use std::time::Duration;
+
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        get!(
+            hr,
+            "/num/:n",
+            exe_try!(|ctx| {
+                let n: u32 = ctx.req().param("n").unwrap_or_default().parse()?;
+                ctx.send(format!("n={}", n), None);
+                Ok(true)
+            })
+        );
+
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::get(format!("http://{}/num/7", addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "n=7");
+
+        // 解析失败通过 `?` 返回 Err，客户端收到 500 而不是连接中断
+        let res = reqwest::get(format!("http://{}/num/abc", addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 500);
+        assert_eq!(res.text().await.unwrap(), "Internal Server Error");
+    }
+}
@@ -67,4 +67,79 @@ mod tests {
         assert_eq!(meta.status, StatusCode::NotFound);
         assert_eq!(meta.body.len(), 14);
     }
+
+    #[test]
+    fn test_redirect() {
+        let mut meta = HttpMetadata::new();
+        meta.redirect(StatusCode::Found, "/login").unwrap();
+        assert_eq!(meta.status, StatusCode::Found);
+        assert_eq!(meta.headers.get(&HeaderKey::Location).unwrap(), "/login");
+
+        // 非 3xx 被拒绝，元数据保持不变
+        let mut meta = HttpMetadata::new();
+        assert!(meta.redirect(StatusCode::Ok, "/x").is_err());
+        assert_eq!(meta.status, StatusCode::Ok);
+        assert!(meta.headers.get(&HeaderKey::Location).is_none());
+
+        meta.redirect_permanent("/new");
+        assert_eq!(meta.status, StatusCode::MovedPermanently);
+        assert_eq!(meta.headers.get(&HeaderKey::Location).unwrap(), "/new");
+        meta.redirect_temporary("/tmp");
+        assert_eq!(meta.status, StatusCode::Found);
+        assert_eq!(meta.headers.get(&HeaderKey::Location).unwrap(), "/tmp");
+    }
+
+    #[test]
+    fn test_error_helpers() {
+        let mut meta = HttpMetadata::new();
+        meta.error(StatusCode::NotFound, "missing");
+        assert_eq!(meta.status, StatusCode::NotFound);
+        assert_eq!(meta.body, b"missing");
+        assert_eq!(
+            meta.headers.get(&HeaderKey::ContentType).unwrap(),
+            "text/plain; charset=utf-8"
+        );
+
+        let mut meta = HttpMetadata::new();
+        meta.error_json(StatusCode::BadRequest, "bad \"id\"");
+        assert_eq!(meta.status, StatusCode::BadRequest);
+        assert_eq!(meta.body, br#"{"error":"bad \"id\""}"#);
+        assert_eq!(
+            meta.headers.get(&HeaderKey::ContentType).unwrap(),
+            "application/json; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn test_etag_if_none_match() {
+        let etag = HttpMetadata::etag_for(b"hello");
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert_ne!(etag, HttpMetadata::etag_for(b"world"));
+
+        // 命中：304 且无响应体
+        let mut meta = HttpMetadata::new();
+        meta.body = b"hello".to_vec();
+        meta.headers
+            .insert(HeaderKey::IfNoneMatch, format!("\"other\", W/{}", etag));
+        assert!(meta.maybe_not_modified());
+        assert_eq!(meta.status, StatusCode::NotModified);
+        assert!(meta.body.is_empty());
+        assert_eq!(meta.headers.get(&HeaderKey::ETag).unwrap(), &etag);
+
+        // 未命中：保持 200 与原响应体，仍然带上 ETag
+        let mut meta = HttpMetadata::new();
+        meta.body = b"hello".to_vec();
+        meta.headers.insert(HeaderKey::IfNoneMatch, "\"stale\"");
+        assert!(!meta.maybe_not_modified());
+        assert_eq!(meta.status, StatusCode::Ok);
+        assert_eq!(meta.body, b"hello");
+        assert_eq!(meta.headers.get(&HeaderKey::ETag).unwrap(), &etag);
+
+        // 非 GET/HEAD 不返回 304
+        let mut meta = HttpMetadata::new();
+        meta.method = HttpMethod::POST;
+        meta.body = b"hello".to_vec();
+        meta.headers.insert(HeaderKey::IfNoneMatch, "*");
+        assert!(!meta.maybe_not_modified());
+    }
 }
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{
+            meta::HttpMetadata,
+            middlewares::auth::{AuthIdentity, BasicAuth, BearerAuth},
+            protocol::{header::HeaderKey, status::StatusCode},
+        },
+    };
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+
+    fn ctx_with_auth(value: Option<&str>) -> Context {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        let mut meta = HttpMetadata::new();
+        if let Some(v) = value {
+            meta.headers.insert(HeaderKey::Authorization, v);
+        }
+        ctx.local.set_value(meta);
+        ctx
+    }
+
+    fn basic(user: &str, pass: &str) -> String {
+        format!("Basic {}", STANDARD.encode(format!("{}:{}", user, pass)))
+    }
+
+    #[test]
+    fn test_basic_parse() {
+        assert_eq!(
+            BasicAuth::parse(&basic("alice", "p:w")),
+            Some(("alice".to_string(), "p:w".to_string()))
+        );
+        assert_eq!(BasicAuth::parse("Basic !!!"), None);
+        assert_eq!(BasicAuth::parse("Bearer abc"), None);
+    }
+
+    #[test]
+    fn test_bearer_parse() {
+        assert_eq!(BearerAuth::parse("bearer abc"), Some("abc".to_string()));
+        assert_eq!(BearerAuth::parse("Bearer "), None);
+        assert_eq!(BearerAuth::parse("Basic abc"), None);
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_valid() {
+        let mw = BasicAuth::new(|u, p| u == "alice" && p == "secret").build();
+        let mut ctx = ctx_with_auth(Some(&basic("alice", "secret")));
+
+        assert!(mw(&mut ctx).await);
+        assert_eq!(
+            ctx.local.get_value::<AuthIdentity>(),
+            Some(AuthIdentity::Basic("alice".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_invalid() {
+        let mw = BasicAuth::new(|u, p| u == "alice" && p == "secret")
+            .realm("admin")
+            .build();
+        let mut ctx = ctx_with_auth(Some(&basic("alice", "wrong")));
+
+        assert!(!mw(&mut ctx).await);
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        assert_eq!(meta.status, StatusCode::Unauthorized);
+        assert_eq!(
+            meta.headers.get(&HeaderKey::WWWAuthenticate).unwrap(),
+            "Basic realm=\"admin\""
+        );
+        assert!(ctx.local.get_value::<AuthIdentity>().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bearer_auth_valid() {
+        let mw = BearerAuth::new(|t| t == "token-123").build();
+        let mut ctx = ctx_with_auth(Some("Bearer token-123"));
+
+        assert!(mw(&mut ctx).await);
+        assert_eq!(
+            ctx.local.get_value::<AuthIdentity>(),
+            Some(AuthIdentity::Bearer("token-123".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bearer_auth_invalid() {
+        let mw = BearerAuth::new(|t| t == "token-123").build();
+
+        for header in [Some("Bearer nope"), None] {
+            let mut ctx = ctx_with_auth(header);
+            assert!(!mw(&mut ctx).await);
+            let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+            assert_eq!(meta.status, StatusCode::Unauthorized);
+            assert!(
+                meta.headers
+                    .get(&HeaderKey::WWWAuthenticate)
+                    .unwrap()
+                    .starts_with("Bearer")
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{
+            meta::HttpMetadata,
+            middlewares::compression::{Compression, Encoding, negotiate},
+            protocol::{header::HeaderKey, status::StatusCode},
+        },
+    };
+    use flate2::read::GzDecoder;
+
+    const BODY: &str = "hello compression hello compression hello compression";
+
+    fn ctx(accept: Option<&str>) -> Context {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        let mut meta = HttpMetadata::new();
+        if let Some(accept) = accept {
+            meta.headers.insert(HeaderKey::AcceptEncoding, accept);
+        }
+        ctx.local.set_value(meta);
+        ctx
+    }
+
+    /// 依次执行协商中间件、模拟处理器与压缩钩子，返回中间件结果
+    async fn run(compression: &Compression, ctx: &mut Context) -> bool {
+        let ok = compression.negotiate()(ctx).await;
+        if ok {
+            ctx.local.get_mut::<HttpMetadata>().unwrap().body = BODY.as_bytes().to_vec();
+        }
+        compression.compress()(ctx).await;
+        ok
+    }
+
+    #[test]
+    fn test_negotiate_q_values() {
+        assert_eq!(negotiate(None), Some(Encoding::Identity));
+        assert_eq!(negotiate(Some("gzip")), Some(Encoding::Gzip));
+        assert_eq!(
+            negotiate(Some("deflate, gzip;q=0.5")),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(
+            negotiate(Some("gzip;q=0, deflate;q=0")),
+            Some(Encoding::Identity)
+        );
+        assert_eq!(negotiate(Some("br")), Some(Encoding::Identity));
+        assert_eq!(negotiate(Some("identity;q=0, gzip")), Some(Encoding::Gzip));
+        assert_eq!(negotiate(Some("identity;q=0, br")), None);
+        assert_eq!(negotiate(Some("*;q=0")), None);
+        assert_eq!(negotiate(Some("*;q=0, identity")), Some(Encoding::Identity));
+        assert_eq!(negotiate(Some("*;q=0, deflate")), Some(Encoding::Deflate));
+        assert_eq!(negotiate(Some("*")), Some(Encoding::Gzip));
+        assert_eq!(negotiate(Some("identity;Q=0.0, gzip;q=bogus")), None);
+    }
+
+    #[tokio::test]
+    async fn test_identity_disabled_with_gzip_compresses() {
+        let compression = Compression::new().min_size(0);
+        let mut ctx = ctx(Some("identity;q=0, gzip"));
+        assert!(run(&compression, &mut ctx).await);
+
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        assert_eq!(
+            meta.headers
+                .get(&HeaderKey::ContentEncoding)
+                .map(String::as_str),
+            Some("gzip")
+        );
+        assert_eq!(
+            meta.headers.get(&HeaderKey::Vary).map(String::as_str),
+            Some("Accept-Encoding")
+        );
+        let mut decoded = String::new();
+        GzDecoder::new(meta.body.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, BODY);
+    }
+
+    #[tokio::test]
+    async fn test_identity_disabled_without_codec_is_406() {
+        let compression = Compression::new().min_size(0);
+        for accept in ["identity;q=0, br", "*;q=0"] {
+            let mut ctx = ctx(Some(accept));
+            assert!(!run(&compression, &mut ctx).await, "{}", accept);
+
+            let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+            assert_eq!(meta.status, StatusCode::NotAcceptable);
+            assert!(!meta.headers.contains(&HeaderKey::ContentEncoding));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identity_passthrough() {
+        let compression = Compression::new().min_size(0);
+        for accept in [None, Some("identity"), Some("br")] {
+            let mut ctx = ctx(accept);
+            assert!(run(&compression, &mut ctx).await);
+
+            let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+            assert_eq!(meta.status, StatusCode::Ok);
+            assert!(!meta.headers.contains(&HeaderKey::ContentEncoding));
+            assert_eq!(meta.body, BODY.as_bytes());
+        }
+
+        // 低于 min_size 的响应体不压缩
+        let mut ctx = ctx(Some("gzip"));
+        assert!(run(&Compression::new(), &mut ctx).await);
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        assert!(!meta.headers.contains(&HeaderKey::ContentEncoding));
+        assert_eq!(meta.body, BODY.as_bytes());
+    }
+}
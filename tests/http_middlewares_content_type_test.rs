@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{
+            meta::HttpMetadata,
+            middlewares::content_type::require,
+            protocol::{
+                content_type::ContentType,
+                media_type::{MediaType, SubMediaType},
+                status::StatusCode,
+            },
+        },
+    };
+
+    fn ctx_with_content_type(value: &str) -> Context {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        let mut meta = HttpMetadata::new();
+        meta.content_type = ContentType::parse(value);
+        ctx.local.set_value(meta);
+        ctx
+    }
+
+    #[tokio::test]
+    async fn test_require_matching_content_type() {
+        let mw = require(MediaType::Application, SubMediaType::Json);
+
+        for value in ["application/json", "application/json; charset=utf-8"] {
+            let mut ctx = ctx_with_content_type(value);
+            assert!(mw(&mut ctx).await, "{}", value);
+            let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+            assert_eq!(meta.status, StatusCode::Ok);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_require_rejects_mismatch() {
+        let mw = require(MediaType::Application, SubMediaType::Json);
+        let mut ctx = ctx_with_content_type("text/plain");
+
+        assert!(!mw(&mut ctx).await);
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        assert_eq!(meta.status, StatusCode::UnsupportedMediaType);
+        assert_eq!(
+            String::from_utf8_lossy(&meta.body),
+            "Unsupported Media Type: expected application/json, got text/plain"
+        );
+    }
+}
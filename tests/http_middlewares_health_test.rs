@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        time::Duration,
+    };
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{
+            meta::HttpMetadata,
+            middlewares::health::{self, liveness, readiness},
+            protocol::status::StatusCode,
+            router::{NodeType, Router},
+        },
+        server::HTTPServer,
+    };
+    use tokio::time::sleep;
+
+    fn ctx() -> Context {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        ctx.local.set_value(HttpMetadata::new());
+        ctx
+    }
+
+    fn status_and_body(ctx: &Context) -> (StatusCode, String) {
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        (meta.status, String::from_utf8_lossy(&meta.body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_liveness_always_ok() {
+        let mut ctx = ctx();
+        assert!(liveness()(&mut ctx).await);
+        assert_eq!(status_and_body(&ctx), (StatusCode::Ok, "ok".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_readiness_follows_probe() {
+        let ready = Arc::new(AtomicBool::new(false));
+        let flag = ready.clone();
+        let handler = readiness(move || flag.load(Ordering::SeqCst));
+
+        let mut ctx = ctx();
+        assert!(handler(&mut ctx).await);
+        assert_eq!(
+            status_and_body(&ctx),
+            (StatusCode::ServiceUnavailable, "not ready".to_string())
+        );
+
+        ready.store(true, Ordering::SeqCst);
+        let mut ctx = self::ctx();
+        assert!(handler(&mut ctx).await);
+        assert_eq!(status_and_body(&ctx), (StatusCode::Ok, "ready".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_mount_serves_probes() {
+        let ready = Arc::new(AtomicBool::new(false));
+        let flag = ready.clone();
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        health::mount(&mut hr, move || flag.load(Ordering::SeqCst));
+
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let get = |path: &str| reqwest::get(format!("http://{}{}", addr, path));
+        assert_eq!(get("/healthz").await.unwrap().status().as_u16(), 200);
+        assert_eq!(get("/readyz").await.unwrap().status().as_u16(), 503);
+
+        ready.store(true, Ordering::SeqCst);
+        let res = get("/readyz").await.unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "ready");
+    }
+}
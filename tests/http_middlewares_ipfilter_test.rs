@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{meta::HttpMetadata, middlewares::ipfilter::IpFilter, protocol::status::StatusCode},
+    };
+    use ipnet::IpNet;
+
+    fn ctx_from(peer: &str) -> Context {
+        let addr: SocketAddr = peer.parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        ctx.local.set_value(HttpMetadata::new());
+        ctx
+    }
+
+    fn nets(list: &[&str]) -> Vec<IpNet> {
+        list.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_allowed_ip() {
+        let mw = IpFilter::new().allow(nets(&["10.0.0.0/8"])).build();
+        let mut ctx = ctx_from("10.1.2.3:4000");
+
+        assert!(mw(&mut ctx).await);
+        assert_eq!(
+            ctx.local.get_ref::<HttpMetadata>().unwrap().status,
+            StatusCode::Ok
+        );
+
+        // 不在 allow 列表中的地址被拒绝
+        let mut ctx = ctx_from("192.168.1.1:4000");
+        assert!(!mw(&mut ctx).await);
+        assert_eq!(
+            ctx.local.get_ref::<HttpMetadata>().unwrap().status,
+            StatusCode::Forbidden
+        );
+    }
+
+    #[tokio::test]
+    async fn test_denied_ip() {
+        let mw = IpFilter::new()
+            .allow(nets(&["10.0.0.0/8"]))
+            .deny(nets(&["10.0.0.66/32"]))
+            .build();
+
+        let mut ctx = ctx_from("10.0.0.66:4000");
+        assert!(!mw(&mut ctx).await);
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        assert_eq!(meta.status, StatusCode::Forbidden);
+        assert_eq!(meta.body, b"Forbidden");
+
+        // IPv4 映射的 IPv6 地址同样命中
+        let mut ctx = ctx_from("[::ffff:10.0.0.66]:4000");
+        assert!(!mw(&mut ctx).await);
+    }
+
+    #[tokio::test]
+    async fn test_empty_lists_allow_all() {
+        let filter = IpFilter::new();
+        assert!(filter.is_allowed("203.0.113.9".parse().unwrap()));
+        assert!(filter.is_allowed("::1".parse().unwrap()));
+
+        let mw = filter.build();
+        let mut ctx = ctx_from("203.0.113.9:80");
+        assert!(mw(&mut ctx).await);
+    }
+
+    #[tokio::test]
+    async fn test_filter_behind_trusted_proxy() {
+        use aex::http::protocol::header::HeaderKey;
+
+        let mw = IpFilter::new()
+            .deny(nets(&["198.51.100.0/24"]))
+            .trusted_proxies(nets(&["10.0.0.0/8"]))
+            .build();
+        let with_xff = |peer: &str, xff: &str| {
+            let mut ctx = ctx_from(peer);
+            ctx.local
+                .get_mut::<HttpMetadata>()
+                .unwrap()
+                .headers
+                .insert(HeaderKey::XForwardedFor, xff);
+            ctx
+        };
+
+        // 经可信代理转发，按客户端地址过滤
+        let mut ctx = with_xff("10.0.0.1:80", "198.51.100.4");
+        assert!(!mw(&mut ctx).await);
+
+        // 被拒绝的客户端直连并伪造 XFF，仍按对端地址过滤
+        let mut ctx = with_xff("198.51.100.4:80", "203.0.113.1");
+        assert!(!mw(&mut ctx).await);
+
+        let mut ctx = with_xff("10.0.0.1:80", "203.0.113.1");
+        assert!(mw(&mut ctx).await);
+    }
+}
@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use aex::{
+        http::{
+            middlewares::jsonrpc::{JsonRpc, RpcError},
+            router::{NodeType, Router},
+        },
+        server::HTTPServer,
+    };
+    use serde_json::{Value, json};
+    use tokio::time::sleep;
+
+    fn rpc() -> JsonRpc {
+        JsonRpc::new()
+            .register("sum", |params| async move {
+                let nums: Vec<i64> = serde_json::from_value(params.unwrap_or_default())
+                    .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+                Ok(json!(nums.iter().sum::<i64>()))
+            })
+            .register("fail", |_| async {
+                Err(RpcError::new(-32000, "boom").with_data(json!({ "retry": false })))
+            })
+    }
+
+    async fn call(rpc: &JsonRpc, body: &str) -> Option<Value> {
+        rpc.handle(body.as_bytes()).await
+    }
+
+    #[tokio::test]
+    async fn test_single_call() {
+        let rpc = rpc();
+        let res = call(
+            &rpc,
+            r#"{"jsonrpc":"2.0","method":"sum","params":[1,2,3],"id":1}"#,
+        )
+        .await;
+        assert_eq!(res, Some(json!({ "jsonrpc": "2.0", "result": 6, "id": 1 })));
+
+        // 通知不产生响应
+        let res = call(&rpc, r#"{"jsonrpc":"2.0","method":"sum","params":[1]}"#).await;
+        assert_eq!(res, None);
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let rpc = rpc();
+        let res = call(
+            &rpc,
+            r#"[
+                {"jsonrpc":"2.0","method":"sum","params":[1,2],"id":"a"},
+                {"jsonrpc":"2.0","method":"sum","params":[5]},
+                {"jsonrpc":"2.0","method":"missing","id":"b"},
+                1
+            ]"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            res,
+            json!([
+                { "jsonrpc": "2.0", "result": 3, "id": "a" },
+                { "jsonrpc": "2.0", "error": { "code": -32601, "message": "Method not found" }, "id": "b" },
+                { "jsonrpc": "2.0", "error": { "code": -32600, "message": "Invalid Request" }, "id": null },
+            ])
+        );
+
+        // 全部为通知时没有响应；空批量是无效请求
+        let res = call(&rpc, r#"[{"jsonrpc":"2.0","method":"sum","params":[1]}]"#).await;
+        assert_eq!(res, None);
+        let res = call(&rpc, "[]").await.unwrap();
+        assert_eq!(res["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn test_error_responses() {
+        let rpc = rpc();
+        let res = call(&rpc, r#"{"jsonrpc":"2.0","method":"fail","id":7}"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            res,
+            json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32000, "message": "boom", "data": { "retry": false } },
+                "id": 7
+            })
+        );
+
+        let res = call(
+            &rpc,
+            r#"{"jsonrpc":"2.0","method":"sum","params":["x"],"id":2}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(res["error"]["code"], RpcError::INVALID_PARAMS);
+
+        let res = call(&rpc, "{not json").await.unwrap();
+        assert_eq!(res["error"]["code"], RpcError::PARSE_ERROR);
+        assert_eq!(res["id"], Value::Null);
+
+        // 版本错误或 params 非数组/对象
+        for body in [
+            r#"{"jsonrpc":"1.0","method":"sum","id":3}"#,
+            r#"{"jsonrpc":"2.0","method":"sum","params":5,"id":3}"#,
+        ] {
+            let res = call(&rpc, body).await.unwrap();
+            assert_eq!(res["error"]["code"], RpcError::INVALID_REQUEST);
+            assert_eq!(res["id"], 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_over_http() {
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post("/rpc", rpc().build()).register();
+
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let client = reqwest::Client::new();
+        let res = client
+            .post(format!("http://{}/rpc", addr))
+            .json(&json!({ "jsonrpc": "2.0", "method": "sum", "params": [40, 2], "id": 1 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert!(
+            res.headers()["content-type"]
+                .to_str()
+                .unwrap()
+                .starts_with("application/json")
+        );
+        let body: Value = res.json().await.unwrap();
+        assert_eq!(body["result"], 42);
+
+        let res = client
+            .post(format!("http://{}/rpc", addr))
+            .json(&json!({ "jsonrpc": "2.0", "method": "sum", "params": [1] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 204);
+    }
+}
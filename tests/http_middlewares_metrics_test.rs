@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use aex::{
+        exe,
+        http::{
+            middlewares::metrics::Metrics,
+            router::{NodeType, Router},
+        },
+        server::HTTPServer,
+    };
+    use tokio::time::sleep;
+
+    #[test]
+    fn test_render_histogram() {
+        let metrics = Metrics::new().buckets(&[0.1, 0.01]);
+        metrics.observe("GET", "/users/:id", 200, 0.005);
+        metrics.observe("GET", "/users/:id", 200, 0.05);
+        metrics.observe("GET", "/users/:id", 404, 3.0);
+
+        assert_eq!(metrics.request_count("GET", "/users/:id", 200), 2);
+        assert_eq!(metrics.request_count("GET", "/users/:id", 500), 0);
+
+        let out = metrics.render();
+        assert!(out.contains("# TYPE aex_http_requests_total counter"));
+        assert!(out.contains(
+            "aex_http_requests_total{method=\"GET\",route=\"/users/:id\",status=\"404\"} 1"
+        ));
+        // 分桶为累计值，且按上界排序
+        let labels = "method=\"GET\",route=\"/users/:id\"";
+        for (le, n) in [("0.01", 1), ("0.1", 2), ("+Inf", 3)] {
+            let line = format!(
+                "aex_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                labels, le, n
+            );
+            assert!(out.contains(&line), "{}", out);
+        }
+        assert!(out.contains(&format!(
+            "aex_http_request_duration_seconds_count{{{}}} 3",
+            labels
+        )));
+    }
+
+    #[test]
+    fn test_mount_global_handler_with_route_macro() {
+        use aex::{get, http::middlewares::metrics, route};
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        route!(hr, get!("/metrics", metrics::handler()));
+        assert!(hr.has_route("GET", "/metrics"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let metrics = Metrics::new();
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/users/:id", exe!(|_ctx| { true }))
+            .middleware(metrics.track())
+            .after(metrics.record())
+            .register();
+        hr.get("/metrics", metrics.handler()).register();
+
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let client = reqwest::Client::new();
+        for id in [1, 2] {
+            let res = client
+                .get(format!("http://{}/users/{}", actual_addr, id))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status().as_u16(), 200);
+        }
+
+        let res = client
+            .get(format!("http://{}/metrics", actual_addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        let content_type = res.headers()["content-type"].to_str().unwrap().to_string();
+        assert!(content_type.starts_with("text/plain"), "{}", content_type);
+        assert!(content_type.contains("version=0.0.4"), "{}", content_type);
+
+        let body = res.text().await.unwrap();
+        assert!(
+            body.contains(
+                "aex_http_requests_total{method=\"GET\",route=\"/users/:id\",status=\"200\"} 2"
+            ),
+            "{}",
+            body
+        );
+        assert!(body.contains(
+            "aex_http_request_duration_seconds_count{method=\"GET\",route=\"/users/:id\"} 2"
+        ));
+    }
+}
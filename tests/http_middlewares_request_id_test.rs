@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{
+            meta::HttpMetadata,
+            middlewares::request_id::{REQUEST_ID_HEADER, RequestId, request_id},
+            protocol::header::HeaderKey,
+        },
+    };
+
+    fn ctx_with_id(value: Option<&str>) -> Context {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        let mut meta = HttpMetadata::new();
+        if let Some(v) = value {
+            meta.headers.insert(HeaderKey::from("x-request-id"), v);
+        }
+        ctx.local.set_value(meta);
+        ctx
+    }
+
+    fn echoed(ctx: &Context) -> String {
+        let meta = ctx.local.get_ref::<HttpMetadata>().unwrap();
+        meta.headers
+            .get(&HeaderKey::from(REQUEST_ID_HEADER))
+            .unwrap()
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_when_absent() {
+        let mw = request_id();
+        let mut a = ctx_with_id(None);
+        let mut b = ctx_with_id(None);
+        assert!(mw(&mut a).await);
+        assert!(mw(&mut b).await);
+
+        let RequestId(id_a) = a.local.get_value::<RequestId>().unwrap();
+        let RequestId(id_b) = b.local.get_value::<RequestId>().unwrap();
+        assert_eq!(id_a.len(), 32);
+        assert!(id_a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(id_a, id_b);
+        assert_eq!(echoed(&a), id_a);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_preserved_when_present() {
+        let mw = request_id();
+        let mut ctx = ctx_with_id(Some("trace-abc-123"));
+        assert!(mw(&mut ctx).await);
+        assert_eq!(
+            ctx.local.get_value::<RequestId>(),
+            Some(RequestId("trace-abc-123".to_string()))
+        );
+        assert_eq!(echoed(&ctx), "trace-abc-123");
+
+        // 非法值被替换
+        let mut ctx = ctx_with_id(Some("bad id\twith spaces"));
+        assert!(mw(&mut ctx).await);
+        let RequestId(id) = ctx.local.get_value::<RequestId>().unwrap();
+        assert_eq!(id.len(), 32);
+    }
+}
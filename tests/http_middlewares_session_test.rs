@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        http::{
+            meta::HttpMetadata,
+            middlewares::session::{MemoryStore, Session, SessionConfig, SessionStore, hmac_sha1},
+            protocol::header::HeaderKey,
+        },
+    };
+
+    fn ctx_with_cookie(cookie: Option<&str>) -> Context {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut ctx = Context::new(None, None, Arc::new(GlobalContext::new(addr, None)), addr);
+        let mut meta = HttpMetadata::new();
+        if let Some(v) = cookie {
+            meta.cookies.insert("aex_sid".to_string(), v.to_string());
+        }
+        ctx.local.set_value(meta);
+        ctx
+    }
+
+    fn set_cookie(ctx: &Context) -> String {
+        ctx.local
+            .get_ref::<HttpMetadata>()
+            .unwrap()
+            .headers
+            .get(&HeaderKey::SetCookie)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_hmac_sha1_rfc2202() {
+        let hex = |b: [u8; 20]| b.iter().map(|x| format!("{:02x}", x)).collect::<String>();
+        assert_eq!(
+            hex(hmac_sha1(b"Jefe", b"what do ya want for nothing?")),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            hex(hmac_sha1(
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_roundtrip() {
+        let store = MemoryStore::new();
+        let config = SessionConfig::new("secret")
+            .store(store.clone())
+            .max_age(60);
+        let mw = config.clone().build();
+
+        let mut ctx = ctx_with_cookie(None);
+        assert!(mw(&mut ctx).await);
+        let mut session = ctx.local.get_value::<Session>().unwrap();
+        session.insert("user", "alice").await;
+
+        let header = set_cookie(&ctx);
+        assert!(header.contains("HttpOnly"));
+        assert!(header.contains("Max-Age=60"));
+        let value = header
+            .strip_prefix("aex_sid=")
+            .and_then(|v| v.split(';').next())
+            .unwrap();
+        assert_eq!(config.verify(value), Some(session.id()));
+
+        let mut ctx = ctx_with_cookie(Some(value));
+        assert!(mw(&mut ctx).await);
+        let loaded = ctx.local.get_value::<Session>().unwrap();
+        assert_eq!(loaded.id(), session.id());
+        assert_eq!(loaded.get("user").map(String::as_str), Some("alice"));
+        assert!(store.load(session.id()).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_cookie_gets_new_session() {
+        let store = MemoryStore::new();
+        let config = SessionConfig::new("secret").store(store.clone());
+        let mw = config.clone().build();
+
+        let mut data = std::collections::HashMap::new();
+        data.insert("user".to_string(), "alice".to_string());
+        store.save("victim", data).await;
+
+        let signed = config.sign("victim");
+        let flipped = if signed.ends_with('0') { '1' } else { '0' };
+        let forged_sig = format!("{}{}", &signed[..signed.len() - 1], flipped);
+        let other_key = SessionConfig::new("other").sign("victim");
+
+        for cookie in [forged_sig.as_str(), other_key.as_str(), "victim", "victim."] {
+            let mut ctx = ctx_with_cookie(Some(cookie));
+            assert!(mw(&mut ctx).await);
+            let session = ctx.local.get_value::<Session>().unwrap();
+            assert_ne!(session.id(), "victim", "cookie {:?} accepted", cookie);
+            assert!(session.data().is_empty());
+            assert!(set_cookie(&ctx).starts_with(&format!("aex_sid={}", session.id())));
+        }
+
+        // 签名正确时才会加载
+        let mut ctx = ctx_with_cookie(Some(&signed));
+        assert!(mw(&mut ctx).await);
+        assert_eq!(ctx.local.get_value::<Session>().unwrap().id(), "victim");
+    }
+}
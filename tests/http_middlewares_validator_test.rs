@@ -6,7 +6,10 @@ use aex::{
     exe,
     http::{
         meta::HttpMetadata,
-        middlewares::validator::{to_validator, value_to_string},
+        middlewares::validator::{
+            ArrayLength, LengthUnit, StringLength, extract_array_lengths, extract_string_lengths,
+            fill_open_ranges, to_validator, value_to_string,
+        },
         router::{NodeType, Router},
     },
     server::HTTPServer,
@@ -790,3 +793,931 @@ async fn test_validator_params_none_fallback() {
     assert!(resp_str.contains("200 OK"));
     assert!(resp_str.contains("params_initialized"));
 }
+
+#[tokio::test]
+async fn test_validator_json_body() {
+    let addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let actual_addr = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut dsl_map = AHashMap::new();
+    dsl_map.insert(
+        "body".to_string(),
+        "(name:string[3,10], age?:int[0,150], tags?:array<string>)".to_string(),
+    );
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.post(
+        "/users",
+        exe!(|ctx| {
+            ctx.send("created", None);
+            true
+        }),
+    )
+    .middleware(to_validator(dsl_map))
+    .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    // 1. 合法 JSON
+    let res = client
+        .post(format!("http://{}/users", actual_addr))
+        .header("content-type", "application/json")
+        .body(r#"{"name":"alice","age":30,"tags":["a","b"]}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().await.unwrap(), "created");
+
+    // 2. 长度约束失败
+    let res = client
+        .post(format!("http://{}/users", actual_addr))
+        .header("content-type", "application/json")
+        .body(r#"{"name":"al"}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(res.text().await.unwrap().starts_with("body validate error"));
+
+    // 3. 非法 JSON
+    let res = client
+        .post(format!("http://{}/users", actual_addr))
+        .header("content-type", "application/json")
+        .body(r#"{"name":"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(
+        res.text()
+            .await
+            .unwrap()
+            .starts_with("body conversion error: invalid JSON")
+    );
+
+    // 4. 数组中的 null 元素不会被静默丢弃；对象字段为 null 仍视为缺失
+    let res = client
+        .post(format!("http://{}/users", actual_addr))
+        .header("content-type", "application/json")
+        .body(r#"{"name":"alice","tags":["a",null,"b"]}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.text().await.unwrap(),
+        "body conversion error: null array element"
+    );
+    let res = client
+        .post(format!("http://{}/users", actual_addr))
+        .header("content-type", "application/json")
+        .body(r#"{"name":"alice","age":null}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+}
+
+#[test]
+fn test_extract_array_lengths() {
+    let (dsl, lengths) =
+        extract_array_lengths("(tags:array<string>[1,5], ids?:array<int[0,100]>(0,3])");
+    assert_eq!(dsl, "(tags:array<string>, ids?:array<int[0,100]>)");
+    assert_eq!(
+        lengths,
+        vec![
+            ArrayLength {
+                field: "tags".into(),
+                min: 1,
+                max: 5,
+                min_inclusive: true,
+                max_inclusive: true,
+            },
+            ArrayLength {
+                field: "ids".into(),
+                min: 0,
+                max: 3,
+                min_inclusive: false,
+                max_inclusive: true,
+            },
+        ]
+    );
+
+    // 元素区间保持不变
+    let (dsl, lengths) = extract_array_lengths("scores:array<int[0,100]>");
+    assert_eq!(dsl, "scores:array<int[0,100]>");
+    assert!(lengths.is_empty());
+
+    // 嵌套字段按完整路径记录，与同名的顶层字段互不影响
+    let (dsl, lengths) = extract_array_lengths(
+        r#"(tags?:array<int>[0,1], profile:object(tags:array<string>[1,5], bio?:string regex("a>[2,3]")), items?:array<object(tags:array<string>(0,2])>[1,3], grid?:array<array<int>[2,2]>)"#,
+    );
+    assert_eq!(
+        dsl,
+        r#"(tags?:array<int>, profile:object(tags:array<string>, bio?:string regex("a>[2,3]")), items?:array<object(tags:array<string>)>, grid?:array<array<int>>)"#
+    );
+    let fields: Vec<&str> = lengths.iter().map(|l| l.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        ["tags", "profile.tags", "items[].tags", "items", "grid[]"]
+    );
+
+    // 数组元素中的对象逐个检查
+    let tags = |n: usize| Value::Array(vec![Value::String("a".into()); n]);
+    let item = |n: usize| Value::Object([("tags".to_string(), tags(n))].into_iter().collect());
+    let body = |items: Vec<Value>| {
+        Value::Object(
+            [
+                ("tags".to_string(), tags(5)),
+                ("items".to_string(), Value::Array(items)),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    };
+    let nested = &lengths[2];
+    assert!(nested.check_value(&body(vec![item(1), item(2)])).is_ok());
+    assert_eq!(
+        nested.check_value(&body(vec![item(1), item(3)])),
+        Err("Field 'items[].tags' array length 3 out of range (0, 2]".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_validator_array_length() {
+    let addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let actual_addr = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut dsl_map = AHashMap::new();
+    dsl_map.insert("body".to_string(), "(tags:array<string>[2,3])".to_string());
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.post(
+        "/tags",
+        exe!(|ctx| {
+            ctx.send("ok", None);
+            true
+        }),
+    )
+    .middleware(to_validator(dsl_map))
+    .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let post = |body: &'static str| {
+        client
+            .post(format!("http://{}/tags", actual_addr))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+    };
+
+    let res = post("tags=a&tags=b").await.unwrap();
+    assert_eq!(res.status(), 200);
+
+    // 太短
+    let res = post("tags=a").await.unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(
+        res.text()
+            .await
+            .unwrap()
+            .contains("array length 1 out of range")
+    );
+
+    // 太长
+    let res = post("tags=a&tags=b&tags=c&tags=d").await.unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_validator_nested_array_length() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.post(
+        "/profile",
+        exe!(|ctx| {
+            ctx.send("ok", None);
+            true
+        }),
+    )
+    .middleware(v!(body => "(tags?:array<string>[0,1], profile?:object(tags:array<string>[1,5]))"))
+    .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let post = |body: &'static str| {
+        let req = client
+            .post(format!("http://{}/profile", actual_addr))
+            .header("content-type", "application/json")
+            .body(body);
+        async move {
+            let res = req.send().await.unwrap();
+            (res.status().as_u16(), res.text().await.unwrap())
+        }
+    };
+
+    // 嵌套字段的区间作用于嵌套的值，而不是同名的顶层字段
+    assert_eq!(
+        post(r#"{"tags":["a"],"profile":{"tags":["a","b","c"]}}"#)
+            .await
+            .0,
+        200
+    );
+    let (status, body) = post(r#"{"profile":{"tags":[]}}"#).await;
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Field 'profile.tags' array length 0 out of range"),
+        "{}",
+        body
+    );
+    let (status, body) = post(r#"{"tags":["a","b"],"profile":{"tags":["a"]}}"#).await;
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Field 'tags' array length 2 out of range"),
+        "{}",
+        body
+    );
+}
+
+#[tokio::test]
+async fn test_validator_array_element_type() {
+    let addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let actual_addr = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut dsl_map = AHashMap::new();
+    dsl_map.insert("body".to_string(), "(scores:array<int[0,100]>)".to_string());
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.post(
+        "/scores",
+        exe!(|ctx| {
+            ctx.send("ok", None);
+            true
+        }),
+    )
+    .middleware(to_validator(dsl_map))
+    .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let post = |ct: &'static str, body: &'static str| {
+        client
+            .post(format!("http://{}/scores", actual_addr))
+            .header("content-type", ct)
+            .body(body)
+            .send()
+    };
+    let form = "application/x-www-form-urlencoded";
+    let json = "application/json";
+
+    // 表单与 JSON 的元素都按 int 校验
+    assert_eq!(
+        post(form, "scores=1&scores=99").await.unwrap().status(),
+        200
+    );
+    assert_eq!(
+        post(json, r#"{"scores":[1,99]}"#).await.unwrap().status(),
+        200
+    );
+
+    // 非 int 元素
+    let res = post(form, "scores=1&scores=x").await.unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(res.text().await.unwrap().contains("not a valid integer"));
+    assert_eq!(
+        post(json, r#"{"scores":[1,"x"]}"#).await.unwrap().status(),
+        400
+    );
+
+    // 元素越界
+    assert_eq!(post(form, "scores=101").await.unwrap().status(), 400);
+}
+
+#[tokio::test]
+async fn test_validator_datetime_and_date() {
+    let addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let actual_addr = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut dsl_map = AHashMap::new();
+    dsl_map.insert(
+        "query".to_string(),
+        "(created_at:datetime, day?:date)".to_string(),
+    );
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.get(
+        "/events",
+        exe!(|ctx| {
+            let created_at = ctx.req().query("created_at").unwrap_or_default();
+            ctx.send(created_at, None);
+            true
+        }),
+    )
+    .middleware(to_validator(dsl_map))
+    .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let get = |query: &'static str| {
+        client
+            .get(format!("http://{}/events?{}", actual_addr, query))
+            .send()
+    };
+
+    // RFC 3339（带时区偏移）会被规范化为 UTC
+    let res = get("created_at=2024-05-01T10:30:00%2B08:00&day=2024-02-29")
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().await.unwrap(), "2024-05-01T02:30:00Z");
+
+    let res = get("created_at=not-a-date").await.unwrap();
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.text().await.unwrap(),
+        "query conversion error: 'not-a-date' is not a valid datetime"
+    );
+
+    // 格式正确但数值越界
+    let res = get("created_at=2024-13-01T00:00:00Z").await.unwrap();
+    assert_eq!(res.status(), 400);
+    let res = get("created_at=2024-01-01T00:00:00Z&day=2023-02-29")
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert!(res.text().await.unwrap().contains("is not a valid date"));
+}
+
+#[tokio::test]
+async fn test_validator_range_inclusivity() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    // `[]` 包含边界，`()` 不包含，可以混用
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.get("/inclusive", exe!(|_ctx| { true }))
+        .middleware(v!(query => "(n:int[0,100], f?:float[0.5,1.5])"))
+        .register();
+    hr.get("/exclusive", exe!(|_ctx| { true }))
+        .middleware(v!(query => "(n:int(0,100), f?:float(0.5,1.5])"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let status = |path: &str| {
+        let req = client.get(format!("http://{}{}", actual_addr, path));
+        async move { req.send().await.unwrap().status().as_u16() }
+    };
+
+    for n in ["0", "100", "50"] {
+        assert_eq!(status(&format!("/inclusive?n={}", n)).await, 200);
+    }
+    assert_eq!(status("/inclusive?n=-1").await, 400);
+    assert_eq!(status("/inclusive?n=101").await, 400);
+    assert_eq!(status("/inclusive?n=1&f=0.5").await, 200);
+
+    assert_eq!(status("/exclusive?n=0").await, 400);
+    assert_eq!(status("/exclusive?n=100").await, 400);
+    assert_eq!(status("/exclusive?n=1").await, 200);
+    assert_eq!(status("/exclusive?n=99").await, 200);
+    assert_eq!(status("/exclusive?n=1&f=0.5").await, 400);
+    assert_eq!(status("/exclusive?n=1&f=1.5").await, 200);
+}
+
+#[test]
+fn test_fill_open_ranges() {
+    assert_eq!(
+        fill_open_ranges("(a:int[0,], b?:int(,100), c:float(0.5,])"),
+        format!(
+            "(a:int[0,{}], b?:int[{},100), c:float(0.5,{:e}])",
+            i64::MAX,
+            i64::MIN,
+            f64::MAX
+        )
+    );
+    assert_eq!(
+        fill_open_ranges("(name:string[,20], tags:array<string>[1,])"),
+        format!("(name:string[0,20], tags:array<string>[1,{}])", usize::MAX)
+    );
+    // 两端齐全的区间与引号内容保持不变
+    let dsl = r#"(n:int[0,100], s:string regex("^[a-z,]+$"))"#;
+    assert_eq!(fill_open_ranges(dsl), dsl);
+}
+
+#[tokio::test]
+async fn test_validator_min_only_and_max_only_ranges() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.get("/min", exe!(|_ctx| { true }))
+        .middleware(v!(query => "(n:int[0,], f?:float(0.5,], s?:string[3,])"))
+        .register();
+    hr.get("/max", exe!(|_ctx| { true }))
+        .middleware(v!(query => "(n:int[,100), f?:float[,1.5], s?:string[,3])"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let status = |path: &str| {
+        let req = client.get(format!("http://{}{}", actual_addr, path));
+        async move { req.send().await.unwrap().status().as_u16() }
+    };
+
+    assert_eq!(status("/min?n=0").await, 200);
+    assert_eq!(status(&format!("/min?n={}", i64::MAX)).await, 200);
+    assert_eq!(status("/min?n=-1").await, 400);
+    assert_eq!(status("/min?n=1&f=1e300").await, 200);
+    assert_eq!(status("/min?n=1&f=0.5").await, 400);
+    assert_eq!(status("/min?n=1&s=abcdefgh").await, 200);
+    assert_eq!(status("/min?n=1&s=ab").await, 400);
+
+    assert_eq!(status("/max?n=99").await, 200);
+    assert_eq!(status(&format!("/max?n={}", i64::MIN)).await, 200);
+    assert_eq!(status("/max?n=100").await, 400);
+    assert_eq!(status("/max?n=1&f=-1e300").await, 200);
+    assert_eq!(status("/max?n=1&f=1.6").await, 400);
+    assert_eq!(status("/max?n=1&s=").await, 200);
+    assert_eq!(status("/max?n=1&s=abcd").await, 400);
+
+    // 错误信息只给出设置了的一端，不输出补齐的 i64 极值
+    let body = |path: &str| {
+        let req = client.get(format!("http://{}{}", actual_addr, path));
+        async move { req.send().await.unwrap().text().await.unwrap() }
+    };
+    assert_eq!(
+        body("/min?n=-1").await,
+        "query validate error: n value -1 must be >= 0"
+    );
+    assert_eq!(
+        body("/max?n=100").await,
+        "query validate error: n value 100 must be < 100"
+    );
+}
+
+#[test]
+fn test_extract_string_lengths() {
+    let (dsl, lengths) = extract_string_lengths(
+        r#"(name:string chars[2,4], nick?:string bytes(0,9], tag:string regex("chars[1,2]"))"#,
+    );
+    assert_eq!(
+        dsl,
+        r#"(name:string, nick?:string, tag:string regex("chars[1,2]"))"#
+    );
+    assert_eq!(
+        lengths,
+        vec![
+            StringLength {
+                field: "name".into(),
+                unit: LengthUnit::Chars,
+                min: 2,
+                max: 4,
+                min_inclusive: true,
+                max_inclusive: true,
+            },
+            StringLength {
+                field: "nick".into(),
+                unit: LengthUnit::Bytes,
+                min: 0,
+                max: 9,
+                min_inclusive: false,
+                max_inclusive: true,
+            },
+        ]
+    );
+
+    // 多字节字符串：3 个字符，9 个字节
+    let s = "日本語";
+    assert!(lengths[0].check(s).is_ok());
+    assert!(lengths[1].check(s).is_ok());
+    assert!(lengths[0].check("日本語です").is_err());
+    assert!(lengths[1].check("日本語です").is_err());
+
+    // 嵌套字段与数组元素按完整路径记录
+    let (dsl, lengths) = extract_string_lengths(
+        "(name:string chars[1,2], profile:object(name:string chars[2,4]), tags?:array<string bytes[1,3]>)",
+    );
+    assert_eq!(
+        dsl,
+        "(name:string, profile:object(name:string), tags?:array<string>)"
+    );
+    let fields: Vec<&str> = lengths.iter().map(|l| l.field.as_str()).collect();
+    assert_eq!(fields, ["name", "profile.name", "tags[]"]);
+}
+
+#[tokio::test]
+async fn test_validator_char_vs_byte_length() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.post("/chars", exe!(|_ctx| { true }))
+        .middleware(v!(body => "(name:string chars[2,4])"))
+        .register();
+    hr.post("/bytes", exe!(|_ctx| { true }))
+        .middleware(v!(body => "(name:string bytes[2,4])"))
+        .register();
+    hr.post("/bytes_min", exe!(|_ctx| { true }))
+        .middleware(v!(body => "(name:string bytes[6,])"))
+        .register();
+    hr.post("/nested", exe!(|_ctx| { true }))
+        .middleware(v!(body => "(name?:string chars[1,2], profile?:object(name:string chars[2,4]), tags?:array<string chars[1,2]>)"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let post = |path: &str, name: &str| {
+        let req = client
+            .post(format!("http://{}{}", actual_addr, path))
+            .header("content-type", "application/json")
+            .body(format!(r#"{{"name":"{}"}}"#, name));
+        async move { req.send().await.unwrap() }
+    };
+
+    // "日本語" 是 3 个字符、9 个字节
+    assert_eq!(post("/chars", "日本語").await.status(), 200);
+    assert_eq!(post("/chars", "abc").await.status(), 200);
+    let res = post("/bytes", "日本語").await;
+    assert_eq!(res.status(), 400);
+    assert!(res.text().await.unwrap().contains("byte length 9"));
+    assert_eq!(post("/bytes", "abc").await.status(), 200);
+    assert_eq!(post("/bytes_min", "日本語").await.status(), 200);
+    assert_eq!(post("/bytes_min", "abc").await.status(), 400);
+
+    // 嵌套字段的区间作用于嵌套的值，而不是同名的顶层字段
+    let nested = |body: &'static str| {
+        let req = client
+            .post(format!("http://{}/nested", actual_addr))
+            .header("content-type", "application/json")
+            .body(body);
+        async move {
+            let res = req.send().await.unwrap();
+            (res.status().as_u16(), res.text().await.unwrap())
+        }
+    };
+    assert_eq!(
+        nested(r#"{"name":"日本","profile":{"name":"日本語"},"tags":["日本","a"]}"#)
+            .await
+            .0,
+        200
+    );
+    let (status, body) = nested(r#"{"name":"ab","profile":{"name":"a"}}"#).await;
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Field 'profile.name' char length 1 out of range"),
+        "{}",
+        body
+    );
+    let (status, body) = nested(r#"{"name":"abc","profile":{"name":"abc"}}"#).await;
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Field 'name' char length 3 out of range"),
+        "{}",
+        body
+    );
+    let (status, body) = nested(r#"{"tags":["ab","abc"]}"#).await;
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Field 'tags[]' char length 3 out of range"),
+        "{}",
+        body
+    );
+}
+
+#[tokio::test]
+async fn test_validator_object_rules() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.post("/users", exe!(|_ctx| { true }))
+        .middleware(v!(body => "(name:string, address:object(city:string[2,20], zip?:int))"))
+        .register();
+    hr.get("/search", exe!(|_ctx| { true }))
+        .middleware(v!(query => "(filter:object(kind:string))"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    let post = |body: &'static str| {
+        client
+            .post(format!("http://{}/users", actual_addr))
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+    };
+
+    // JSON 请求体递归校验嵌套字段
+    let res = post(r#"{"name":"a","address":{"city":"Paris","zip":75001}}"#)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = post(r#"{"name":"a","address":{"city":"P"}}"#)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = post(r#"{"name":"a","address":"Paris"}"#).await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    // 扁平来源上的对象规则给出明确错误，而不是 panic
+    let res = client
+        .get(format!("http://{}/search?filter=x", actual_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        res.text().await.unwrap(),
+        "query conversion error: 'x' cannot be an object; nested fields require a JSON body"
+    );
+}
+
+#[tokio::test]
+async fn test_validator_rejects_non_finite_floats() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.get("/price", exe!(|_ctx| { true }))
+        .middleware(v!(query => "(p:float[0,100])"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    // NaN 与任何边界比较都为 false，不能借此绕过范围检查
+    for raw in ["NaN", "nan", "inf", "-inf", "infinity"] {
+        let res = client
+            .get(format!("http://{}/price?p={}", actual_addr, raw))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "{} should be rejected", raw);
+        assert_eq!(
+            res.text().await.unwrap(),
+            format!("query conversion error: '{}' is not a valid float", raw)
+        );
+    }
+
+    let res = client
+        .get(format!("http://{}/price?p=42.5", actual_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn test_validator_large_integers_not_corrupted() {
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.get(
+        "/items",
+        exe!(|ctx| {
+            let id = ctx.req().query("id").unwrap_or_default();
+            ctx.send(id, None);
+            true
+        }),
+    )
+    .middleware(v!(query => "(id:int)"))
+    .register();
+    hr.post("/items", exe!(|_ctx| { true }))
+        .middleware(v!(body => "(id:int[9007199254740992,9007199254740992])"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+    let client = reqwest::Client::new();
+
+    // 2^53 + 1 无法用 f64 精确表示，校验后写回的值必须保持原样
+    let res = client
+        .get(format!("http://{}/items?id=9007199254740993", actual_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().await.unwrap(), "9007199254740993");
+
+    let post = |body: &'static str| {
+        client
+            .post(format!("http://{}/items", actual_addr))
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+    };
+    // 经过 f64 比较时 2^53 + 1 会被当成 2^53 而误判通过
+    let res = post(r#"{"id":9007199254740992}"#).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = post(r#"{"id":9007199254740993}"#).await.unwrap();
+    assert_eq!(res.status(), 400);
+
+    // 超出 i64 的整数被拒绝，而不是降级为浮点数
+    let res = client
+        .get(format!(
+            "http://{}/items?id=18446744073709551615",
+            actual_addr
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = post(r#"{"id":18446744073709551615}"#).await.unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_validator_connection_policy_on_error() {
+    use aex::http::middlewares::validator::OnError;
+
+    let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let ok = || {
+        exe!(|ctx| {
+            ctx.send("ok", None);
+            true
+        })
+    };
+    let mut hr = Router::new(NodeType::Static("root".into()));
+    hr.get("/keep", ok())
+        .middleware(v!(query => "(id:int)"))
+        .register();
+    hr.get("/close", ok())
+        .middleware(v!(on_error = OnError::Close; query => "(id:int)"))
+        .register();
+
+    let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+    tokio::spawn(async move {
+        let _ = server.start().await;
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+
+    let connection = |path: &'static str| async move {
+        let res = reqwest::get(format!("http://{}{}", actual_addr, path))
+            .await
+            .unwrap();
+        let header = res
+            .headers()
+            .get("connection")
+            .map(|v| v.to_str().unwrap().to_string());
+        (res.status().as_u16(), header)
+    };
+
+    assert_eq!(connection("/keep?id=x").await, (400, None));
+    assert_eq!(
+        connection("/close?id=x").await,
+        (400, Some("close".to_string()))
+    );
+    // 校验通过时两种策略都不影响连接
+    assert_eq!(connection("/close?id=1").await, (200, None));
+
+    // KeepAlive：400 之后同一连接上的下一个请求仍被处理
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    async fn round_trip<S>(stream: &mut BufReader<S>, path: &str) -> (String, bool)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let req = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        stream.get_mut().write_all(req.as_bytes()).await.unwrap();
+        let mut status = String::new();
+        stream.read_line(&mut status).await.unwrap();
+        let (mut len, mut close) = (0, false);
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            let line = line.trim_end().to_ascii_lowercase();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(v) = line.strip_prefix("content-length:") {
+                len = v.trim().parse().unwrap();
+            }
+            close |= line == "connection: close";
+        }
+        let mut body = vec![0; len];
+        stream.read_exact(&mut body).await.unwrap();
+        (status.trim_end().to_string(), close)
+    }
+
+    let socket = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+    let mut stream = BufReader::new(socket);
+    let (status, close) = round_trip(&mut stream, "/keep?id=x").await;
+    assert_eq!(
+        (status.as_str(), close),
+        ("HTTP/1.1 400 Bad Request", false)
+    );
+    let (status, _) = tokio::time::timeout(
+        tokio::time::Duration::from_secs(2),
+        round_trip(&mut stream, "/keep?id=1"),
+    )
+    .await
+    .expect("second request on the kept-alive connection was not answered");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+
+    // Close：400 之后服务端关闭连接
+    let socket = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+    let mut stream = BufReader::new(socket);
+    let (status, close) = round_trip(&mut stream, "/close?id=x").await;
+    assert_eq!((status.as_str(), close), ("HTTP/1.1 400 Bad Request", true));
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest).await.unwrap();
+    assert!(rest.is_empty());
+}
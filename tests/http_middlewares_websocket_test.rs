@@ -3,12 +3,13 @@ mod websocket_tests {
     use aex::{
         connection::{context::Context, global::GlobalContext},
         http::{
-            middlewares::websocket::WebSocket,
+            middlewares::websocket::{WebSocket, WebSocketHub, WebSocketSender, WsConnId},
             protocol::{
                 header::{HeaderKey, Headers},
                 method::HttpMethod,
+                status::StatusCode,
             },
-            websocket::{WSCodec, WSFrame},
+            websocket::{WSCloseError, WSCodec, WSFrame},
         },
         tcp::types::{Command, Frame},
     };
@@ -52,6 +53,23 @@ mod websocket_tests {
         assert!(!WebSocket::check(HttpMethod::POST, &headers_ref));
     }
 
+    #[test]
+    fn test_check_connection_tokens() {
+        let check = |connection: &str| {
+            let mut headers = AHashMap::new();
+            headers.insert(HeaderKey::Upgrade, "websocket".to_string());
+            headers.insert(HeaderKey::Connection, connection.to_string());
+            WebSocket::check(HttpMethod::GET, &Headers::from(headers))
+        };
+
+        assert!(check("keep-alive, Upgrade"));
+        assert!(check("Upgrade,keep-alive"));
+        assert!(check(" upgrade "));
+        // 仅包含子串的单个 token 不算
+        assert!(!check("keep-alive-upgrade-ish"));
+        assert!(!check("no-upgrade, keep-alive"));
+    }
+
     // --- 2. Codec 编解码测试 (核心更新) ---
     #[tokio::test]
     async fn test_ws_codec_decode_text() {
@@ -132,6 +150,11 @@ mod websocket_tests {
             .await
             .unwrap();
 
+        // 服务端发起关闭，客户端回显后循环结束
+        let reply = client_framed.next().await.unwrap().unwrap();
+        assert_eq!(reply, WSFrame::Close(1000, None));
+        client_framed.send(reply).await.unwrap();
+
         let res = server_handle.await.unwrap();
         assert!(res.is_ok());
     }
@@ -246,6 +269,9 @@ mod websocket_tests {
             .send(WSFrame::Text("exit".into()))
             .await
             .unwrap();
+        let reply = client_framed.next().await.unwrap().unwrap();
+        assert_eq!(reply, WSFrame::Close(1000, None));
+        client_framed.send(reply).await.unwrap();
 
         // 5. 验证服务是否正常关闭
         let res = server_handle.await.unwrap();
@@ -965,4 +991,1187 @@ mod websocket_tests {
         // 触发 Command::data()
         assert_eq!(frame.data(), &data);
     }
+
+    fn ws_ctx(
+        server: tokio::io::DuplexStream,
+        global: Arc<GlobalContext>,
+        addr: SocketAddr,
+    ) -> Context {
+        let (s_reader, s_writer) = tokio::io::split(server);
+        let reader = Some(Box::new(BufReader::new(s_reader))
+            as Box<dyn tokio::io::AsyncBufRead + Send + Sync + Unpin>);
+        let writer =
+            Some(Box::new(s_writer) as Box<dyn tokio::io::AsyncWrite + Send + Sync + Unpin>);
+        Context::new(reader, writer, global, addr)
+    }
+
+    #[tokio::test]
+    async fn test_hub_relays_messages_between_clients() {
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let hub = WebSocketHub::new();
+        global.set(hub.clone()).await;
+
+        // 收到的文本转发给其他所有连接
+        let ws = WebSocket::new().on_text(|_ws, ctx, text| {
+            let hub = ctx.local.get_value::<WebSocketHub>().unwrap();
+            let WsConnId(me) = ctx.local.get_value::<WsConnId>().unwrap();
+            Box::pin(async move {
+                hub.broadcast_except(me, WSFrame::Text(text)).await;
+                true
+            })
+        });
+
+        let (client_a, server_a) = duplex(1024);
+        let (client_b, server_b) = duplex(1024);
+        let mut ctx_a = ws_ctx(server_a, global.clone(), addr);
+        let mut ctx_b = ws_ctx(server_b, global.clone(), addr);
+        let ws_a = ws.clone();
+        let handle_a = tokio::spawn(async move { WebSocket::run(&ws_a, &mut ctx_a).await });
+        let handle_b = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx_b).await });
+
+        // 等待两个连接都完成注册
+        for _ in 0..50 {
+            if hub.len().await == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(hub.len().await, 2);
+
+        let mut a = Framed::new(client_a, WSCodec);
+        let mut b = Framed::new(client_b, WSCodec);
+        a.send(WSFrame::Text("hello from A".into())).await.unwrap();
+
+        let received = tokio::time::timeout(std::time::Duration::from_secs(2), b.next())
+            .await
+            .expect("B did not receive the relayed message")
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, WSFrame::Text("hello from A".into()));
+
+        // 定向发送
+        let ids = hub.ids().await;
+        assert_eq!(ids.len(), 2);
+        for id in ids {
+            assert!(hub.send_to(id, WSFrame::Text(format!("to {}", id))).await);
+        }
+        assert!(!hub.send_to(999, WSFrame::Text("nobody".into())).await);
+
+        // 关闭后从 hub 注销
+        a.send(WSFrame::Close(1000, None)).await.unwrap();
+        b.send(WSFrame::Close(1000, None)).await.unwrap();
+        handle_a.await.unwrap().unwrap();
+        handle_b.await.unwrap().unwrap();
+        assert_eq!(hub.len().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_utf8_text_closes_with_1007() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let called = Arc::new(AtomicBool::new(false));
+        let flag = called.clone();
+        let ws = WebSocket::new().on_text(move |_ws, _ctx, _text| {
+            flag.store(true, Ordering::SeqCst);
+            Box::pin(async { true })
+        });
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        client
+            .write_all(&create_masked_frame(0x1, &[0x68, 0xff, 0xfe]))
+            .await
+            .unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(2), framed.next())
+            .await
+            .expect("no close frame received")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(reply, WSFrame::Close(1007, _)));
+        assert!(handle.await.unwrap().is_err());
+        assert!(!called.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_parse_close_payload_code_allowlist() {
+        let accepted: [u16; 11] = [
+            1000, 1001, 1002, 1003, 1007, 1008, 1009, 1010, 1011, 3000, 4999,
+        ];
+        let rejected: [u16; 10] = [0, 999, 1004, 1005, 1006, 1012, 1015, 2999, 5000, u16::MAX];
+
+        for code in accepted {
+            let payload = code.to_be_bytes();
+            let (parsed, _) = WebSocket::parse_close_payload(&payload).unwrap();
+            assert_eq!(parsed, code);
+        }
+        for code in rejected {
+            let err = WebSocket::parse_close_payload(&code.to_be_bytes()).unwrap_err();
+            let close = err.downcast_ref::<WSCloseError>().unwrap();
+            assert_eq!(close.code, 1002, "code {} should be rejected", code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_connections_get_distinct_ids() {
+        let global = Arc::new(GlobalContext::new(
+            "127.0.0.1:8080".parse::<SocketAddr>().unwrap(),
+            None,
+        ));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let ws = WebSocket::new().on_text(move |_ws, ctx, _text| {
+            let _ = tx.send((WebSocket::conn_id(ctx), ctx.req().peer_addr()));
+            Box::pin(async { true })
+        });
+
+        let mut clients = Vec::new();
+        let mut handles = Vec::new();
+        for port in [40001u16, 40002] {
+            let addr = SocketAddr::from(([127, 0, 0, 1], port));
+            let (client, server) = duplex(1024);
+            let mut ctx = ws_ctx(server, global.clone(), addr);
+            let ws = ws.clone();
+            handles.push(tokio::spawn(
+                async move { WebSocket::run(&ws, &mut ctx).await },
+            ));
+            clients.push(Framed::new(client, WSCodec));
+        }
+
+        for client in clients.iter_mut() {
+            client.send(WSFrame::Text("hi".into())).await.unwrap();
+        }
+        let (id_a, addr_a) = rx.recv().await.unwrap();
+        let (id_b, addr_b) = rx.recv().await.unwrap();
+        assert!(id_a.is_some() && id_b.is_some());
+        assert_ne!(id_a, id_b);
+        let mut ports = [addr_a.unwrap().port(), addr_b.unwrap().port()];
+        ports.sort();
+        assert_eq!(ports, [40001, 40002]);
+
+        for client in clients.iter_mut() {
+            client.send(WSFrame::Close(1000, None)).await.unwrap();
+        }
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sender_pushes_after_handler_returns() {
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+
+        // 处理器立即返回，推送由后台任务完成
+        let ws = WebSocket::new().on_text(|_ws, ctx, text| {
+            let sender = WebSocketSender::from_ctx(ctx).unwrap();
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                assert!(sender.send_text(format!("later: {}", text)));
+                assert!(sender.send_binary(vec![1, 2, 3]));
+            });
+            Box::pin(async { true })
+        });
+
+        let (client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        let mut framed = Framed::new(client, WSCodec);
+        framed.send(WSFrame::Text("sub".into())).await.unwrap();
+
+        let timeout = std::time::Duration::from_secs(2);
+        let first = tokio::time::timeout(timeout, framed.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(first, WSFrame::Text("later: sub".into()));
+        let second = tokio::time::timeout(timeout, framed.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(second, WSFrame::Binary(vec![1, 2, 3]));
+
+        framed.send(WSFrame::Close(1000, None)).await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    fn upgrade_meta(origin: Option<&str>) -> aex::http::meta::HttpMetadata {
+        let mut meta = aex::http::meta::HttpMetadata::new();
+        meta.method = HttpMethod::GET;
+        meta.headers.insert(HeaderKey::Upgrade, "websocket");
+        meta.headers.insert(HeaderKey::Connection, "Upgrade");
+        meta.headers
+            .insert(HeaderKey::SecWebSocketKey, "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(origin) = origin {
+            meta.headers.insert(HeaderKey::Origin, origin);
+        }
+        meta
+    }
+
+    /// 通过中间件发起握手，返回服务端写回的首行（无响应时为空）
+    async fn handshake_status(ws: WebSocket, origin: Option<&str>) -> (String, StatusCode) {
+        use aex::http::meta::HttpMetadata;
+        use tokio::io::AsyncReadExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        ctx.local.set_value(upgrade_meta(origin));
+
+        let mw = WebSocket::to_middleware(ws);
+        let handle = tokio::spawn(async move {
+            let passed = mw(&mut ctx).await;
+            let status = ctx.local.get_ref::<HttpMetadata>().unwrap().status;
+            (passed, status)
+        });
+
+        let mut buf = vec![0u8; 256];
+        let n = tokio::time::timeout(std::time::Duration::from_millis(300), client.read(&mut buf))
+            .await
+            .unwrap_or(Ok(0))
+            .unwrap();
+        drop(client);
+        let (passed, status) = handle.await.unwrap();
+        assert!(!passed);
+        let text = String::from_utf8_lossy(&buf[..n]);
+        (text.lines().next().unwrap_or("").to_string(), status)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_origin_checking() {
+        let restricted = || WebSocket::new().allowed_origins(["https://app.example.com"]);
+
+        let (line, _) = handshake_status(restricted(), Some("https://APP.example.com")).await;
+        assert_eq!(line, "HTTP/1.1 101 Switching Protocols");
+
+        for origin in [Some("https://evil.example.com"), None] {
+            let (line, status) = handshake_status(restricted(), origin).await;
+            assert!(line.is_empty(), "no 101 expected, got {}", line);
+            assert_eq!(status, StatusCode::Forbidden);
+        }
+
+        // 默认不限制
+        let (line, _) = handshake_status(WebSocket::new(), Some("https://any.example.com")).await;
+        assert_eq!(line, "HTTP/1.1 101 Switching Protocols");
+        let (line, _) = handshake_status(WebSocket::new(), None).await;
+        assert_eq!(line, "HTTP/1.1 101 Switching Protocols");
+    }
+
+    #[tokio::test]
+    async fn test_ping_flood_closes_with_1008() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().ping_limit(3, std::time::Duration::from_secs(10));
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        for i in 0..6u8 {
+            client
+                .write_all(&create_masked_frame(0x9, &[i]))
+                .await
+                .unwrap();
+        }
+
+        let mut framed = Framed::new(client, WSCodec);
+        let mut pongs = 0;
+        loop {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(2), framed.next())
+                .await
+                .expect("no close frame received")
+                .unwrap()
+                .unwrap();
+            match frame {
+                WSFrame::Pong(_) => pongs += 1,
+                WSFrame::Close(code, _) => {
+                    assert_eq!(code, 1008);
+                    break;
+                }
+                other => panic!("unexpected frame {:?}", other),
+            }
+        }
+        // 阈值内的 Ping 正常回复，超出后不再回复
+        assert_eq!(pongs, 3);
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_max_connections_per_ip() {
+        use aex::{
+            exe,
+            http::{
+                router::{NodeType, Router},
+                types::Executor,
+            },
+            server::HTTPServer,
+        };
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let ws = WebSocket::new().max_connections_per_ip(2);
+        let probe = ws.clone();
+        let mw: Arc<Executor> = Arc::from(WebSocket::to_middleware(ws));
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/ws", exe!(|_ctx| { true }))
+            .middleware(mw)
+            .register();
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let handshake = "GET /ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                         Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                         Sec-WebSocket-Version: 13\r\n\r\n";
+        let connect = || async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream.write_all(handshake.as_bytes()).await.unwrap();
+            let mut buf = vec![0u8; 512];
+            let n = stream.read(&mut buf).await.unwrap();
+            let head = String::from_utf8_lossy(&buf[..n]).to_string();
+            (stream, head)
+        };
+
+        let (first, head) = connect().await;
+        assert!(head.starts_with("HTTP/1.1 101"));
+        let (_second, head) = connect().await;
+        assert!(head.starts_with("HTTP/1.1 101"));
+        assert_eq!(probe.connections_from(addr.ip()), 2);
+
+        // 超出上限的握手被拒绝
+        let (_extra, head) = connect().await;
+        assert!(head.starts_with("HTTP/1.1 429"), "{}", head);
+
+        // 断开一个连接后名额释放
+        drop(first);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(probe.connections_from(addr.ip()), 1);
+        let (_third, head) = connect().await;
+        assert!(head.starts_with("HTTP/1.1 101"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_track_traffic() {
+        use aex::http::middlewares::websocket::WsMetricsSnapshot;
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().on_text(|_ws, ctx, text| {
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                sender.send_text(text.to_uppercase());
+            }
+            Box::pin(async { true })
+        });
+        let probe = ws.clone();
+        assert_eq!(probe.metrics(), WsMetricsSnapshot::default());
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        client
+            .write_all(&create_masked_frame(0x1, b"hello"))
+            .await
+            .unwrap();
+        client
+            .write_all(&create_masked_frame(0x9, b"abc"))
+            .await
+            .unwrap();
+
+        let (client_r, mut client_w) = tokio::io::split(client);
+        let mut framed = tokio_util::codec::FramedRead::new(client_r, WSCodec);
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Text("HELLO".into())
+        );
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Pong(b"abc".to_vec())
+        );
+
+        // 写任务在发送完成后才计数，稍等其落账
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let m = probe.metrics();
+        assert_eq!(m.frames_received, 2);
+        assert_eq!(m.bytes_received, 8);
+        assert_eq!(m.frames_sent, 2);
+        assert_eq!(m.bytes_sent, 8);
+        assert_eq!(m.active_connections, 1);
+
+        // 关闭后连接数归零，关闭帧计入接收
+        client_w
+            .write_all(&create_masked_frame(0x8, &1000u16.to_be_bytes()))
+            .await
+            .unwrap();
+        handle.await.unwrap().unwrap();
+        let m = probe.metrics();
+        assert_eq!(m.frames_received, 3);
+        assert_eq!(m.bytes_received, 10);
+        assert_eq!(m.active_connections, 0);
+    }
+
+    #[tokio::test]
+    async fn test_json_messages_round_trip() {
+        use serde::{Deserialize, Serialize};
+        use tokio::io::AsyncWriteExt;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Move {
+            player: String,
+            x: i32,
+            y: i32,
+        }
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().on_json(|_ws, ctx, mut msg: Move| {
+            msg.x += 1;
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                sender.send_json(&msg);
+            }
+            Box::pin(async { true })
+        });
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        client
+            .write_all(&create_masked_frame(
+                0x1,
+                br#"{"player":"alice","x":1,"y":2}"#,
+            ))
+            .await
+            .unwrap();
+        // 结构不匹配的 JSON 同样视为非法
+        client
+            .write_all(&create_masked_frame(0x1, br#"{"player":"bob"}"#))
+            .await
+            .unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        let reply = framed.next().await.unwrap().unwrap();
+        let WSFrame::Text(text) = reply else {
+            panic!("expected text frame, got {:?}", reply);
+        };
+        assert_eq!(
+            serde_json::from_str::<Move>(&text).unwrap(),
+            Move {
+                player: "alice".into(),
+                x: 2,
+                y: 2
+            }
+        );
+
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(2), framed.next())
+            .await
+            .expect("no close frame received")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(reply, WSFrame::Close(1007, _)));
+        framed.send(reply).await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handler_closes_with_custom_code() {
+        use aex::http::websocket::HandlerAction;
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().on_text(|_ws, _ctx, text| {
+            Box::pin(async move {
+                if text == "bad-token" {
+                    HandlerAction::Close(4001, "unauthorized".into())
+                } else {
+                    HandlerAction::Continue
+                }
+            })
+        });
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        client
+            .write_all(&create_masked_frame(0x1, b"hello"))
+            .await
+            .unwrap();
+        client
+            .write_all(&create_masked_frame(0x1, b"bad-token"))
+            .await
+            .unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(2), framed.next())
+            .await
+            .expect("no close frame received")
+            .unwrap()
+            .unwrap();
+        assert_eq!(reply, WSFrame::Close(4001, Some("unauthorized".into())));
+        framed.send(reply).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        // bool 返回值仍可用：false 等价于 1000 正常关闭
+        assert_eq!(HandlerAction::from(true), HandlerAction::Continue);
+        assert_eq!(
+            HandlerAction::from(false),
+            HandlerAction::Close(1000, String::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_permessage_deflate_round_trip() {
+        use aex::http::websocket::{MessageDeflater, MessageInflater, PerMessageDeflate};
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new()
+            .permessage_deflate(PerMessageDeflate::default())
+            .on_text(|_ws, ctx, text| {
+                if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                    sender.send_text(format!("echo:{}", text));
+                }
+                Box::pin(async { true })
+            });
+
+        let (mut client, server) = duplex(4096);
+        let mut ctx = ws_ctx(server, global, addr);
+        // 握手阶段协商出的参数
+        ctx.local.set_value(PerMessageDeflate::default());
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        // 客户端发送两条保留上下文的压缩消息（RSV1）
+        let mut deflater = MessageDeflater::new(false);
+        for _ in 0..2 {
+            let payload = deflater.compress(b"hello hello hello").unwrap();
+            let mut frame = create_masked_frame(0x1, &payload);
+            frame[0] |= 0x40;
+            client.write_all(&frame).await.unwrap();
+        }
+
+        let mut framed = Framed::new(client, WSCodec);
+        let mut inflater = MessageInflater::new(false);
+        let mut sizes = Vec::new();
+        for _ in 0..2 {
+            let reply = framed.next().await.unwrap().unwrap();
+            let WSFrame::Compressed(0x1, data) = reply else {
+                panic!("expected compressed text, got {:?}", reply);
+            };
+            sizes.push(data.len());
+            let text = inflater.decompress(&data, 1024).unwrap();
+            assert_eq!(text, b"echo:hello hello hello");
+        }
+        // 服务端保留了压缩窗口
+        assert!(sizes[1] < sizes[0]);
+
+        framed.send(WSFrame::Close(1000, None)).await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rsv1_without_deflate_is_rejected() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new();
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        let mut frame = create_masked_frame(0x1, b"hi");
+        frame[0] |= 0x40;
+        client.write_all(&frame).await.unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        let reply = framed.next().await.unwrap().unwrap();
+        assert!(matches!(reply, WSFrame::Close(1002, _)));
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_large_binary_sent_fragmented() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().fragment_size(4).on_text(|_ws, ctx, text| {
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                // 未超过阈值的消息仍是单帧
+                sender.send_text("ok");
+                sender.send_binary(text.into_bytes());
+            }
+            Box::pin(async { true })
+        });
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        client
+            .write_all(&create_masked_frame(0x1, b"0123456789"))
+            .await
+            .unwrap();
+
+        // 服务端帧不带掩码且都小于 126 字节：逐帧读取 FIN、opcode 与负载
+        let mut frames = Vec::new();
+        let mut message = Vec::new();
+        loop {
+            let mut head = [0u8; 2];
+            client.read_exact(&mut head).await.unwrap();
+            let mut payload = vec![0u8; (head[1] & 0x7f) as usize];
+            client.read_exact(&mut payload).await.unwrap();
+            let (fin, opcode) = (head[0] & 0x80 != 0, head[0] & 0x0f);
+            frames.push((fin, opcode, payload.len()));
+            if opcode != 0x1 {
+                message.extend_from_slice(&payload);
+            }
+            if fin && opcode != 0x1 {
+                break;
+            }
+        }
+
+        assert_eq!(
+            frames,
+            vec![
+                (true, 0x1, 2),
+                (false, 0x2, 4),
+                (false, 0x0, 4),
+                (true, 0x0, 2)
+            ]
+        );
+        assert_eq!(message, b"0123456789");
+    }
+
+    /// 处理器收到 "bye" 时主动关闭，但保持读循环继续
+    fn closing_ws(timeout: std::time::Duration) -> WebSocket {
+        WebSocket::new()
+            .close_timeout(timeout)
+            .on_text(|_ws, ctx, text| {
+                if text == "bye" {
+                    WebSocketSender::from_ctx(ctx)
+                        .unwrap()
+                        .close(1000, Some("bye"));
+                }
+                Box::pin(async { true })
+            })
+    }
+
+    #[tokio::test]
+    async fn test_close_handshake_echoed() {
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = closing_ws(std::time::Duration::from_secs(5));
+
+        let (client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        let mut framed = Framed::new(client, WSCodec);
+        framed.send(WSFrame::Text("bye".into())).await.unwrap();
+        let reply = framed.next().await.unwrap().unwrap();
+        assert_eq!(reply, WSFrame::Close(1000, Some("bye".into())));
+
+        // 关闭期间的数据帧被丢弃，收到回显后立即结束
+        let started = std::time::Instant::now();
+        framed.send(WSFrame::Text("late".into())).await.unwrap();
+        framed.send(WSFrame::Close(1000, None)).await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(1), handle)
+            .await
+            .expect("server did not finish after close echo")
+            .unwrap()
+            .unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+
+        // 服务端已断开
+        assert!(framed.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_handshake_times_out() {
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = closing_ws(std::time::Duration::from_millis(100));
+
+        let (client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        let mut framed = Framed::new(client, WSCodec);
+        framed.send(WSFrame::Text("bye".into())).await.unwrap();
+        let started = std::time::Instant::now();
+        assert!(matches!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Close(1000, _)
+        ));
+
+        // 客户端不回显，超时后服务端断开
+        tokio::time::timeout(std::time::Duration::from_secs(2), handle)
+            .await
+            .expect("server did not time out the close handshake")
+            .unwrap()
+            .unwrap();
+        assert!(started.elapsed() >= std::time::Duration::from_millis(90));
+        assert!(framed.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_client_initiated_close_echoed() {
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+
+        for (sent, echoed) in [
+            (WSFrame::Close(1001, Some("going away".into())), 1001),
+            (WSFrame::Close(4000, None), 4000),
+        ] {
+            let ws = WebSocket::new();
+            let (client, server) = duplex(1024);
+            let mut ctx = ws_ctx(server, global.clone(), addr);
+            let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+            // 客户端先发起关闭，服务端须回显关闭帧后断开
+            let mut framed = Framed::new(client, WSCodec);
+            framed.send(sent).await.unwrap();
+            let reply = tokio::time::timeout(std::time::Duration::from_secs(2), framed.next())
+                .await
+                .expect("no close echo received")
+                .unwrap()
+                .unwrap();
+            assert_eq!(reply, WSFrame::Close(echoed, None));
+            handle.await.unwrap().unwrap();
+            assert!(framed.next().await.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lone_continuation_closes_with_1002() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new();
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        // 孤立的连续帧：没有进行中的分片消息
+        client
+            .write_all(&create_masked_frame(0x0, b"orphan"))
+            .await
+            .unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        let reply = framed.next().await.unwrap().unwrap();
+        assert!(matches!(reply, WSFrame::Close(1002, _)));
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fragmented_text_is_reassembled() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().on_text(|_ws, ctx, text| {
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                sender.send_text(text);
+            }
+            Box::pin(async { true })
+        });
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        // "你好" 的 UTF-8 字节跨分片边界，中间穿插一个 Ping
+        let text = "你好".as_bytes();
+        let mut first = create_masked_frame(0x1, &text[..2]);
+        first[0] &= 0x7f;
+        let mut middle = create_masked_frame(0x0, &text[2..4]);
+        middle[0] &= 0x7f;
+        for frame in [
+            first,
+            create_masked_frame(0x9, b"p"),
+            middle,
+            create_masked_frame(0x0, &text[4..]),
+        ] {
+            client.write_all(&frame).await.unwrap();
+        }
+
+        let mut framed = Framed::new(client, WSCodec);
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Pong(b"p".to_vec())
+        );
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Text("你好".into())
+        );
+
+        framed.send(WSFrame::Close(1000, None)).await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connection_state_persists_across_messages() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new()
+            .with_state(|_ctx| 0usize)
+            .on_text_with_state(|_ws, ctx, count: &mut usize, text| {
+                *count += 1;
+                if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                    sender.send_text(format!("{}:{}", count, text));
+                }
+                Box::pin(async { true })
+            });
+
+        // 每个连接各自计数
+        for _ in 0..2 {
+            let ws = ws.clone();
+            let (mut client, server) = duplex(1024);
+            let mut ctx = ws_ctx(server, global.clone(), addr);
+            let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+            for text in ["a", "b", "c"] {
+                client
+                    .write_all(&create_masked_frame(0x1, text.as_bytes()))
+                    .await
+                    .unwrap();
+            }
+
+            let mut framed = Framed::new(client, WSCodec);
+            for expected in ["1:a", "2:b", "3:c"] {
+                assert_eq!(
+                    framed.next().await.unwrap().unwrap(),
+                    WSFrame::Text(expected.into())
+                );
+            }
+
+            framed.send(WSFrame::Close(1000, None)).await.unwrap();
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queued_frames_flushed_in_batches() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::task::Poll;
+        use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+        /// 转发写入并统计 flush 次数
+        struct FlushCounter<W> {
+            inner: W,
+            flushes: Arc<AtomicUsize>,
+        }
+
+        impl<W: AsyncWrite + Unpin> AsyncWrite for FlushCounter<W> {
+            fn poll_write(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                std::pin::Pin::new(&mut self.inner).poll_write(cx, buf)
+            }
+
+            fn poll_flush(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                self.flushes.fetch_add(1, Ordering::SeqCst);
+                std::pin::Pin::new(&mut self.inner).poll_flush(cx)
+            }
+
+            fn poll_shutdown(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                std::pin::Pin::new(&mut self.inner).poll_shutdown(cx)
+            }
+        }
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new().on_text(|_ws, ctx, _text| {
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                for i in 0..10 {
+                    sender.send_text(i.to_string());
+                }
+            }
+            Box::pin(async { true })
+        });
+
+        let (mut client, server) = duplex(4096);
+        let (s_reader, s_writer) = tokio::io::split(server);
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let writer = FlushCounter {
+            inner: s_writer,
+            flushes: flushes.clone(),
+        };
+        let mut ctx = Context::new(
+            Some(Box::new(BufReader::new(s_reader))),
+            Some(Box::new(writer)),
+            global,
+            addr,
+        );
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        client
+            .write_all(&create_masked_frame(0x1, b"go"))
+            .await
+            .unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        for i in 0..10 {
+            assert_eq!(
+                framed.next().await.unwrap().unwrap(),
+                WSFrame::Text(i.to_string())
+            );
+        }
+        // 同一批排队的 10 帧合并写出，而不是逐帧 flush
+        assert!(flushes.load(Ordering::SeqCst) < 10);
+
+        framed.send(WSFrame::Close(1000, None)).await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auto_pong_echoes_ping_payload() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        let ws = WebSocket::new();
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        // 延迟测量类的 Ping 携带时间戳，最大 125 字节
+        let stamp = 1_700_000_000_123u64.to_be_bytes();
+        client
+            .write_all(&create_masked_frame(0x9, &stamp))
+            .await
+            .unwrap();
+        let max = vec![0x5a; 125];
+        client
+            .write_all(&create_masked_frame(0x9, &max))
+            .await
+            .unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Pong(stamp.to_vec())
+        );
+        assert_eq!(framed.next().await.unwrap().unwrap(), WSFrame::Pong(max));
+
+        // 超过 125 字节的控制帧是协议错误
+        framed
+            .get_mut()
+            .write_all(&create_masked_frame(0x9, &[0u8; 126]))
+            .await
+            .unwrap();
+        let reply = framed.next().await.unwrap().unwrap();
+        assert!(matches!(reply, WSFrame::Close(1002, _)));
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_validates_key_length() {
+        use aex::{
+            exe,
+            http::{
+                router::{NodeType, Router},
+                types::Executor,
+            },
+            server::HTTPServer,
+        };
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        assert!(WebSocket::is_valid_key("dGhlIHNhbXBsZSBub25jZQ=="));
+        assert!(!WebSocket::is_valid_key("c2hvcnQ="));
+        assert!(!WebSocket::is_valid_key("not base64!!"));
+
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mw: Arc<Executor> = Arc::from(WebSocket::to_middleware(WebSocket::new()));
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/ws", exe!(|_ctx| { true }))
+            .middleware(mw)
+            .register();
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let status_line = |key: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let req = format!(
+                "GET /ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                 Connection: Upgrade\r\nSec-WebSocket-Key: {}\r\n\
+                 Sec-WebSocket-Version: 13\r\n\r\n",
+                key
+            );
+            stream.write_all(req.as_bytes()).await.unwrap();
+            let mut buf = vec![0u8; 256];
+            let n = stream.read(&mut buf).await.unwrap();
+            let text = String::from_utf8_lossy(&buf[..n]).to_string();
+            text.lines().next().unwrap_or("").to_string()
+        };
+
+        assert_eq!(
+            status_line("dGhlIHNhbXBsZSBub25jZQ==").await,
+            "HTTP/1.1 101 Switching Protocols"
+        );
+        for key in ["c2hvcnQ=", "not base64!!", "dGhlIHNhbXBsZSBub25jZQ==dGhl"] {
+            assert_eq!(
+                status_line(key).await,
+                "HTTP/1.1 400 Bad Request",
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extension_offers_exposed_after_handshake() {
+        use aex::http::websocket::WSExtensionOffers;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let offers = WSExtensionOffers::parse(
+            "permessage-deflate; client_max_window_bits; server_max_window_bits=\"10\", \
+             x-webkit-deflate-frame, , Foo;Bar=1 ;baz",
+        );
+        let owned = |k: &str, v: Option<&str>| (k.to_string(), v.map(str::to_string));
+        assert_eq!(
+            offers.0,
+            vec![
+                (
+                    "permessage-deflate".to_string(),
+                    vec![
+                        owned("client_max_window_bits", None),
+                        owned("server_max_window_bits", Some("10")),
+                    ]
+                ),
+                ("x-webkit-deflate-frame".to_string(), vec![]),
+                (
+                    "foo".to_string(),
+                    vec![owned("bar", Some("1")), owned("baz", None)]
+                ),
+            ]
+        );
+        assert!(offers.contains("FOO"));
+        assert!(!offers.contains("bar"));
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None));
+        // 处理器在连接期间读取扩展提议并回显名称
+        let ws = WebSocket::new().on_text(|_ws, ctx, _text| {
+            let names: Vec<_> = WebSocket::extensions(ctx)
+                .map(|offers| offers.0.iter().map(|(n, _)| n.clone()).collect())
+                .unwrap_or_default();
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                sender.send_text(names.join(","));
+            }
+            Box::pin(async { true })
+        });
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let mut meta = upgrade_meta(None);
+        meta.headers.insert(
+            HeaderKey::SecWebSocketExtensions,
+            "permessage-deflate; client_max_window_bits, x-custom; mode=fast",
+        );
+        ctx.local.set_value(meta);
+
+        let mw = WebSocket::to_middleware(ws);
+        let handle = tokio::spawn(async move {
+            mw(&mut ctx).await;
+            ctx
+        });
+
+        // 未启用压缩：101 响应中不应答任何扩展
+        let mut buf = vec![0u8; 512];
+        let n = client.read(&mut buf).await.unwrap();
+        let head = String::from_utf8_lossy(&buf[..n]).to_string();
+        assert!(head.starts_with("HTTP/1.1 101"));
+        assert!(!head.contains("Sec-WebSocket-Extensions"));
+
+        client
+            .write_all(&create_masked_frame(0x1, b"hi"))
+            .await
+            .unwrap();
+        let mut framed = Framed::new(client, WSCodec);
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Text("permessage-deflate,x-custom".into())
+        );
+        framed.send(WSFrame::Close(1000, None)).await.unwrap();
+
+        let ctx = handle.await.unwrap();
+        let offers = WebSocket::extensions(&ctx).unwrap();
+        assert_eq!(
+            offers.get("x-custom"),
+            Some(&[owned("mode", Some("fast"))][..])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oversized_single_frame_closes_with_1009() {
+        use tokio::io::AsyncWriteExt;
+
+        let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+        let global = Arc::new(GlobalContext::new(addr, None).with_max_body_size(64));
+        let ws = WebSocket::new();
+
+        let (mut client, server) = duplex(1024);
+        let mut ctx = ws_ctx(server, global, addr);
+        let handle = tokio::spawn(async move { WebSocket::run(&ws, &mut ctx).await });
+
+        // 未分片的二进制帧声明 1 MiB 负载，只发送帧头
+        let mut head = vec![0x82, 0xff];
+        head.extend_from_slice(&(1u64 << 20).to_be_bytes());
+        head.extend_from_slice(&[1, 2, 3, 4]);
+        client.write_all(&head).await.unwrap();
+
+        let mut framed = Framed::new(client, WSCodec);
+        assert_eq!(
+            framed.next().await.unwrap().unwrap(),
+            WSFrame::Close(1009, Some("Message too big".into()))
+        );
+        assert!(handle.await.unwrap().is_err());
+    }
 }
@@ -28,7 +28,7 @@ mod tests {
         use aex::http::router::NodeType;
 
         let static_node = NodeType::Static("test".to_string());
-        let param_node = NodeType::Param("id".to_string());
+        let param_node = NodeType::Param("id".to_string(), None);
         let wildcard_node = NodeType::Wildcard;
 
         assert!(static_node.is_static());
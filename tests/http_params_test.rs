@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use aex::http::params::Params;
+    use aex::http::params::{Params, split_url};
     #[test]
     fn test_new_params_with_query() {
         let url = "https://example.com/search?q=rust&tags=programming&tags=backend".to_string();
@@ -58,4 +58,85 @@ mod tests {
         assert_eq!(parsed.get("key1").unwrap()[0], "");
         assert_eq!(parsed.get("key2").unwrap()[0], "");
     }
+
+    #[test]
+    fn test_parse_pairs_edge_cases() {
+        let parsed = Params::parse_pairs("flag&a=&b=1=2&&c=x&c=");
+
+        // 裸键存在且值为空
+        assert_eq!(parsed.get("flag").unwrap(), &vec![String::new()]);
+        assert_eq!(parsed.get("a").unwrap(), &vec![String::new()]);
+        // 只按第一个 '=' 切分
+        assert_eq!(parsed.get("b").unwrap(), &vec!["1=2".to_string()]);
+        // 重复键保留顺序，空片段被忽略
+        assert_eq!(
+            parsed.get("c").unwrap(),
+            &vec!["x".to_string(), String::new()]
+        );
+        assert_eq!(parsed.len(), 4);
+        assert!(!parsed.contains_key(""));
+
+        // 查询串走同一套规则
+        let params = Params::new("/search?flag&q=a=b".to_string());
+        assert_eq!(params.query.get("flag").unwrap()[0], "");
+        assert_eq!(params.query_as::<String>("q").as_deref(), Some("a=b"));
+    }
+
+    #[test]
+    fn test_typed_param() {
+        let mut params = Params::new("/users/42/posts/abc".to_string());
+        // 未匹配任何路径参数
+        assert_eq!(params.param::<u64>("id"), None);
+
+        let mut data = ahash::AHashMap::new();
+        data.insert("id".to_string(), "42".to_string());
+        data.insert("slug".to_string(), "abc".to_string());
+        params.data = Some(data);
+
+        assert_eq!(params.param::<u64>("id"), Some(42));
+        assert_eq!(params.param::<String>("slug"), Some("abc".to_string()));
+        // 缺失
+        assert_eq!(params.param::<u64>("missing"), None);
+        // 无法解析
+        assert_eq!(params.param::<u64>("slug"), None);
+    }
+
+    #[test]
+    fn test_typed_query() {
+        let params = Params::new("/list?page=2&id=1&id=x&id=3&flag=true".to_string());
+
+        assert_eq!(params.query_as::<usize>("page"), Some(2));
+        assert_eq!(params.query_as::<bool>("flag"), Some(true));
+        // 重复参数取第一个
+        assert_eq!(params.query_as::<u32>("id"), Some(1));
+        assert_eq!(params.query_vec::<u32>("id"), vec![1, 3]);
+        assert_eq!(params.query_vec::<String>("id").len(), 3);
+
+        // 缺失与解析失败
+        assert_eq!(params.query_as::<usize>("missing"), None);
+        assert_eq!(params.query_as::<usize>("flag"), None);
+        assert!(params.query_vec::<u32>("missing").is_empty());
+        assert!(params.query_vec::<u32>("flag").is_empty());
+    }
+
+    #[test]
+    fn test_split_url_strips_fragment() {
+        assert_eq!(split_url("/docs#intro"), ("/docs", ""));
+        assert_eq!(split_url("/docs?page=2#intro"), ("/docs", "page=2"));
+        assert_eq!(split_url("/docs#a?b=1"), ("/docs", ""));
+
+        let params = Params::new("/docs?page=2#intro".to_string());
+        assert_eq!(params.query_as::<u32>("page"), Some(2));
+    }
+
+    #[test]
+    fn test_split_url_only_first_question_mark() {
+        assert_eq!(split_url("/search?q=a?b&x=1"), ("/search", "q=a?b&x=1"));
+        assert_eq!(split_url("/search??q=1"), ("/search", "?q=1"));
+        assert_eq!(split_url("/plain"), ("/plain", ""));
+
+        let params = Params::new("/search?q=a?b&x=1".to_string());
+        assert_eq!(params.query.get("q").unwrap(), &vec!["a?b".to_string()]);
+        assert_eq!(params.query_as::<u32>("x"), Some(1));
+    }
 }
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use aex::http::protocol::range::{ByteRange, RangeRequest};
+
+    fn partial(start: u64, end: u64) -> RangeRequest {
+        RangeRequest::Partial(ByteRange { start, end })
+    }
+
+    #[test]
+    fn test_parse_explicit_and_open_ranges() {
+        assert_eq!(RangeRequest::parse(Some("bytes=0-99"), 100), partial(0, 99));
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=10-19"), 100),
+            partial(10, 19)
+        );
+        assert_eq!(RangeRequest::parse(Some("bytes=90-"), 100), partial(90, 99));
+        // 结束位置超出时截断到末尾
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=50-500"), 100),
+            partial(50, 99)
+        );
+    }
+
+    #[test]
+    fn test_parse_suffix_range() {
+        assert_eq!(RangeRequest::parse(Some("bytes=-10"), 100), partial(90, 99));
+        assert_eq!(RangeRequest::parse(Some("bytes=-500"), 100), partial(0, 99));
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=-0"), 100),
+            RangeRequest::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn test_parse_out_of_bounds() {
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=100-"), 100),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            RangeRequest::parse(Some("bytes=200-300"), 100),
+            RangeRequest::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn test_parse_ignored_headers() {
+        for header in [
+            None,
+            Some("items=0-1"),
+            Some("bytes=5-1"),
+            Some("bytes=0-1,4-5"),
+        ] {
+            assert_eq!(RangeRequest::parse(header, 100), RangeRequest::Full);
+        }
+        let r = ByteRange { start: 10, end: 19 };
+        assert_eq!(r.len(), 10);
+        assert_eq!(r.content_range(100), "bytes 10-19/100");
+    }
+}
@@ -2,7 +2,7 @@
 mod tests {
     use aex::connection::context::{BoxReader, TypeMapExt};
     use aex::http::params::Params;
-    use aex::http::protocol::method::HttpMethod;
+    use aex::http::protocol::{header::HeaderKey, method::HttpMethod};
     use aex::{
         connection::context::LocalTypeMap,
         http::{meta::HttpMetadata, req::Request},
@@ -116,6 +116,32 @@ mod tests {
         assert!(req.query("none").is_none());
     }
 
+    #[tokio::test]
+    async fn test_typed_header_access() {
+        let mut local = LocalTypeMap::new();
+        let input = b"GET / HTTP/1.1\r\n\
+                      Content-Length: 42 \r\n\
+                      X-Retry-Count: 3\r\n\
+                      X-Ratio: abc\r\n\
+                      \r\n";
+
+        let reader = BufReader::new(Cursor::new(input));
+        let mut reader: Option<BoxReader> = Some(Box::new(reader));
+        let mut req = Request::new(&mut reader, &mut local);
+        req.parse_to_local().await.unwrap();
+
+        assert_eq!(req.header_as::<usize>(&HeaderKey::ContentLength), Some(42));
+        assert_eq!(req.content_length(), 42);
+        let retry = HeaderKey::from("X-Retry-Count");
+        assert_eq!(req.header(&retry), Some("3"));
+        assert_eq!(req.header_as::<u32>(&retry), Some(3));
+
+        // 缺失或无法解析时为 None
+        assert_eq!(req.header(&HeaderKey::Authorization), None);
+        assert_eq!(req.header_as::<u32>(&HeaderKey::Authorization), None);
+        assert_eq!(req.header_as::<f64>(&HeaderKey::from("X-Ratio")), None);
+    }
+
     #[tokio::test]
     async fn test_read_line_limit_exceeded() {
         let mut local = LocalTypeMap::new();
@@ -129,4 +155,259 @@ mod tests {
         // 虽然代码里没直接检查长度，但 read_until 内部 buf 会增长。
         // 这里可以通过 Mock 来模拟超时。
     }
+
+    #[tokio::test]
+    async fn test_body_helpers() {
+        let mut local = LocalTypeMap::new();
+        let mut meta = HttpMetadata::new();
+        meta.params = Some(Params::new("/submit".to_string()));
+        local.set_value(meta);
+
+        let input = b"name=bob&tag=a&tag=b";
+        let mut reader: Option<BoxReader> =
+            Some(Box::new(BufReader::new(Cursor::new(input.to_vec()))));
+        let mut req = Request::new(&mut reader, &mut local);
+
+        // 读取前为空
+        assert!(req.bytes().is_empty());
+        assert_eq!(req.text().unwrap(), "");
+
+        local_body(&mut req, input.len()).await;
+        assert_eq!(req.bytes(), input);
+        assert_eq!(req.text().unwrap(), "name=bob&tag=a&tag=b");
+
+        let form = req.form_map();
+        assert_eq!(form.get("name").unwrap(), &vec!["bob".to_string()]);
+        assert_eq!(
+            form.get("tag").unwrap(),
+            &vec!["a".to_string(), "b".to_string()]
+        );
+        // 解析结果回写到 params，单值 getter 可直接使用
+        assert_eq!(req.form("name").unwrap(), "bob");
+    }
+
+    #[tokio::test]
+    async fn test_text_rejects_invalid_utf8() {
+        let mut local = LocalTypeMap::new();
+        local.set_value(HttpMetadata::new());
+        let input = [0xffu8, 0xfe];
+        let mut reader: Option<BoxReader> =
+            Some(Box::new(BufReader::new(Cursor::new(input.to_vec()))));
+        let mut req = Request::new(&mut reader, &mut local);
+
+        local_body(&mut req, input.len()).await;
+        assert_eq!(req.bytes(), &input);
+        assert!(req.text().is_err());
+    }
+
+    async fn local_body(req: &mut Request<'_>, len: usize) {
+        let meta = req.local.get_mut::<HttpMetadata>().unwrap();
+        meta.headers
+            .insert(HeaderKey::ContentLength, len.to_string());
+        req.read_body().await.unwrap();
+    }
+
+    fn with_accept(local: &mut LocalTypeMap, accept: Option<&str>) {
+        let mut meta = HttpMetadata::new();
+        if let Some(accept) = accept {
+            meta.headers.insert(HeaderKey::Accept, accept);
+        }
+        local.set_value(meta);
+    }
+
+    #[test]
+    fn test_accept_negotiation() {
+        use aex::http::protocol::{
+            content_type::ContentType,
+            media_type::{MediaType, SubMediaType},
+        };
+
+        let json = ContentType::parse("application/json");
+        let html = ContentType::parse("text/html");
+        let plain = ContentType::parse("text/plain");
+
+        let mut local = LocalTypeMap::new();
+        let mut reader: Option<BoxReader> = None;
+        with_accept(&mut local, Some("application/json;q=0.9, text/html"));
+        let req = Request::new(&mut reader, &mut local);
+
+        assert!(req.accepts(MediaType::Application, SubMediaType::Json));
+        assert!(req.accepts(MediaType::Text, SubMediaType::Html));
+        assert!(!req.accepts(MediaType::Text, SubMediaType::Plain));
+        // html 权重更高
+        assert_eq!(
+            req.preferred(&[json.clone(), html.clone()]),
+            Some(html.clone())
+        );
+        // 只有 json 可选时退而求其次
+        assert_eq!(
+            req.preferred(&[plain.clone(), json.clone()]),
+            Some(json.clone())
+        );
+        assert_eq!(req.preferred(&[plain]), None);
+
+        // 通配符与 q=0 排除
+        let mut local = LocalTypeMap::new();
+        with_accept(&mut local, Some("text/*;q=0.5, */*;q=0.1, text/plain;q=0"));
+        let req = Request::new(&mut reader, &mut local);
+        assert!(req.accepts(MediaType::Text, SubMediaType::Html));
+        assert!(!req.accepts(MediaType::Text, SubMediaType::Plain));
+        assert_eq!(
+            req.preferred(&[json.clone(), html.clone()]),
+            Some(html.clone())
+        );
+
+        // 没有 Accept 头：全部接受，取第一个候选
+        let mut local = LocalTypeMap::new();
+        with_accept(&mut local, None);
+        let req = Request::new(&mut reader, &mut local);
+        assert!(req.accepts(MediaType::Image, SubMediaType::Png));
+        assert_eq!(req.preferred(&[json.clone(), html]), Some(json));
+    }
+
+    #[test]
+    fn test_real_ip() {
+        use aex::http::req::real_ip;
+        use ipnet::IpNet;
+        use std::net::{IpAddr, SocketAddr};
+
+        let trusted: Vec<IpNet> = vec!["10.0.0.0/8".parse().unwrap()];
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let headers = |key: HeaderKey, value: &str| {
+            let mut meta = HttpMetadata::new();
+            meta.headers.insert(key, value);
+            meta.headers
+        };
+
+        // 不可信对端伪造的 XFF 被忽略
+        let spoofed = headers(HeaderKey::XForwardedFor, "1.1.1.1");
+        assert_eq!(
+            real_ip(ip("203.0.113.7"), &spoofed, &trusted),
+            ip("203.0.113.7")
+        );
+
+        // 可信代理：从右往左取第一个不可信地址，客户端自带的前缀不被采信
+        let xff = headers(HeaderKey::XForwardedFor, "1.1.1.1, 198.51.100.2, 10.0.0.3");
+        assert_eq!(real_ip(ip("10.0.0.1"), &xff, &trusted), ip("198.51.100.2"));
+
+        // 全部是可信代理时取最左侧
+        let internal = headers(HeaderKey::XForwardedFor, "10.1.1.1, 10.0.0.3");
+        assert_eq!(real_ip(ip("10.0.0.1"), &internal, &trusted), ip("10.1.1.1"));
+
+        let fwd = headers(
+            HeaderKey::Forwarded,
+            "for=192.0.2.43, for=\"[2001:db8:cafe::17]:4711\";proto=https",
+        );
+        assert_eq!(
+            real_ip(ip("10.0.0.1"), &fwd, &trusted),
+            ip("2001:db8:cafe::17")
+        );
+
+        let unknown = headers(HeaderKey::Forwarded, "for=unknown");
+        assert_eq!(real_ip(ip("10.0.0.1"), &unknown, &trusted), ip("10.0.0.1"));
+
+        // Request::real_ip 使用 Context 填入的对端地址
+        let mut local = LocalTypeMap::new();
+        let mut meta = HttpMetadata::new();
+        meta.headers
+            .insert(HeaderKey::XForwardedFor, "198.51.100.9");
+        local.set_value(meta);
+        let mut reader: Option<BoxReader> = None;
+        let peer: SocketAddr = "10.0.0.1:8080".parse().unwrap();
+        let req = Request::new(&mut reader, &mut local).with_peer_addr(peer);
+        assert_eq!(req.real_ip(&trusted), ip("198.51.100.9"));
+        assert_eq!(req.real_ip(&[]), ip("10.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_headers_retained() {
+        let mut local = LocalTypeMap::new();
+        let input = b"GET / HTTP/1.1\r\n\
+                      X-Foo: a\r\n\
+                      x-foo: b, \"c,d\"\r\n\
+                      Cookie: user=alice\r\n\
+                      Cookie: theme=dark\r\n\
+                      Content-Length: 0\r\n\
+                      Content-Length: 0\r\n\
+                      \r\n";
+        let mut reader: Option<BoxReader> = Some(Box::new(BufReader::new(Cursor::new(input))));
+        Request::new(&mut reader, &mut local)
+            .parse_to_local()
+            .await
+            .unwrap();
+
+        let meta = local.get_value::<HttpMetadata>().unwrap();
+        let foo = HeaderKey::from("X-Foo");
+        assert_eq!(meta.headers.get(&foo).unwrap(), "a, b, \"c,d\"");
+        assert_eq!(meta.headers.get_all(&foo), vec!["a", "b", "\"c,d\""]);
+        assert!(meta.headers.get_all(&HeaderKey::Accept).is_empty());
+        // Cookie 以分号拼接，两行的值都能解析到
+        assert_eq!(meta.cookies.get("user").unwrap(), "alice");
+        assert_eq!(meta.cookies.get("theme").unwrap(), "dark");
+        assert_eq!(meta.headers.get(&HeaderKey::ContentLength).unwrap(), "0");
+
+        // 不一致的 Content-Length 直接拒绝
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\n";
+        let mut reader: Option<BoxReader> = Some(Box::new(BufReader::new(Cursor::new(input))));
+        let mut local = LocalTypeMap::new();
+        assert!(
+            Request::new(&mut reader, &mut local)
+                .parse_to_local()
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_body_chunked_uses_configured_limit() {
+        use aex::connection::{context::Context, global::GlobalContext};
+        use aex::http::req::BodyTooLarge;
+
+        let chunked = |body: &'static [u8]| {
+            let reader: BoxReader = Box::new(BufReader::new(Cursor::new(body)));
+            let addr = "127.0.0.1:0".parse().unwrap();
+            let global = GlobalContext::new(addr, None).with_max_body_size(8);
+            let mut ctx = Context::new(Some(reader), None, Arc::new(global), addr);
+            let mut meta = HttpMetadata::new();
+            meta.is_chunked = true;
+            ctx.local.set_value(meta);
+            ctx
+        };
+
+        // 上限来自 GlobalContext::max_body_size，而不是 MAX_BODY_SIZE
+        let mut ctx = chunked(b"8\r\n12345678\r\n0\r\n\r\n");
+        assert_eq!(ctx.req().read_body().await.unwrap(), b"12345678");
+
+        let mut ctx = chunked(b"9\r\n123456789\r\n0\r\n\r\n");
+        let err = ctx.req().read_body().await.unwrap_err();
+        assert!(err.is::<BodyTooLarge>());
+    }
+
+    #[tokio::test]
+    async fn test_read_body_rejects_declared_length_over_limit() {
+        use aex::connection::{context::Context, global::GlobalContext};
+        use aex::http::req::BodyTooLarge;
+
+        let sized = |length: &str| {
+            let reader: BoxReader = Box::new(BufReader::new(Cursor::new(&b"12345678"[..])));
+            let addr = "127.0.0.1:0".parse().unwrap();
+            let global = GlobalContext::new(addr, None).with_max_body_size(8);
+            let mut ctx = Context::new(Some(reader), None, Arc::new(global), addr);
+            let mut meta = HttpMetadata::new();
+            meta.headers.insert(HeaderKey::ContentLength, length);
+            ctx.local.set_value(meta);
+            ctx
+        };
+
+        let mut ctx = sized("8");
+        assert_eq!(ctx.req().read_body().await.unwrap(), b"12345678");
+
+        // 声明的长度超出上限时不分配缓冲区，直接失败
+        let mut ctx = sized("99999999999");
+        let err = ctx.req().read_body().await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<BodyTooLarge>(),
+            Some(&BodyTooLarge { limit: 8 })
+        );
+    }
 }
@@ -154,4 +154,620 @@ mod tests {
 
     //     assert!(send_attempt.is_err(), "应该因为锁被占用而超时");
     // }
+
+    #[tokio::test]
+    async fn test_stream_chunked_response() {
+        use aex::{
+            exe,
+            http::router::{NodeType, Router},
+            server::HTTPServer,
+        };
+        use std::{net::SocketAddr, time::Duration};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/stream",
+            exe!(|ctx| {
+                let Ok(mut w) = ctx.res().stream().await else {
+                    return false;
+                };
+                for chunk in ["alpha,", "beta,", "gamma"] {
+                    if w.send(chunk).await.is_err() {
+                        return false;
+                    }
+                }
+                w.finish().await.is_ok()
+            }),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // 客户端按分块重新组装
+        let res = reqwest::get(format!("http://{}/stream", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.headers().get("transfer-encoding").unwrap(), "chunked");
+        assert_eq!(res.text().await.unwrap(), "alpha,beta,gamma");
+
+        // 原始报文：三个分块 + 结束块，且没有追加常规响应
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(b"GET /stream HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut raw = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut raw))
+            .await
+            .unwrap()
+            .unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(!raw.contains("Content-Length"));
+        assert!(raw.ends_with("\r\n\r\n6\r\nalpha,\r\n5\r\nbeta,\r\n5\r\ngamma\r\n0\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_write_chunk_and_finish() {
+        use aex::{
+            exe,
+            http::router::{NodeType, Router},
+            server::HTTPServer,
+        };
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/proxy",
+            exe!(|ctx| {
+                ctx.res().set_header("X-Upstream", "test");
+                // 每段数据都通过新的 `ctx.res()` 写出，模拟边读上游边转发
+                for part in [&b"first;"[..], b"", b"second;", b"third"] {
+                    if ctx.res().write_chunk(part).await.is_err() {
+                        return false;
+                    }
+                }
+                ctx.res().finish_chunked().await.is_ok()
+            }),
+        )
+        .register();
+        hr.get(
+            "/empty",
+            exe!(|ctx| { ctx.res().finish_chunked().await.is_ok() }),
+        )
+        .register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::get(format!("http://{}/proxy", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.headers().get("transfer-encoding").unwrap(), "chunked");
+        assert_eq!(res.headers().get("x-upstream").unwrap(), "test");
+        assert_eq!(res.text().await.unwrap(), "first;second;third");
+
+        let res = reqwest::get(format!("http://{}/empty", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "");
+
+        // 原始报文：空数据不产生结束块，最后只有一个结束块
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(b"GET /proxy HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut raw = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut raw))
+            .await
+            .unwrap()
+            .unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(!raw.contains("Content-Length"));
+        assert!(raw.ends_with("\r\n\r\n6\r\nfirst;\r\n7\r\nsecond;\r\n5\r\nthird\r\n0\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_finish_chunked_is_idempotent() {
+        use aex::connection::{context::Context, global::GlobalContext};
+        use aex::http::meta::HttpMetadata;
+        use std::{net::SocketAddr, sync::Arc};
+        use tokio::io::AsyncReadExt;
+
+        let ctx_with = |server: tokio::io::DuplexStream| {
+            let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+            let writer: Box<dyn tokio::io::AsyncWrite + Send + Sync + Unpin> = Box::new(server);
+            let mut ctx = Context::new(
+                None,
+                Some(writer),
+                Arc::new(GlobalContext::new(addr, None)),
+                addr,
+            );
+            ctx.local.set_value(HttpMetadata::new());
+            ctx
+        };
+
+        // 连续两次 finish_chunked，之后 write_chunk 报错
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut ctx = ctx_with(server);
+        ctx.res().write_chunk(b"data").await.unwrap();
+        ctx.res().finish_chunked().await.unwrap();
+        ctx.res().finish_chunked().await.unwrap();
+        assert!(ctx.res().write_chunk(b"late").await.is_err());
+        drop(ctx);
+        let mut raw = Vec::new();
+        client.read_to_end(&mut raw).await.unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(raw.ends_with("\r\n\r\n4\r\ndata\r\n0\r\n\r\n"));
+        assert_eq!(raw.matches("0\r\n\r\n").count(), 1);
+
+        // ChunkedWriter 已结束后再调用 finish_chunked 不再写出结束块
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut ctx = ctx_with(server);
+        let mut writer = ctx.res().stream().await.unwrap();
+        writer.send("data").await.unwrap();
+        writer.finish().await.unwrap();
+        ctx.res().finish_chunked().await.unwrap();
+        drop(ctx);
+        let mut raw = Vec::new();
+        client.read_to_end(&mut raw).await.unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert_eq!(raw.matches("0\r\n\r\n").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unfinished_chunked_response_is_terminated() {
+        use aex::{
+            exe,
+            http::router::{NodeType, Router},
+            server::HTTPServer,
+        };
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/stream",
+            exe!(|ctx| {
+                let Ok(mut w) = ctx.res().stream().await else {
+                    return false;
+                };
+                w.send("partial").await.is_ok()
+            }),
+        )
+        .register();
+        hr.get(
+            "/proxy",
+            exe!(|ctx| { ctx.res().write_chunk(b"partial").await.is_ok() }),
+        )
+        .register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // 处理器没有调用 finish：路由补写结束分块，同一连接上的下一个响应不受影响
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /stream HTTP/1.1\r\nHost: localhost\r\n\r\n\
+                  GET /proxy HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut raw = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut raw))
+            .await
+            .unwrap()
+            .unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        let responses: Vec<&str> = raw.split("HTTP/1.1 200 OK").skip(1).collect();
+        assert_eq!(responses.len(), 2, "{}", raw);
+        for response in responses {
+            assert!(
+                response.ends_with("\r\n\r\n7\r\npartial\r\n0\r\n\r\n"),
+                "{}",
+                raw
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunked_response_without_body() {
+        use aex::connection::{context::Context, global::GlobalContext};
+        use aex::http::protocol::method::HttpMethod;
+        use std::{net::SocketAddr, sync::Arc};
+        use tokio::io::AsyncReadExt;
+
+        let ctx_with = |server: tokio::io::DuplexStream, method: HttpMethod, status: StatusCode| {
+            let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+            let writer: Box<dyn tokio::io::AsyncWrite + Send + Sync + Unpin> = Box::new(server);
+            let mut ctx = Context::new(
+                None,
+                Some(writer),
+                Arc::new(GlobalContext::new(addr, None)),
+                addr,
+            );
+            let mut meta = HttpMetadata::new();
+            meta.method = method;
+            meta.status = status;
+            ctx.local.set_value(meta);
+            ctx
+        };
+
+        // HEAD：保留分块头部，但不写出任何分块
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut ctx = ctx_with(server, HttpMethod::HEAD, StatusCode::Ok);
+        let mut writer = ctx.res().stream().await.unwrap();
+        writer.send("data").await.unwrap();
+        writer.finish().await.unwrap();
+        ctx.res().send_response().await.unwrap();
+        drop(ctx);
+        let mut raw = Vec::new();
+        client.read_to_end(&mut raw).await.unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(raw.contains("Transfer-Encoding: chunked"), "{}", raw);
+        assert!(raw.ends_with("\r\n\r\n"), "{}", raw);
+        assert!(!raw.contains("data"), "{}", raw);
+        assert!(!raw.contains("0\r\n\r\n"), "{}", raw);
+
+        // 204 / 304：既没有 Transfer-Encoding 也没有分块
+        for status in [StatusCode::NoContent, StatusCode::NotModified] {
+            let (mut client, server) = tokio::io::duplex(1024);
+            let mut ctx = ctx_with(server, HttpMethod::GET, status);
+            ctx.res().write_chunk(b"data").await.unwrap();
+            ctx.res().send_response().await.unwrap();
+            drop(ctx);
+            let mut raw = Vec::new();
+            client.read_to_end(&mut raw).await.unwrap();
+            let raw = String::from_utf8_lossy(&raw);
+            assert!(!raw.contains("Transfer-Encoding"), "{}", raw);
+            assert!(raw.ends_with("\r\n\r\n"), "{}", raw);
+            assert!(!raw.contains("data"), "{}", raw);
+            assert!(!raw.contains("0\r\n\r\n"), "{}", raw);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_file_with_ranges() {
+        use aex::{
+            exe,
+            http::router::{NodeType, Router},
+            server::HTTPServer,
+        };
+        use std::{net::SocketAddr, time::Duration};
+
+        let path = std::env::temp_dir().join(format!("aex-range-{}.txt", std::process::id()));
+        std::fs::write(&path, b"0123456789abcdefghij").unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let file = path.clone();
+        hr.all(
+            "/file",
+            exe!(
+                move |ctx, file| { ctx.res().send_file(&*file).await.is_ok() },
+                |ctx| { file.clone() }
+            ),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let url = format!("http://{}/file", actual_addr);
+        let client = reqwest::Client::new();
+
+        // 完整内容
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.headers().get("accept-ranges").unwrap(), "bytes");
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/plain");
+        assert_eq!(res.text().await.unwrap(), "0123456789abcdefghij");
+
+        // 显式区间
+        let res = client
+            .get(&url)
+            .header("Range", "bytes=0-4")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 206);
+        assert_eq!(res.headers().get("content-range").unwrap(), "bytes 0-4/20");
+        assert_eq!(res.text().await.unwrap(), "01234");
+
+        // 后缀区间
+        let res = client
+            .get(&url)
+            .header("Range", "bytes=-5")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 206);
+        assert_eq!(
+            res.headers().get("content-range").unwrap(),
+            "bytes 15-19/20"
+        );
+        assert_eq!(res.text().await.unwrap(), "fghij");
+
+        // 越界区间
+        let res = client
+            .get(&url)
+            .header("Range", "bytes=20-30")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 416);
+        assert_eq!(res.headers().get("content-range").unwrap(), "bytes */20");
+
+        // HEAD 只返回头部
+        let res = client
+            .head(&url)
+            .header("Range", "bytes=0-4")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 206);
+        assert_eq!(res.headers().get("content-length").unwrap(), "5");
+        assert!(res.bytes().await.unwrap().is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_send_file_streams_large_range_with_handler_headers() {
+        use aex::{
+            exe,
+            http::router::{NodeType, Router},
+            server::HTTPServer,
+        };
+        use std::time::Duration;
+
+        // 比复制缓冲区大得多的文件，按块写出后内容仍需完整
+        let data: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("aex-large-{}.bin", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let file = path.clone();
+        hr.get(
+            "/download",
+            exe!(
+                move |ctx, file| {
+                    ctx.res()
+                        .set_header("Cache-Control", "max-age=60")
+                        .set_header("ETag", "\"v1\"")
+                        .set_header("Content-Disposition", "attachment; filename=\"a.bin\"");
+                    ctx.res().send_file(&*file).await.is_ok()
+                },
+                |ctx| { file.clone() }
+            ),
+        )
+        .register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::Client::new()
+            .get(format!("http://{}/download", actual_addr))
+            .header("Range", "bytes=1000-")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 206);
+        let headers = res.headers();
+        assert_eq!(headers.get("cache-control").unwrap(), "max-age=60");
+        assert_eq!(headers.get("etag").unwrap(), "\"v1\"");
+        assert_eq!(
+            headers.get("content-disposition").unwrap(),
+            "attachment; filename=\"a.bin\""
+        );
+        assert_eq!(headers.get("content-length").unwrap(), "299000");
+        assert_eq!(
+            headers.get("content-range").unwrap(),
+            "bytes 1000-299999/300000"
+        );
+        assert_eq!(res.bytes().await.unwrap().as_ref(), &data[1000..]);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_content_length_matches_body() {
+        use aex::{
+            exe,
+            http::router::{NodeType, Router},
+            server::HTTPServer,
+        };
+        use std::{net::SocketAddr, time::Duration};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        // 处理器写入了错误的长度
+        hr.get(
+            "/stale",
+            exe!(|ctx| {
+                ctx.send("hello world", None);
+                ctx.res().set_header(HeaderKey::ContentLength, "999");
+                true
+            }),
+        )
+        .register();
+        hr.get(
+            "/short",
+            exe!(|ctx| {
+                ctx.send("abc", None);
+                ctx.res().set_header(HeaderKey::ContentLength, "1");
+                true
+            }),
+        )
+        .register();
+        hr.get(
+            "/empty",
+            exe!(|ctx| {
+                ctx.send("ignored", None);
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    meta.status = StatusCode::NoContent;
+                }
+                true
+            }),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let raw = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+            let req = format!(
+                "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                path
+            );
+            stream.write_all(req.as_bytes()).await.unwrap();
+            let mut raw = Vec::new();
+            tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut raw))
+                .await
+                .expect("client waited for a body that never came")
+                .unwrap();
+            String::from_utf8(raw).unwrap()
+        };
+
+        for (path, body) in [("/stale", "hello world"), ("/short", "abc")] {
+            let resp = raw(path).await;
+            let lengths: Vec<&str> = resp
+                .lines()
+                .filter(|l| l.to_ascii_lowercase().starts_with("content-length:"))
+                .collect();
+            assert_eq!(lengths, vec![format!("Content-Length: {}", body.len())]);
+            assert!(resp.ends_with(&format!("\r\n\r\n{}", body)));
+        }
+
+        let resp = raw("/empty").await;
+        assert!(resp.starts_with("HTTP/1.1 204 No Content"));
+        assert!(!resp.contains("Content-Length"));
+        assert!(resp.ends_with("\r\n\r\n"));
+    }
+
+    /// 统计 poll_write / poll_flush 次数的写入器
+    #[derive(Clone, Default)]
+    struct CountingWriter {
+        data: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+        writes: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        flushes: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl tokio::io::AsyncWrite for CountingWriter {
+        fn poll_write(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.writes
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.data.lock().unwrap().extend_from_slice(buf);
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            self.flushes
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_coalesces_into_single_write() {
+        use std::sync::atomic::Ordering;
+
+        let counter = CountingWriter::default();
+        let mut writer: Option<BoxWriter> = Some(Box::new(counter.clone()));
+        let mut local = LocalTypeMap::new();
+
+        let mut meta = HttpMetadata::new();
+        meta.status = StatusCode::Ok;
+        meta.headers.insert(HeaderKey::ContentType, "text/plain");
+        meta.headers.insert(HeaderKey::from("X-Trace"), "abc");
+        meta.body = vec![b'x'; 4096];
+        local.set_value(meta);
+
+        Response {
+            writer: &mut writer,
+            local: &mut local,
+        }
+        .send_response()
+        .await
+        .unwrap();
+
+        // 状态行、头部与 body 一次写出并只 flush 一次
+        assert_eq!(counter.writes.load(Ordering::SeqCst), 1);
+        assert_eq!(counter.flushes.load(Ordering::SeqCst), 1);
+        let data = counter.data.lock().unwrap();
+        assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(data.ends_with(&[b'x'; 4096]));
+    }
 }
@@ -1091,4 +1091,1582 @@ mod tests {
 
         assert_eq!(res.status().as_u16(), 200);
     }
+
+    #[tokio::test]
+    async fn test_expect_100_continue_before_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/upload",
+            exe!(|ctx| {
+                let user = ctx.req().form("user").unwrap_or_default();
+                ctx.send(format!("User:{}", user), None);
+                true
+            }),
+        )
+        .register();
+
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let body = "user=Gemini";
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        let head = format!(
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\n\
+             Content-Type: application/x-www-form-urlencoded\r\n\
+             Content-Length: {}\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+
+        // 在发送 body 之前就应该收到中间响应
+        let mut buf = [0u8; 64];
+        let n = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+            .await
+            .expect("server did not send 100 Continue")
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..n]).unwrap(),
+            "HTTP/1.1 100 Continue\r\n\r\n"
+        );
+
+        stream.write_all(body.as_bytes()).await.unwrap();
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .unwrap()
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 200 OK"));
+        assert!(resp.ends_with("User:Gemini"));
+    }
+
+    #[tokio::test]
+    async fn test_expect_100_continue_sent_when_handler_reads_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/echo",
+            exe!(|ctx| {
+                let Ok(body) = ctx.req().read_body().await else {
+                    return false;
+                };
+                ctx.send(String::from_utf8_lossy(&body).to_string(), None);
+                true
+            }),
+        )
+        .register();
+        hr.post(
+            "/ignore",
+            exe!(|ctx| {
+                ctx.send("ignored", None);
+                true
+            }),
+        )
+        .register();
+
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // text/plain 不会被预读：处理器调用 read_body 时才发送 100 Continue
+        let body = "plain text";
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        let head = format!(
+            "POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Type: text/plain\r\n\
+             Content-Length: {}\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+
+        let mut buf = [0u8; 64];
+        let n = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+            .await
+            .expect("server did not send 100 Continue")
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..n]).unwrap(),
+            "HTTP/1.1 100 Continue\r\n\r\n"
+        );
+
+        stream.write_all(body.as_bytes()).await.unwrap();
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .unwrap()
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 200 OK"), "{}", resp);
+        assert!(resp.ends_with("plain text"), "{}", resp);
+
+        // 处理器不读取请求体：不发送 100 Continue，直接返回最终响应并关闭连接
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        let head = format!(
+            "POST /ignore HTTP/1.1\r\nHost: localhost\r\nContent-Type: text/plain\r\n\
+             Content-Length: {}\r\nExpect: 100-continue\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .expect("connection awaiting an unsent body was kept open")
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 200 OK"), "{}", resp);
+        assert!(!resp.contains("100 Continue"), "{}", resp);
+        assert!(resp.ends_with("ignored"), "{}", resp);
+    }
+
+    #[tokio::test]
+    async fn test_payload_too_large_rejected_before_read() {
+        use aex::connection::global::GlobalContext;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/upload",
+            exe!(|ctx| {
+                ctx.send("unreachable", None);
+                true
+            }),
+        )
+        .register();
+
+        let globals = Arc::new(GlobalContext::new(actual_addr, None).with_max_body_size(16));
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 只发送头部：服务端必须在读取请求体之前就返回 413
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        let head = "POST /upload HTTP/1.1\r\nHost: localhost\r\n\
+                    Content-Type: application/x-www-form-urlencoded\r\n\
+                    Content-Length: 1048576\r\n\r\n";
+        stream.write_all(head.as_bytes()).await.unwrap();
+
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .expect("server did not reject oversized body")
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 413 Payload Too Large"));
+        assert!(!resp.contains("unreachable"));
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_limit() {
+        use aex::connection::global::GlobalContext;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/upload",
+            exe!(|ctx| {
+                let body = ctx.req().text().unwrap_or_default();
+                ctx.send(format!("Body:{}", body), None);
+                true
+            }),
+        )
+        .register();
+
+        let globals = Arc::new(GlobalContext::new(actual_addr, None).with_max_body_size(16));
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let head = "POST /upload HTTP/1.1\r\nHost: localhost\r\n\
+                    Transfer-Encoding: chunked\r\n\r\n";
+
+        // 限制内的分块请求体被完整解码
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(format!("{}5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n", head).as_bytes())
+            .await
+            .unwrap();
+        let mut buf = vec![0u8; 1024];
+        let n = stream.read(&mut buf).await.unwrap();
+        let resp = String::from_utf8_lossy(&buf[..n]);
+        assert!(resp.starts_with("HTTP/1.1 200 OK"));
+        assert!(resp.ends_with("Body:hello world"));
+
+        // 超出上限后立即 413 并关闭，不等待剩余分块
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(format!("{}a\r\n0123456789\r\na\r\n0123456789\r\n", head).as_bytes())
+            .await
+            .unwrap();
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .expect("server did not close oversized chunked body")
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 413 Payload Too Large"));
+        assert!(!resp.contains("Body:"));
+    }
+
+    #[test]
+    fn test_param_route_wins_over_wildcard() {
+        use aex::http::params::SmallParams;
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/files/*", exe!(|_ctx| { true })).register();
+        hr.get("/files/:id/meta", exe!(|_ctx| { true })).register();
+        hr.get("/files/latest/meta", exe!(|_ctx| { true }))
+            .register();
+
+        let mut params = SmallParams::new();
+        let node = hr
+            .match_route(&["files", "42", "meta"], &mut params)
+            .unwrap();
+        assert!(matches!(node.node_type, NodeType::Static(ref s) if s == "meta"));
+        assert_eq!(params.get("id"), Some("42"));
+
+        // 静态分支优先，且不留下参数
+        let mut params = SmallParams::new();
+        hr.match_route(&["files", "latest", "meta"], &mut params)
+            .unwrap();
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_wildcard_fallback_after_backtracking() {
+        use aex::http::params::SmallParams;
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/files/*", exe!(|_ctx| { true })).register();
+        hr.get("/files/:id/meta", exe!(|_ctx| { true })).register();
+
+        // 参数分支走不通时回退到通配符，并撤销已绑定的参数
+        for segs in [
+            &["files", "42", "raw"][..],
+            &["files", "42"][..],
+            &["files", "a", "b", "c"][..],
+        ] {
+            let mut params = SmallParams::new();
+            let node = hr.match_route(segs, &mut params).unwrap();
+            assert!(matches!(node.node_type, NodeType::Wildcard), "{:?}", segs);
+            assert!(params.is_empty());
+        }
+
+        assert!(hr.has_route("GET", "/files/42/raw"));
+        assert!(!hr.has_route("GET", "/other"));
+    }
+
+    #[tokio::test]
+    async fn test_form_body_length_guard() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/submit",
+            exe!(|ctx| {
+                let user = ctx.req().form("user").unwrap_or_else(|| "<none>".into());
+                ctx.send(format!("User:{}", user), None);
+                true
+            }),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let client = reqwest::Client::new();
+        // 长度为 0 时不能去读 body（否则会阻塞），长度非 0 时必须读取并解析
+        for (body, expected) in [("", "User:<none>"), ("user=Gemini", "User:Gemini")] {
+            let res = tokio::time::timeout(
+                Duration::from_secs(2),
+                client
+                    .post(format!("http://{}/submit", actual_addr))
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .body(body)
+                    .send(),
+            )
+            .await
+            .expect("request hung")
+            .unwrap();
+
+            assert_eq!(res.status().as_u16(), 200);
+            assert_eq!(res.text().await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_form_body_read_once() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/submit",
+            exe!(|ctx| {
+                // 路由已读取过请求体，这里返回缓存而不是再次读取 socket
+                let raw = ctx.req().read_body().await.unwrap_or_default();
+                let user = ctx.req().form("user").unwrap_or_default();
+                ctx.send(
+                    format!("{}|User:{}", String::from_utf8_lossy(&raw), user),
+                    None,
+                );
+                true
+            }),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let res = tokio::time::timeout(
+            Duration::from_secs(2),
+            reqwest::Client::new()
+                .post(format!("http://{}/submit", actual_addr))
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .body("user=Gemini&age=20")
+                .send(),
+        )
+        .await
+        .expect("request hung")
+        .unwrap();
+
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "user=Gemini&age=20|User:Gemini");
+    }
+
+    #[tokio::test]
+    async fn test_matched_route_pattern() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let route = exe!(|ctx| {
+            let pattern = ctx.req().matched_route().unwrap_or_default();
+            ctx.send(pattern, None);
+            true
+        });
+        hr.get("/users/:id", route.clone()).register();
+        hr.get("/files/*", route.clone()).register();
+        hr.get("/", route).register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        for (path, expected) in [
+            ("/users/42", "/users/:id"),
+            ("/files/a/b.txt", "/files/*"),
+            ("/", "/"),
+        ] {
+            let res = reqwest::get(format!("http://{}{}", actual_addr, path))
+                .await
+                .unwrap();
+            assert_eq!(res.text().await.unwrap(), expected, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_after_hooks_run_on_success_and_interruption() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let after_runs = Arc::new(AtomicUsize::new(0));
+
+        let counter = after_runs.clone();
+        let after: Arc<Executor> = Arc::new(move |ctx: &mut Context| {
+            let c = counter.clone();
+            async move {
+                c.fetch_add(1, Ordering::SeqCst);
+                // 后置阶段仍可修改响应
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    meta.headers.insert(HeaderKey::from("x-after"), "1");
+                }
+                false
+            }
+            .boxed()
+        });
+        let blocker = exe!(|ctx| {
+            ctx.status(StatusCode::Forbidden);
+            false
+        });
+
+        hr.get(
+            "/ok",
+            exe!(|ctx| {
+                ctx.send("ok", None);
+                true
+            }),
+        )
+        .after(after.clone())
+        .register();
+        hr.get("/blocked", exe!(|_ctx| { true }))
+            .middleware(blocker)
+            .after(after)
+            .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::get(format!("http://{}/ok", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.headers().get("x-after").unwrap(), "1");
+        assert_eq!(after_runs.load(Ordering::SeqCst), 1);
+
+        let res = reqwest::get(format!("http://{}/blocked", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 403);
+        assert_eq!(after_runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_body_returns_400() {
+        use aex::connection::global::GlobalContext;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/submit",
+            exe!(|ctx| {
+                ctx.send("unreachable", None);
+                true
+            }),
+        )
+        .register();
+
+        let globals = Arc::new(
+            GlobalContext::new(actual_addr, None)
+                .with_body_read_timeout(Duration::from_millis(300)),
+        );
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let head = "POST /submit HTTP/1.1\r\nHost: localhost\r\n\
+                    Content-Type: application/x-www-form-urlencoded\r\n\
+                    Content-Length: 100\r\n\r\nname=alice";
+
+        // 1. 发送 10 字节后关闭写端
+        // 2. 发送 10 字节后保持连接，依赖读取超时
+        for close_write in [true, false] {
+            let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+            stream.write_all(head.as_bytes()).await.unwrap();
+            if close_write {
+                stream.shutdown().await.unwrap();
+            }
+
+            let mut resp = Vec::new();
+            tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+                .await
+                .expect("server hung on incomplete body")
+                .unwrap();
+            let resp = String::from_utf8_lossy(&resp);
+            assert!(resp.starts_with("HTTP/1.1 400 Bad Request"), "{}", resp);
+            assert!(resp.contains("Incomplete body"));
+            assert!(!resp.contains("unreachable"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unread_body_is_not_parsed_as_next_request() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/",
+            exe!(|ctx| {
+                ctx.send("hit /", None);
+                true
+            }),
+        )
+        .register();
+        hr.get(
+            "/admin",
+            exe!(|ctx| {
+                ctx.send("hit /admin", None);
+                true
+            }),
+        )
+        .register();
+
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 处理器不读取 text/plain 请求体，请求体里夹带的请求不能被执行；
+        // 请求体被丢弃后连接仍可继续处理下一个请求
+        let body = "GET /admin HTTP/1.1\r\nHost: x\r\n\r\n";
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        let req = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: text/plain\r\n\
+             Content-Length: {}\r\n\r\n{}\
+             POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\
+             Connection: close\r\n\r\n",
+            body.len(),
+            body
+        );
+        stream.write_all(req.as_bytes()).await.unwrap();
+
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .expect("server did not finish the pipelined requests")
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 200 OK"), "{}", resp);
+        assert!(!resp.contains("hit /admin"), "{}", resp);
+        assert_eq!(resp.matches("HTTP/1.1 200 OK").count(), 2, "{}", resp);
+        assert_eq!(resp.matches("hit /").count(), 2, "{}", resp);
+    }
+
+    #[tokio::test]
+    async fn test_partially_streamed_body_closes_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post(
+            "/",
+            exe!(|ctx| {
+                let mut head = [0u8; 4];
+                let read = match ctx.req().body_stream(1024) {
+                    Ok(mut stream) => stream.read_exact(&mut head).await.is_ok(),
+                    Err(_) => false,
+                };
+                ctx.send(if read { "partial" } else { "failed" }, None);
+                true
+            }),
+        )
+        .stream_body()
+        .register();
+
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 只读取了一部分的流式请求体无法确定剩余长度，连接必须关闭
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(
+                b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\n0123456789",
+            )
+            .await
+            .unwrap();
+
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .expect("connection with a partially read body was kept open")
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 200 OK"), "{}", resp);
+        assert!(resp.contains("Connection: close"), "{}", resp);
+        assert!(resp.ends_with("partial"), "{}", resp);
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_head_returns_408() {
+        use aex::connection::global::GlobalContext;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/slow",
+            exe!(|ctx| {
+                ctx.send("unreachable", None);
+                true
+            }),
+        )
+        .register();
+
+        let globals = Arc::new(
+            GlobalContext::new(actual_addr, None)
+                .with_header_read_timeout(Duration::from_millis(300))
+                .with_server_header("aex-test"),
+        );
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 请求行只发送一半，之后不再发送
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream.write_all(b"GET /slow HT").await.unwrap();
+
+        let mut resp = Vec::new();
+        tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+            .await
+            .expect("server hung on slow request head")
+            .unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 408 Request Timeout"), "{}", resp);
+        assert!(resp.contains("Server: aex-test\r\n"), "{}", resp);
+        assert!(!resp.contains("unreachable"));
+
+        // 空闲连接不计时：首字节到达后才开始计算期限
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        sleep(Duration::from_millis(500)).await;
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut resp = Vec::new();
+        stream.read_to_end(&mut resp).await.unwrap();
+        assert!(String::from_utf8_lossy(&resp).starts_with("HTTP/1.1 200"));
+    }
+
+    #[tokio::test]
+    async fn test_handler_panic_returns_500() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/boom",
+            exe!(|_ctx| {
+                panic!("boom");
+            }),
+        )
+        .register();
+        hr.get(
+            "/ok",
+            exe!(|ctx| {
+                ctx.send("still alive", None);
+                true
+            }),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::get(format!("http://{}/boom", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 500);
+        assert_eq!(res.text().await.unwrap(), "Internal Server Error");
+
+        // 服务器仍然可用
+        let res = reqwest::get(format!("http://{}/ok", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "still alive");
+    }
+
+    #[tokio::test]
+    async fn test_after_hooks_run_when_handler_panics() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let after_status = Arc::new(AtomicUsize::new(0));
+
+        let seen = after_status.clone();
+        let after: Arc<Executor> = Arc::new(move |ctx: &mut Context| {
+            let seen = seen.clone();
+            async move {
+                // 后置执行器看到的是 panic 转换后的 500
+                if let Some(meta) = ctx.local.get_mut::<HttpMetadata>() {
+                    seen.store(meta.status.as_u16() as usize, Ordering::SeqCst);
+                    meta.headers.insert(HeaderKey::from("x-after"), "1");
+                }
+                true
+            }
+            .boxed()
+        });
+        let panicking_after: Arc<Executor> = Arc::new(|_ctx: &mut Context| {
+            async move {
+                panic!("after boom");
+            }
+            .boxed()
+        });
+
+        hr.get(
+            "/boom",
+            exe!(|_ctx| {
+                panic!("boom");
+            }),
+        )
+        .after(after)
+        .register();
+        hr.get(
+            "/after-boom",
+            exe!(|ctx| {
+                ctx.send("ok", None);
+                true
+            }),
+        )
+        .after(panicking_after)
+        .register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::get(format!("http://{}/boom", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 500);
+        assert_eq!(res.headers().get("x-after").unwrap(), "1");
+        assert_eq!(after_status.load(Ordering::SeqCst), 500);
+
+        // 后置执行器自身 panic 不影响已生成的响应
+        let res = reqwest::get(format!("http://{}/after-boom", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_custom_not_found_and_error_handlers() {
+        use aex::http::protocol::media_type::SubMediaType;
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/boom",
+            exe!(|_ctx| {
+                panic!("boom");
+            }),
+        )
+        .register();
+        hr.set_not_found(exe!(|ctx| {
+            let path = ctx.local.get_ref::<HttpMetadata>().unwrap().path.clone();
+            ctx.send(
+                format!(r#"{{"error":"not found","path":"{}"}}"#, path),
+                Some(SubMediaType::Json),
+            );
+            true
+        }))
+        .set_error_handler(exe!(|ctx| {
+            ctx.send(r#"{"error":"internal"}"#, Some(SubMediaType::Json));
+            true
+        }));
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let res = reqwest::get(format!("http://{}/missing", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 404);
+        assert!(
+            res.headers()["content-type"]
+                .to_str()
+                .unwrap()
+                .contains("json")
+        );
+        assert_eq!(
+            res.text().await.unwrap(),
+            r#"{"error":"not found","path":"/missing"}"#
+        );
+
+        let res = reqwest::get(format!("http://{}/boom", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 500);
+        assert_eq!(res.text().await.unwrap(), r#"{"error":"internal"}"#);
+    }
+
+    #[test]
+    fn test_param_constraints() {
+        use aex::http::params::SmallParams;
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/user/:id(\\d+)", exe!(|_ctx| { true })).register();
+        hr.get("/user/me", exe!(|_ctx| { true })).register();
+        hr.get("/post/:slug([a-z-]+)/edit", exe!(|_ctx| { true }))
+            .register();
+        hr.get("/post/*", exe!(|_ctx| { true })).register();
+
+        let mut params = SmallParams::new();
+        let node = hr.match_route(&["user", "42"], &mut params).unwrap();
+        assert!(node.node_type.is_param());
+        assert_eq!(params.get("id"), Some("42"));
+
+        assert!(hr.has_route("GET", "/user/42"));
+        assert!(hr.has_route("GET", "/user/me"));
+        // 约束按整段匹配
+        assert!(!hr.has_route("GET", "/user/abc"));
+        assert!(!hr.has_route("GET", "/user/42abc"));
+
+        // 约束不满足时回退到通配符
+        let mut params = SmallParams::new();
+        let node = hr
+            .match_route(&["post", "Hello_World", "edit"], &mut params)
+            .unwrap();
+        assert!(node.node_type.is_wildcard());
+        assert!(params.is_empty());
+
+        let mut params = SmallParams::new();
+        hr.match_route(&["post", "hello-world", "edit"], &mut params)
+            .unwrap();
+        assert_eq!(params.get("slug"), Some("hello-world"));
+    }
+
+    #[test]
+    fn test_routes_introspection() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/", exe!(|_ctx| { true })).register();
+        hr.get("/users/:id(\\d+)", exe!(|_ctx| { true }))
+            .middleware(exe!(|_ctx| { true }))
+            .register();
+        hr.delete("/users/:id(\\d+)", exe!(|_ctx| { true }))
+            .register();
+        hr.post("/users", exe!(|_ctx| { true })).register();
+        hr.get("/static/*", exe!(|_ctx| { true })).register();
+        hr.all("/health", exe!(|_ctx| { true })).register();
+
+        let routes = hr.routes();
+        assert_eq!(
+            routes,
+            vec![
+                ("/".to_string(), "GET".to_string(), false),
+                ("/health".to_string(), "*".to_string(), false),
+                ("/static/*".to_string(), "GET".to_string(), false),
+                ("/users".to_string(), "POST".to_string(), false),
+                ("/users/:id(\\d+)".to_string(), "DELETE".to_string(), false),
+                ("/users/:id(\\d+)".to_string(), "GET".to_string(), true),
+            ]
+        );
+
+        // 还原的模板可以再次注册并命中同一个节点
+        let mut copy = Router::new(NodeType::Static("root".into()));
+        for (pattern, method, _) in &routes {
+            copy.insert(pattern, Some(method), exe!(|_ctx| { true }), None);
+        }
+        assert_eq!(copy.routes().len(), routes.len());
+        assert!(copy.has_route("DELETE", "/users/42"));
+        assert!(!copy.has_route("DELETE", "/users/abc"));
+    }
+
+    #[test]
+    fn test_route_conflicts() {
+        use aex::http::router::RouteConflict;
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.try_insert("/users/:id", Some("GET"), exe!(|_ctx| { true }), None)
+            .unwrap();
+        // 同一路径的其它方法不算冲突
+        hr.try_insert("/users/:id", Some("post"), exe!(|_ctx| { true }), None)
+            .unwrap();
+
+        let err = hr
+            .try_insert("/users/:id/", Some("get"), exe!(|_ctx| { true }), None)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RouteConflict::Duplicate {
+                pattern: "/users/:id".into(),
+                method: "GET".into()
+            }
+        );
+
+        let err = hr
+            .try_insert(
+                "/users/:uid/posts",
+                Some("GET"),
+                exe!(|_ctx| { true }),
+                None,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(err, RouteConflict::ParamMismatch { ref existing, .. } if existing == ":id")
+        );
+
+        let err = hr
+            .try_insert("/users/*", Some("GET"), exe!(|_ctx| { true }), None)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RouteConflict::Ambiguous {
+                pattern: "/users/*".into(),
+                other: "/users/:id".into()
+            }
+        );
+
+        hr.try_insert("/files/*", Some("GET"), exe!(|_ctx| { true }), None)
+            .unwrap();
+        assert!(matches!(
+            hr.check_conflict("/files/:name", Some("GET")),
+            Err(RouteConflict::Ambiguous { .. })
+        ));
+        // 更深的参数路由与通配符不会匹配同一路径
+        assert!(hr.check_conflict("/files/:name/raw", Some("GET")).is_ok());
+
+        // 被拒绝的注册不会修改路由表
+        assert_eq!(hr.routes().len(), 3);
+
+        // insert 仍然覆盖，只记录警告
+        hr.insert("/users/:id", Some("GET"), exe!(|_ctx| { false }), None);
+        assert_eq!(hr.routes().len(), 3);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(Router::normalize_path("/a//b/").unwrap(), vec!["a", "b"]);
+        assert_eq!(Router::normalize_path("/a/./b").unwrap(), vec!["a", "b"]);
+        assert_eq!(Router::normalize_path("/a/x/../b").unwrap(), vec!["a", "b"]);
+        assert!(Router::normalize_path("/").unwrap().is_empty());
+        // 越过根目录被拒绝
+        assert!(Router::normalize_path("/..").is_none());
+        assert!(Router::normalize_path("/a/../../etc/passwd").is_none());
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/a/b", exe!(|_ctx| { true })).register();
+        assert!(hr.has_route("GET", "//a///b"));
+        assert!(hr.has_route("GET", "/a/c/../b?x=1"));
+        assert!(!hr.has_route("GET", "/../a/b"));
+    }
+
+    #[tokio::test]
+    async fn test_dot_segments_routed() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/files/:name",
+            exe!(|ctx| {
+                let name = ctx.req().param("name").unwrap_or_default();
+                ctx.send(format!("file:{}", name), None);
+                true
+            }),
+        )
+        .register();
+
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 原样发送请求行，避免客户端预先规范化路径
+        async fn get(addr: SocketAddr, path: &str) -> String {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(
+                    format!(
+                        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                        path
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            String::from_utf8_lossy(&buf).to_string()
+        }
+
+        let resp = get(actual_addr, "//files//a.txt").await;
+        assert!(resp.starts_with("HTTP/1.1 200 OK"));
+        assert!(resp.ends_with("file:a.txt"));
+
+        let resp = get(actual_addr, "/files/x/../b.txt").await;
+        assert!(resp.ends_with("file:b.txt"));
+
+        let resp = get(actual_addr, "/files/../../etc/passwd").await;
+        assert!(resp.starts_with("HTTP/1.1 400 Bad Request"));
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_warning() {
+        use aex::connection::global::GlobalContext;
+
+        /// 把日志写入内存，供断言使用
+        #[derive(Clone, Default)]
+        struct Capture(Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let logs = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        // 单线程运行时，服务器任务与测试在同一线程，均使用该 subscriber
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/fast", exe!(|_ctx| { true })).register();
+        hr.get(
+            "/slow/:id",
+            exe!(|_ctx| {
+                sleep(Duration::from_millis(120)).await;
+                true
+            }),
+        )
+        .register();
+
+        let globals = Arc::new(
+            GlobalContext::new(actual_addr, None)
+                .with_slow_request_threshold(Duration::from_millis(50)),
+        );
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let client = reqwest::Client::new();
+        for path in ["/fast", "/slow/7"] {
+            let res = client
+                .get(format!("http://{}{}", actual_addr, path))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status().as_u16(), 200);
+        }
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let warnings: Vec<&str> = output
+            .lines()
+            .filter(|l| l.contains("slow request"))
+            .collect();
+        assert_eq!(warnings.len(), 1, "{}", output);
+        let line = warnings[0];
+        assert!(line.contains("WARN"));
+        assert!(line.contains("method=\"GET\""), "{}", line);
+        assert!(line.contains("path=/slow/7"), "{}", line);
+        assert!(line.contains("route=\"/slow/:id\""), "{}", line);
+        assert!(line.contains("elapsed_ms="), "{}", line);
+    }
+
+    #[tokio::test]
+    async fn test_server_and_security_headers() {
+        use aex::connection::global::GlobalContext;
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/plain", exe!(|_ctx| { true })).register();
+        hr.get(
+            "/embed",
+            exe!(|ctx| {
+                ctx.res().set_header("X-Frame-Options", "SAMEORIGIN");
+                true
+            }),
+        )
+        .register();
+        hr.get(
+            "/stream",
+            exe!(|ctx| {
+                ctx.res().set_header("X-Frame-Options", "SAMEORIGIN");
+                let Ok(mut w) = ctx.res().stream().await else {
+                    return false;
+                };
+                w.send("streamed").await.is_ok() && w.finish().await.is_ok()
+            }),
+        )
+        .register();
+
+        let globals = Arc::new(
+            GlobalContext::new(actual_addr, None)
+                .with_server_header("aex-test")
+                .with_security_headers(true),
+        );
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let client = reqwest::Client::new();
+        let res = client
+            .get(format!("http://{}/plain", actual_addr))
+            .send()
+            .await
+            .unwrap();
+        let headers = res.headers();
+        assert_eq!(headers["server"], "aex-test");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["x-frame-options"], "DENY");
+        assert_eq!(
+            headers["referrer-policy"],
+            "strict-origin-when-cross-origin"
+        );
+
+        // 404 同样带上；处理器设置的值不被覆盖
+        let res = client
+            .get(format!("http://{}/missing", actual_addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 404);
+        assert_eq!(res.headers()["server"], "aex-test");
+        let res = client
+            .get(format!("http://{}/embed", actual_addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.headers()["x-frame-options"], "SAMEORIGIN");
+
+        // 处理器自行提交的流式响应同样带上，且不重复
+        let res = client
+            .get(format!("http://{}/stream", actual_addr))
+            .send()
+            .await
+            .unwrap();
+        let headers = res.headers();
+        assert_eq!(headers["transfer-encoding"], "chunked");
+        assert_eq!(headers["server"], "aex-test");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers.get_all("x-frame-options").iter().count(), 1);
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(res.text().await.unwrap(), "streamed");
+
+        // 默认配置不发送
+        let plain = GlobalContext::new(actual_addr, None);
+        assert!(plain.server_header.is_none());
+        assert!(!plain.security_headers);
+    }
+
+    #[tokio::test]
+    async fn test_handler_observes_cancellation() {
+        use aex::connection::global::GlobalContext;
+        use tokio::io::AsyncWriteExt;
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<&'static str>();
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/slow",
+            exe!(
+                move |ctx, tx| {
+                    tokio::select! {
+                        _ = ctx.cancelled() => {
+                            let _ = tx.send("cancelled");
+                            ctx.status(StatusCode::ServiceUnavailable).send("cancelled", None);
+                        }
+                        _ = sleep(Duration::from_secs(5)) => {
+                            let _ = tx.send("finished");
+                        }
+                    }
+                    true
+                },
+                |ctx| { tx.clone() }
+            ),
+        )
+        .register();
+
+        let globals = Arc::new(
+            GlobalContext::new(actual_addr, None).with_request_timeout(Duration::from_millis(800)),
+        );
+        let server = HTTPServer::new(actual_addr, Some(globals)).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 客户端在处理器运行中断开
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(50)).await;
+        let started = std::time::Instant::now();
+        drop(stream);
+        let seen = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("handler did not observe the disconnect");
+        assert_eq!(seen, Some("cancelled"));
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        // 请求超时同样触发取消
+        let res = reqwest::get(format!("http://{}/slow", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 503);
+        assert_eq!(rx.recv().await, Some("cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_global_middleware_runs_before_route_middleware() {
+        let order = Arc::new(std::sync::Mutex::new(Vec::<&'static str>::new()));
+        let step = |name: &'static str, pass: bool| -> Arc<Executor> {
+            let order = order.clone();
+            Arc::new(move |_ctx: &mut Context| {
+                order.lock().unwrap().push(name);
+                async move { pass }.boxed()
+            })
+        };
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/a", step("HANDLER", true))
+            .middleware(step("ROUTE", true))
+            .register();
+        hr.get("/b", step("HANDLER", true)).register();
+        // 路由注册之后添加的全局中间件同样生效
+        hr.use_global(step("GLOBAL_1", true))
+            .use_global(step("GLOBAL_2", true));
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let take = |path: &str| {
+            let path = path.to_string();
+            let order = order.clone();
+            async move {
+                let res = reqwest::get(format!("http://{}{}", actual_addr, path))
+                    .await
+                    .unwrap();
+                (
+                    res.status().as_u16(),
+                    std::mem::take(&mut *order.lock().unwrap()),
+                )
+            }
+        };
+
+        assert_eq!(
+            take("/a").await,
+            (200, vec!["GLOBAL_1", "GLOBAL_2", "ROUTE", "HANDLER"])
+        );
+        assert_eq!(
+            take("/b").await,
+            (200, vec!["GLOBAL_1", "GLOBAL_2", "HANDLER"])
+        );
+        // 未命中路由时不执行
+        assert_eq!(take("/missing").await, (404, vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_request_body() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let count = exe!(|ctx| {
+            let copied = match ctx.req().body_stream(4 * 1024 * 1024) {
+                Ok(mut body) => tokio::io::copy(&mut body, &mut tokio::io::sink()).await,
+                Err(e) => Err(std::io::Error::other(e)),
+            };
+            match copied {
+                Ok(n) => ctx.send(n.to_string(), None),
+                Err(_) => {
+                    ctx.status(StatusCode::PayloadTooLarge);
+                }
+            };
+            true
+        });
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.post("/upload", count).stream_body().register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        // 超过默认 max_body_size（1MB）的请求体不会被预读拒绝
+        let client = reqwest::Client::new();
+        let res = client
+            .post(format!("http://{}/upload", actual_addr))
+            .body(vec![b'x'; 3 * 1024 * 1024])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(res.text().await.unwrap(), (3 * 1024 * 1024).to_string());
+
+        let res = client
+            .post(format!("http://{}/upload", actual_addr))
+            .body(vec![b'x'; 5 * 1024 * 1024])
+            .send()
+            .await;
+        if let Ok(res) = res {
+            assert_eq!(res.status().as_u16(), 413);
+        }
+
+        // 分块请求体边读边解码
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(
+                b"POST /upload HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n\
+                  5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut buf = vec![0u8; 1024];
+        let n = stream.read(&mut buf).await.unwrap();
+        let res = String::from_utf8_lossy(&buf[..n]);
+        assert!(res.starts_with("HTTP/1.1 200"), "{}", res);
+        assert!(res.ends_with("11"), "{}", res);
+    }
+
+    #[test]
+    fn test_has_route_ignores_fragment() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/docs", exe!(|_ctx| { true })).register();
+
+        assert!(hr.has_route("GET", "/docs#intro"));
+        assert!(hr.has_route("GET", "/docs?a=1?b#x"));
+        assert!(!hr.has_route("GET", "/docs/more#x"));
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_to_plain_route_rejected() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let called = Arc::new(AtomicUsize::new(0));
+        let counter = called.clone();
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        let handler: Arc<Executor> = Arc::new(move |ctx: &mut Context| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                ctx.send("plain", None);
+                true
+            }
+            .boxed()
+        });
+        hr.get("/plain", handler).register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /plain HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        // 连接会被复用，半关闭写端让服务端在响应后断开
+        stream.shutdown().await.unwrap();
+        let mut resp = Vec::new();
+        stream.read_to_end(&mut resp).await.unwrap();
+        let resp = String::from_utf8_lossy(&resp);
+        assert!(resp.starts_with("HTTP/1.1 400"), "{}", resp);
+        assert!(!resp.contains("plain\r\n") && !resp.ends_with("plain"));
+        assert_eq!(called.load(Ordering::SeqCst), 0);
+
+        // 普通 GET 不受影响
+        let res = reqwest::get(format!("http://{}/plain", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "plain");
+        assert_eq!(called.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_header_section_returns_431() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/",
+            exe!(|ctx| {
+                ctx.send("unreachable", None);
+                true
+            }),
+        )
+        .register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+
+        let send = |extra: String| async move {
+            let mut stream = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+            let req = format!("GET / HTTP/1.1\r\nHost: localhost\r\n{}\r\n", extra);
+            let _ = stream.write_all(req.as_bytes()).await;
+            let mut resp = Vec::new();
+            let _ = tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+                .await
+                .expect("server hung on oversized headers");
+            String::from_utf8_lossy(&resp).to_string()
+        };
+
+        // 大量短小的头部：数量超限
+        let many: String = (0..500).map(|i| format!("X-H{}: v\r\n", i)).collect();
+        let resp = send(many).await;
+        assert!(resp.starts_with("HTTP/1.1 431"), "{}", resp);
+        assert!(!resp.contains("unreachable"));
+
+        // 单个超长头部：总字节数超限
+        let resp = send(format!("X-Big: {}\r\n", "a".repeat(16 * 1024))).await;
+        assert!(resp.starts_with("HTTP/1.1 431"), "{}", resp);
+
+        // 上限以内的请求正常处理
+        let few: String = (0..10).map(|i| format!("X-H{}: v\r\n", i)).collect();
+        let resp = send(format!("{}Connection: close\r\n", few)).await;
+        assert!(resp.starts_with("HTTP/1.1 200"), "{}", resp);
+    }
 }
@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, time::Duration};
+
+    use aex::{
+        exe,
+        http::{
+            router::{NodeType, Router},
+            sse::SseEvent,
+        },
+        server::HTTPServer,
+    };
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_sse_event_encoding() {
+        let event = SseEvent::new("line1\nline2")
+            .event("update")
+            .id("7")
+            .retry(3000);
+        assert_eq!(
+            String::from_utf8(event.to_bytes()).unwrap(),
+            "event: update\nid: 7\nretry: 3000\ndata: line1\ndata: line2\n\n"
+        );
+        assert_eq!(SseEvent::new("hi").to_bytes(), b"data: hi\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream_delivers_events() {
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/events",
+            exe!(|ctx| {
+                let (tx, rx) = mpsc::channel(4);
+                tokio::spawn(async move {
+                    let _ = tx.send(SseEvent::new("first").event("tick")).await;
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    let _ = tx.send(SseEvent::new("second").event("tick")).await;
+                });
+                match ctx.res().sse().await {
+                    Ok(stream) => stream.run(rx).await.is_ok(),
+                    Err(_) => false,
+                }
+            }),
+        )
+        .register();
+
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let actual_addr = tokio::net::TcpListener::bind(addr)
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut res = reqwest::get(format!("http://{}/events", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(res.headers().get("cache-control").unwrap(), "no-cache");
+
+        // 每个事件都是单独 flush 的分块
+        let first = res.chunk().await.unwrap().unwrap();
+        assert_eq!(&first[..], b"event: tick\ndata: first\n\n");
+        let second = res.chunk().await.unwrap().unwrap();
+        assert_eq!(&second[..], b"event: tick\ndata: second\n\n");
+
+        // 发送端关闭后流结束
+        let end = tokio::time::timeout(Duration::from_secs(2), res.chunk())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(end.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_stops_when_client_disconnects() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel::<(&'static str, usize)>();
+        let sse_done = done_tx.clone();
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get(
+            "/ticks",
+            exe!(
+                move |ctx, done_tx| {
+                    ctx.watch_disconnect();
+                    let Ok(mut w) = ctx.res().stream().await else {
+                        return false;
+                    };
+                    // 慢速生产者：每 200ms 一条，只靠写失败很难及时发现断开
+                    let mut sent = 0;
+                    loop {
+                        if w.send(format!("tick {}\n", sent)).await.is_err() {
+                            break;
+                        }
+                        sent += 1;
+                        tokio::select! {
+                            _ = tokio::time::sleep(Duration::from_millis(200)) => {}
+                            _ = w.disconnected() => {}
+                        }
+                    }
+                    let _ = done_tx.send(("stream", sent));
+                    false
+                },
+                |ctx| { done_tx.clone() }
+            ),
+        )
+        .register();
+        hr.get(
+            "/events",
+            exe!(
+                move |ctx, sse_done| {
+                    ctx.watch_disconnect();
+                    // 发送端一直存在但不产生事件
+                    let (tx, rx) = mpsc::channel::<SseEvent>(1);
+                    let Ok(stream) = ctx.res().sse().await else {
+                        return false;
+                    };
+                    let result = stream.run(rx).await;
+                    drop(tx);
+                    let _ = sse_done.send(("sse", result.is_err() as usize));
+                    false
+                },
+                |ctx| { sse_done.clone() }
+            ),
+        )
+        .register();
+
+        let actual_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = HTTPServer::new(actual_addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        for (path, expected) in [("/ticks", "stream"), ("/events", "sse")] {
+            let mut client = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+            client
+                .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+                .await
+                .unwrap();
+            let mut buf = [0u8; 256];
+            assert!(client.read(&mut buf).await.unwrap() > 0);
+            drop(client);
+
+            let started = std::time::Instant::now();
+            let (kind, n) = tokio::time::timeout(Duration::from_secs(2), done_rx.recv())
+                .await
+                .expect("handler kept running after disconnect")
+                .unwrap();
+            assert_eq!(kind, expected);
+            assert!(n >= 1);
+            assert!(
+                started.elapsed() < Duration::from_millis(150),
+                "{:?}",
+                started.elapsed()
+            );
+        }
+    }
+}
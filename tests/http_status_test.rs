@@ -119,4 +119,31 @@ mod tests {
             assert!(!status.to_str().is_empty());
         }
     }
+
+    #[test]
+    fn test_as_u16_roundtrip() {
+        for code in [100, 200, 204, 301, 304, 400, 401, 404, 413, 429, 500, 503] {
+            let status = StatusCode::from_u16(code).unwrap();
+            assert_eq!(status.as_u16(), code);
+            assert_eq!(StatusCode::from_u16(status.as_u16()), Some(status));
+        }
+    }
+
+    #[test]
+    fn test_reason_phrase() {
+        let cases = [
+            (StatusCode::Continue, "Continue"),
+            (StatusCode::Ok, "OK"),
+            (StatusCode::NoContent, "No Content"),
+            (StatusCode::MovedPermanently, "Moved Permanently"),
+            (StatusCode::NotFound, "Not Found"),
+            (StatusCode::PayloadTooLarge, "Payload Too Large"),
+            (StatusCode::TooManyRequests, "Too Many Requests"),
+            (StatusCode::InternalServerError, "Internal Server Error"),
+            (StatusCode::ServiceUnavailable, "Service Unavailable"),
+        ];
+        for (status, phrase) in cases {
+            assert_eq!(status.reason_phrase(), phrase);
+        }
+    }
 }
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+    use aex::{
+        exe,
+        http::{
+            middlewares::websocket::{WebSocket, WebSocketSender},
+            router::{NodeType, Router},
+            testing::{WsClient, frame, handshake_request},
+            types::Executor,
+            websocket::WSFrame,
+        },
+        server::HTTPServer,
+    };
+    use tokio::time::sleep;
+
+    async fn echo_server() -> SocketAddr {
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let ws = WebSocket::new().on_text(|_ws, ctx, text| {
+            if let Some(sender) = WebSocketSender::from_ctx(ctx) {
+                sender.send_text(format!("echo:{}", text));
+            }
+            Box::pin(async { true })
+        });
+        let mw: Arc<Executor> = Arc::from(WebSocket::to_middleware(ws));
+
+        let mut hr = Router::new(NodeType::Static("root".into()));
+        hr.get("/ws", exe!(|_ctx| { true }))
+            .middleware(mw)
+            .register();
+
+        let server = HTTPServer::new(addr, None).http(hr).clone();
+        tokio::spawn(async move {
+            let _ = server.start().await;
+        });
+        sleep(Duration::from_millis(200)).await;
+        addr
+    }
+
+    #[test]
+    fn test_frame_encoding() {
+        assert_eq!(frame(0x1, b"hi", false), vec![0x81, 0x02, b'h', b'i']);
+
+        let masked = frame(0x2, &[0u8; 200], true);
+        assert_eq!(&masked[..4], &[0x82, 0x80 | 126, 0x00, 200]);
+        assert_eq!(masked.len(), 4 + 4 + 200);
+
+        let req = handshake_request("/chat");
+        assert!(req.starts_with("GET /chat HTTP/1.1\r\n"));
+        assert!(req.ends_with("\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_ws_client_echo() {
+        let addr = echo_server().await;
+        let mut client = WsClient::connect(addr, "/ws").await.unwrap();
+
+        client.send_text("hello").await.unwrap();
+        assert_eq!(
+            client.recv_text().await.unwrap().as_deref(),
+            Some("echo:hello")
+        );
+
+        client.send_ping(b"p").await.unwrap();
+        assert_eq!(
+            client.recv().await.unwrap(),
+            Some(WSFrame::Pong(b"p".to_vec()))
+        );
+
+        client.close(1000).await.unwrap();
+    }
+}
@@ -315,4 +315,150 @@ mod tests {
         assert_eq!(dst[1], 0x7E); // 16-bit length indicator
         assert_eq!(&dst[2..4], &200u16.to_be_bytes());
     }
+
+    #[test]
+    fn test_encode_fragmented() {
+        let mut codec = WSCodec {};
+        let mut dst = BytesMut::new();
+        codec
+            .encode(WSFrame::Fragmented(0x1, 3, b"hello".to_vec()), &mut dst)
+            .unwrap();
+        assert_eq!(
+            &dst[..],
+            &[0x01, 3, b'h', b'e', b'l', 0x80, 2, b'l', b'o'][..]
+        );
+
+        // 不超过分片大小时就是普通单帧
+        let mut dst = BytesMut::new();
+        codec
+            .encode(WSFrame::Fragmented(0x2, 8, vec![7, 7]), &mut dst)
+            .unwrap();
+        assert_eq!(&dst[..], &[0x82, 2, 7, 7][..]);
+
+        assert_eq!(WSFrame::Fragmented(0x2, 8, vec![7, 7]).payload_len(), 2);
+    }
+
+    #[test]
+    fn test_deflate_context_takeover() {
+        use aex::http::websocket::{MessageDeflater, MessageInflater};
+
+        let msg = br#"{"type":"tick","symbol":"AEX","price":1234.5,"volume":42}"#;
+
+        // 保留上下文：第二条相同消息直接引用窗口中的内容，明显更短
+        let mut deflater = MessageDeflater::new(false);
+        let first = deflater.compress(msg).unwrap();
+        let second = deflater.compress(msg).unwrap();
+        assert!(second.len() < first.len());
+
+        // 不保留上下文：每条消息独立压缩，输出完全一致
+        let mut fresh = MessageDeflater::new(true);
+        let a = fresh.compress(msg).unwrap();
+        let b = fresh.compress(msg).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, first);
+
+        // 解压端必须使用相同的 takeover 设置
+        let mut inflater = MessageInflater::new(false);
+        assert_eq!(inflater.decompress(&first, 1024).unwrap(), msg);
+        assert_eq!(inflater.decompress(&second, 1024).unwrap(), msg);
+
+        let mut inflater = MessageInflater::new(true);
+        assert_eq!(inflater.decompress(&a, 1024).unwrap(), msg);
+        assert_eq!(inflater.decompress(&b, 1024).unwrap(), msg);
+
+        // 超出上限以 1009 拒绝
+        let mut inflater = MessageInflater::new(true);
+        assert_eq!(inflater.decompress(&a, 8).unwrap_err().code, 1009);
+    }
+
+    #[test]
+    fn test_permessage_deflate_negotiation() {
+        use aex::http::websocket::PerMessageDeflate;
+
+        let server = PerMessageDeflate::default();
+        let agreed = server
+            .negotiate("permessage-deflate; client_max_window_bits")
+            .unwrap();
+        assert_eq!(agreed, PerMessageDeflate::default());
+        assert_eq!(agreed.response_header(), "permessage-deflate");
+
+        let agreed = server
+            .negotiate("permessage-deflate; server_no_context_takeover; client_no_context_takeover")
+            .unwrap();
+        assert!(agreed.server_no_context_takeover && agreed.client_no_context_takeover);
+        assert_eq!(
+            agreed.response_header(),
+            "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+        );
+
+        // 服务端配置总会生效
+        let forced = PerMessageDeflate {
+            server_no_context_takeover: true,
+            client_no_context_takeover: false,
+        };
+        assert!(
+            forced
+                .negotiate("permessage-deflate")
+                .unwrap()
+                .server_no_context_takeover
+        );
+
+        // 不支持的窗口大小、未知或重复参数的提议被跳过，回退到下一个提议
+        assert_eq!(
+            server.negotiate("permessage-deflate; server_max_window_bits=10, permessage-deflate"),
+            Some(PerMessageDeflate::default())
+        );
+        assert_eq!(server.negotiate("permessage-deflate; foo"), None);
+        assert_eq!(
+            server.negotiate(
+                "permessage-deflate; server_no_context_takeover; server_no_context_takeover"
+            ),
+            None
+        );
+        assert_eq!(server.negotiate("x-webkit-deflate-frame"), None);
+    }
+
+    #[test]
+    fn test_codec_compressed_frames() {
+        let mut codec = WSCodec;
+        let mut dst = BytesMut::new();
+        codec
+            .encode(WSFrame::Compressed(0x1, vec![1, 2, 3]), &mut dst)
+            .unwrap();
+        // FIN + RSV1 + 文本
+        assert_eq!(&dst[..], &[0xc1, 3, 1, 2, 3][..]);
+
+        // RSV1 帧不做 UTF-8 校验，原样交给读循环解压
+        let mut src = BytesMut::from(&[0xc1u8, 2, 0xff, 0xfe][..]);
+        assert_eq!(
+            codec.decode(&mut src).unwrap(),
+            Some(WSFrame::Compressed(0x1, vec![0xff, 0xfe]))
+        );
+    }
+
+    #[test]
+    fn test_bounded_codec_rejects_oversized_frame_before_buffering() {
+        use aex::http::websocket::{BoundedWSCodec, WSCloseError};
+
+        let close_code = |err: anyhow::Error| err.downcast_ref::<WSCloseError>().unwrap().code;
+
+        // 只有帧头、负载尚未到达：声明的长度超过上限时立即拒绝
+        let mut codec = BoundedWSCodec::new(1024);
+        let mut src = BytesMut::from(&[0x82, 0x7e, 0x04, 0x01][..]);
+        assert_eq!(close_code(codec.decode(&mut src).unwrap_err()), 1009);
+
+        let mut src = BytesMut::from(&[0x82, 0x7f][..]);
+        src.extend_from_slice(&(1u64 << 40).to_be_bytes());
+        assert_eq!(close_code(codec.decode(&mut src).unwrap_err()), 1009);
+
+        // 上限以内的帧照常等待剩余负载
+        let mut src = BytesMut::from(&[0x82, 0x7e, 0x04, 0x00][..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+
+        // 无上限的 WSCodec 也不会因长度溢出而 panic
+        let mut src = BytesMut::from(&[0x82, 0xff][..]);
+        src.extend_from_slice(&u64::MAX.to_be_bytes());
+        src.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(close_code(WSCodec.decode(&mut src).unwrap_err()), 1009);
+    }
 }
@@ -135,3 +135,340 @@ async fn test_server_communication_bus() {
 
     println!("Server communication bus test passed!");
 }
+
+#[test]
+fn test_server_worker_threads_config() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+    let server = Server::new(addr, None).worker_threads(3);
+    assert_eq!(server.workers(), 3);
+    let rt = server.build_runtime().unwrap();
+    assert_eq!(rt.metrics().num_workers(), 3);
+
+    // 未配置时使用 CPU 核数
+    let server = Server::new(addr, None);
+    let cores = std::thread::available_parallelism().unwrap().get();
+    assert_eq!(server.workers(), cores);
+}
+
+#[tokio::test]
+async fn test_server_reuse_port_listeners() {
+    let temp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let actual_addr = temp_listener.local_addr().unwrap();
+    drop(temp_listener);
+
+    let mut http_router = HttpRouter::default();
+    http_router
+        .get(
+            "/",
+            aex::exe!(|ctx| {
+                ctx.send("ok", None);
+                true
+            }),
+        )
+        .register();
+
+    let server = Server::new(actual_addr, None)
+        .worker_threads(2)
+        .reuse_port(true)
+        .http(http_router);
+    server.start().await.unwrap();
+
+    // 多个监听器共享同一端口，请求都能得到处理
+    for _ in 0..8 {
+        let res = reqwest::get(format!("http://{}/", actual_addr))
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "ok");
+    }
+}
+
+#[tokio::test]
+async fn test_server_start_reports_bind_failure() {
+    // 端口已被占用：start 返回绑定错误，而不是静默成功
+    let occupied = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let actual_addr = occupied.local_addr().unwrap();
+
+    let mut http_router = HttpRouter::default();
+    http_router.get("/", aex::exe!(|_ctx| { true })).register();
+
+    let server = Server::new(actual_addr, None).http(http_router);
+    let err = server.start().await.unwrap_err();
+    let io = err.downcast_ref::<std::io::Error>().unwrap();
+    assert_eq!(io.kind(), std::io::ErrorKind::AddrInUse);
+}
+
+#[tokio::test]
+async fn test_server_max_connections() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let temp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let actual_addr = temp_listener.local_addr().unwrap();
+    drop(temp_listener);
+
+    let mut http_router = HttpRouter::default();
+    http_router
+        .get(
+            "/",
+            aex::exe!(|ctx| {
+                ctx.send("ok", None);
+                true
+            }),
+        )
+        .register();
+
+    let server = Server::new(actual_addr, None)
+        .max_connections(1)
+        .http(http_router);
+    server.start().await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // 第一个连接只发送一半请求，占住唯一的名额
+    let mut first = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+    first.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // 超出上限的连接立即收到 503
+    let mut extra = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+    let mut resp = Vec::new();
+    timeout(Duration::from_secs(2), extra.read_to_end(&mut resp))
+        .await
+        .expect("extra connection was not refused")
+        .unwrap();
+    assert!(String::from_utf8_lossy(&resp).starts_with("HTTP/1.1 503 Service Unavailable"));
+
+    // 第一个连接正常完成，名额释放后新连接可以被处理
+    first
+        .write_all(b"Host: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut resp = Vec::new();
+    timeout(Duration::from_secs(2), first.read_to_end(&mut resp))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&resp).starts_with("HTTP/1.1 200 OK"));
+
+    sleep(Duration::from_millis(50)).await;
+    let res = reqwest::get(format!("http://{}/", actual_addr))
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn test_server_idle_connection_released() {
+    use aex::connection::global::GlobalContext;
+    use tokio::io::AsyncReadExt;
+
+    let temp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let actual_addr = temp_listener.local_addr().unwrap();
+    drop(temp_listener);
+
+    let mut http_router = HttpRouter::default();
+    http_router
+        .get(
+            "/",
+            aex::exe!(|ctx| {
+                ctx.send("ok", None);
+                true
+            }),
+        )
+        .register();
+
+    let globals = Arc::new(
+        GlobalContext::new(actual_addr, None).with_idle_timeout(Duration::from_millis(200)),
+    );
+    let server = Server::new(actual_addr, Some(globals))
+        .max_connections(1)
+        .http(http_router);
+    server.start().await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // 连接后不发送任何数据：空闲超时后直接关闭，不回复 408
+    let mut idle = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+    let mut resp = Vec::new();
+    timeout(Duration::from_secs(2), idle.read_to_end(&mut resp))
+        .await
+        .expect("idle connection was never closed")
+        .unwrap();
+    assert!(resp.is_empty(), "{}", String::from_utf8_lossy(&resp));
+
+    // 名额已释放，新连接可以被处理
+    sleep(Duration::from_millis(50)).await;
+    let res = reqwest::get(format!("http://{}/", actual_addr))
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn test_server_set_keepalive() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (socket, _) = listener.accept().await.unwrap();
+
+    Server::set_keepalive(&socket).unwrap();
+    assert!(socket2::SockRef::from(&socket).keepalive().unwrap());
+}
+
+#[tokio::test]
+async fn test_server_http_and_tcp_share_port() {
+    use aex::tcp::router::Router as TcpRouter;
+    use aex::tcp::types::{Codec, Command, Frame};
+    use bincode::{Decode, Encode};
+    use serde::{Deserialize, Serialize};
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::Mutex;
+
+    #[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone)]
+    struct MockCommand {
+        id: u32,
+        data: Vec<u8>,
+    }
+
+    impl Codec for MockCommand {}
+    impl Command for MockCommand {
+        fn id(&self) -> u32 {
+            self.id
+        }
+        fn data(&self) -> &Vec<u8> {
+            &self.data
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone)]
+    struct MockFrame {
+        command: Vec<u8>,
+    }
+
+    impl Codec for MockFrame {}
+    impl Frame for MockFrame {
+        fn payload(&self) -> Option<Vec<u8>> {
+            Some(self.command.clone())
+        }
+        fn command(&self) -> Option<&Vec<u8>> {
+            Some(&self.command)
+        }
+        fn is_flat(&self) -> bool {
+            false
+        }
+    }
+
+    let temp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let actual_addr = temp_listener.local_addr().unwrap();
+    drop(temp_listener);
+
+    let mut http_router = HttpRouter::default();
+    http_router
+        .get(
+            "/",
+            aex::exe!(|ctx| {
+                ctx.send("http", None);
+                true
+            }),
+        )
+        .register();
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let mut tcp_router = TcpRouter::<MockFrame, MockCommand>::new();
+    tcp_router.on_simple(7, {
+        let received = received.clone();
+        Box::new(move |_ctx, _frame, cmd: MockCommand| {
+            let received = received.clone();
+            async move {
+                received.lock().await.push(cmd.data);
+                Ok(true)
+            }
+            .boxed()
+        })
+    });
+
+    let server = Server::new(actual_addr, None)
+        .http(http_router)
+        .tcp(tcp_router);
+    server
+        .start_with_protocols::<MockFrame, MockCommand>()
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    // HTTP 请求由 HTTP 路由处理
+    let res = reqwest::get(format!("http://{}/", actual_addr))
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "http");
+
+    // 同一端口上的二进制帧交给 TCP 路由
+    let frame = MockFrame {
+        command: Codec::encode(&MockCommand {
+            id: 7,
+            data: b"binary".to_vec(),
+        }),
+    };
+    let mut client = tokio::net::TcpStream::connect(actual_addr).await.unwrap();
+    client.write_all(&Codec::encode(&frame)).await.unwrap();
+    client.shutdown().await.unwrap();
+
+    timeout(Duration::from_secs(2), async {
+        while received.lock().await.is_empty() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("binary frame was not dispatched");
+    assert_eq!(*received.lock().await, vec![b"binary".to_vec()]);
+}
+
+#[tokio::test]
+async fn test_server_configure_socket_nodelay() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (socket, _) = listener.accept().await.unwrap();
+
+    // 默认不修改
+    Server::new(addr, None).configure_socket(&socket).unwrap();
+    assert!(!socket.nodelay().unwrap());
+
+    Server::new(addr, None)
+        .tcp_nodelay(true)
+        .configure_socket(&socket)
+        .unwrap();
+    assert!(socket.nodelay().unwrap());
+}
+
+#[tokio::test]
+async fn test_server_small_io_buffer() {
+    let temp_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let actual_addr = temp_listener.local_addr().unwrap();
+    drop(temp_listener);
+
+    let mut http_router = HttpRouter::default();
+    http_router
+        .get(
+            "/",
+            aex::exe!(|ctx| {
+                ctx.send("x".repeat(64 * 1024), None);
+                true
+            }),
+        )
+        .register();
+
+    // 缓冲区远小于请求头与响应体
+    let server = Server::new(actual_addr, None)
+        .tcp_nodelay(true)
+        .io_buffer_size(16)
+        .http(http_router);
+    server.start().await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/", actual_addr))
+        .header("X-Padding", "p".repeat(512))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap().len(), 64 * 1024);
+}
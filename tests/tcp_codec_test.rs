@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use aex::tcp::{
+        codec::{FrameTooLarge, LengthDelimitedCodec},
+        types::Codec,
+    };
+    use bincode::{Decode, Encode};
+    use bytes::BytesMut;
+    use futures::{SinkExt, StreamExt};
+    use serde::{Deserialize, Serialize};
+    use tokio::io::{AsyncWriteExt, duplex};
+    use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+    #[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq)]
+    struct Ping {
+        seq: u32,
+        note: String,
+    }
+
+    impl Codec for Ping {}
+
+    fn ping(seq: u32) -> Ping {
+        Ping {
+            seq,
+            note: format!("ping-{}", seq),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frame_split_across_reads() {
+        let mut wire = BytesMut::new();
+        let mut codec = LengthDelimitedCodec::<Ping>::new();
+        codec.encode(ping(1), &mut wire).unwrap();
+        codec.encode(ping(2), &mut wire).unwrap();
+        assert_eq!(
+            u32::from_be_bytes(wire[..4].try_into().unwrap()) as usize,
+            Codec::encode(&ping(1)).len()
+        );
+
+        let (mut client, server) = duplex(64);
+        let mut frames = FramedRead::new(server, LengthDelimitedCodec::<Ping>::new());
+
+        // 长度前缀与负载都被拆开发送
+        let (head, rest) = wire.split_at(2);
+        let (mid, tail) = rest.split_at(5);
+        let writer = tokio::spawn({
+            let (head, mid, tail) = (head.to_vec(), mid.to_vec(), tail.to_vec());
+            async move {
+                for part in [head, mid, tail] {
+                    client.write_all(&part).await.unwrap();
+                    client.flush().await.unwrap();
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+
+        assert_eq!(frames.next().await.unwrap().unwrap(), ping(1));
+        assert_eq!(frames.next().await.unwrap().unwrap(), ping(2));
+        writer.await.unwrap();
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_framed_roundtrip() {
+        let (client, server) = duplex(1024);
+        let mut sink = FramedWrite::new(client, LengthDelimitedCodec::<Ping>::new());
+        let mut stream = FramedRead::new(server, LengthDelimitedCodec::<Ping>::new());
+
+        sink.send(ping(7)).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), ping(7));
+    }
+
+    #[test]
+    fn test_oversized_length_rejected() {
+        let mut codec = LengthDelimitedCodec::<Ping>::new().max_frame_length(16);
+
+        // 只有长度前缀也会立即拒绝，不等待负载
+        let mut src = BytesMut::from(&u32::MAX.to_be_bytes()[..]);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<FrameTooLarge>(),
+            Some(&FrameTooLarge {
+                length: u32::MAX as usize,
+                max: 16
+            })
+        );
+
+        let big = Ping {
+            seq: 1,
+            note: "x".repeat(32),
+        };
+        let err = codec.encode(big, &mut BytesMut::new()).unwrap_err();
+        assert!(err.is::<FrameTooLarge>());
+    }
+}
@@ -1,10 +1,210 @@
 #[cfg(test)]
 mod tests {
-    use aex::tcp::router::Router;
+    use std::{net::SocketAddr, sync::Arc};
+
+    use aex::{
+        connection::{context::Context, global::GlobalContext},
+        tcp::{
+            router::{DecodeErrorPolicy, Doer, Router},
+            types::{Codec, Command, Frame},
+        },
+    };
+    use bincode::{Decode, Encode};
+    use futures::FutureExt;
+    use serde::{Deserialize, Serialize};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream, duplex},
+        sync::Mutex,
+    };
+
+    #[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone)]
+    struct MockCommand {
+        _id: u32,
+        data: Vec<u8>,
+    }
+
+    impl Codec for MockCommand {}
+    impl Command for MockCommand {
+        fn id(&self) -> u32 {
+            self._id
+        }
+        fn data(&self) -> &Vec<u8> {
+            &self.data
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone)]
+    struct MockFrame {
+        command: Vec<u8>,
+    }
+
+    impl Codec for MockFrame {}
+    impl Frame for MockFrame {
+        fn payload(&self) -> Option<Vec<u8>> {
+            Some(self.command.clone())
+        }
+        fn command(&self) -> Option<&Vec<u8>> {
+            Some(&self.command)
+        }
+        fn is_flat(&self) -> bool {
+            false
+        }
+    }
+
+    fn frame(id: u32, data: &[u8]) -> MockFrame {
+        let cmd = MockCommand {
+            _id: id,
+            data: data.to_vec(),
+        };
+        MockFrame {
+            command: Codec::encode(&cmd),
+        }
+    }
+
+    fn recorder(tag: &'static str, log: Arc<Mutex<Vec<String>>>) -> Doer<MockFrame, MockCommand> {
+        Box::new(move |_ctx, _frame, cmd: MockCommand| {
+            let log = log.clone();
+            async move {
+                let data = String::from_utf8_lossy(&cmd.data).to_string();
+                log.lock().await.push(format!("{}:{}", tag, data));
+                Ok(true)
+            }
+            .boxed()
+        })
+    }
+
+    /// 模拟一个连接：返回客户端写端与连接上的 Context
+    fn connection() -> (DuplexStream, Arc<Mutex<Context>>) {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let (client, server) = duplex(1024);
+        let ctx = Context::new(
+            Some(Box::new(BufReader::new(server))),
+            None,
+            Arc::new(GlobalContext::new(addr, None)),
+            addr,
+        );
+        (client, Arc::new(Mutex::new(ctx)))
+    }
+
+    /// 声明长度远超上限的帧，无法通过等待更多数据修复
+    const BAD_FRAME: [u8; 8] = [0xff, 0xff, 0, 0, 0, 0, 0, 0];
 
     #[test]
     fn test_router_new() {
         let router = Router::<(), ()>::new();
         assert_eq!(router.handlers.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_dispatch_by_command_id() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let ctx = Arc::new(Mutex::new(Context::new(
+            None,
+            None,
+            Arc::new(GlobalContext::new(addr, None)),
+            addr,
+        )));
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        // 未设置 extractor 时按 Command::id() 分发
+        let mut router = Router::<MockFrame, MockCommand>::new();
+        router.on_simple(1, recorder("login", log.clone()));
+        router.on_simple(2, recorder("move", log.clone()));
+
+        for f in [frame(1, b"alice"), frame(2, b"north"), frame(9, b"noop")] {
+            assert!(router.handle_frame(ctx.clone(), f).await.unwrap());
+        }
+        assert_eq!(*log.lock().await, vec!["login:alice", "move:north"]);
+
+        // 自定义 extractor 优先
+        let mut router = Router::<MockFrame, MockCommand>::new().extractor(|c| c.id() + 1);
+        router.on_simple(2, recorder("shifted", log.clone()));
+        router
+            .handle_frame(ctx.clone(), frame(1, b"x"))
+            .await
+            .unwrap();
+        assert_eq!(log.lock().await.last().unwrap(), "shifted:x");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_frame_closes_only_that_connection() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut router = Router::<MockFrame, MockCommand>::new();
+        router.on_simple(1, recorder("cmd", log.clone()));
+        let router = Arc::new(router);
+
+        // 非法帧：handle 正常返回（关闭该连接），不会 panic 或报错
+        let (mut client, ctx) = connection();
+        let task = tokio::spawn({
+            let router = router.clone();
+            async move { router.handle(ctx).await }
+        });
+        client.write_all(&BAD_FRAME).await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(2), task)
+            .await
+            .expect("connection was not closed")
+            .unwrap()
+            .unwrap();
+
+        // 之后的连接照常处理
+        let (mut client, ctx) = connection();
+        let task = tokio::spawn({
+            let router = router.clone();
+            async move { router.handle(ctx).await }
+        });
+        client
+            .write_all(&Codec::encode(&frame(1, b"ok")))
+            .await
+            .unwrap();
+        drop(client);
+        task.await.unwrap().unwrap();
+        assert_eq!(*log.lock().await, vec!["cmd:ok"]);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_frame_skipped() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut router =
+            Router::<MockFrame, MockCommand>::new().on_decode_error(DecodeErrorPolicy::Skip);
+        router.on_simple(1, recorder("cmd", log.clone()));
+
+        let (mut client, ctx) = connection();
+        let task = tokio::spawn(async move { router.handle(ctx).await });
+
+        client.write_all(&BAD_FRAME).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        // 命令部分无法解码的帧同样被跳过
+        let garbage = MockFrame {
+            command: vec![1, 2],
+        };
+        client.write_all(&Codec::encode(&garbage)).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        client
+            .write_all(&Codec::encode(&frame(1, b"after")))
+            .await
+            .unwrap();
+        drop(client);
+
+        task.await.unwrap().unwrap();
+        assert_eq!(*log.lock().await, vec!["cmd:after"]);
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_closed() {
+        let router = Router::<MockFrame, MockCommand>::new()
+            .idle_timeout(std::time::Duration::from_millis(100));
+
+        // 客户端连上后什么都不发送
+        let (mut client, ctx) = connection();
+        let started = std::time::Instant::now();
+        tokio::spawn(async move { router.handle(ctx).await });
+
+        let mut buf = [0u8; 1];
+        let n = tokio::time::timeout(std::time::Duration::from_secs(2), client.read(&mut buf))
+            .await
+            .expect("idle connection was not closed")
+            .unwrap();
+        assert_eq!(n, 0);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(100));
+    }
 }